@@ -0,0 +1,42 @@
+//! Uses cached methods without `std`, and without `alloc` unless the `alloc` feature is enabled.
+//!
+//! Build it for a target without `std`, e.g. `thumbv7em-none-eabihf`, so that a reference to `std`
+//! in generated code fails.
+
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    // Bounded memoization in `FixedMap`, which doesn't allocate.
+    #[cached(capacity = 4)]
+    pub fn x_times(&self, n: u64) -> u64 {
+        n * self.x
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn name(&self) -> &str {
+        alloc::format!("hoge{}", self.x)
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn printable(&self) -> impl core::fmt::Display {
+        self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field(new)]
+pub struct Hoge {
+    x: u64,
+}
+
+pub fn use_caches(x: u64) -> u64 {
+    let hoge = Hoge::new(x);
+    *hoge.two_times_x() + hoge.x_times(3)
+}
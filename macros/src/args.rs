@@ -0,0 +1,1062 @@
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::parse::{Parse, Parser};
+use syn::punctuated::Punctuated;
+
+/// Default name of the field injected by `#[add_cache_field]`.
+pub(crate) const DEFAULT_FIELD: &str = "__cache_fields__";
+
+/// Arguments of `#[impl_cached_method(...)]`.
+#[derive(Default)]
+pub(crate) struct ImplArgs {
+    /// `target = "Hoge"`: Name of the struct, used if the impl is for a type alias.
+    pub target: Option<syn::Ident>,
+    /// `field = "caches"`: Name of the injected field.
+    pub field: Option<syn::Ident>,
+    /// `for = "crate::model::Hoge"`: Key pairing with `#[add_cache_field(from = "...")]`.
+    pub for_: Option<syn::Path>,
+    /// `cell_path = "my_crate::MyCell"`: Cell type holding caches, which must implement
+    /// `CacheCell<T>`.
+    pub cell_path: Option<syn::Path>,
+    /// `inline`: Mark generated methods with `#[inline]` unless they have `#[inline(...)]`.
+    pub inline: bool,
+    /// `warm_caches`: Implement `WarmCaches`.
+    pub warm_caches: bool,
+    /// `assert_send_sync`: Assert that caches are `Send + Sync` at compile time.
+    pub assert_send_sync: bool,
+    /// `doc_note`: Append a note on caching to docs of cached methods.
+    pub doc_note: bool,
+    /// `dyn_trait` or `dyn_trait = "HogeCaches"`: Generate a dyn-safe trait of cached methods. The
+    /// name defaults to `CachedHoge`.
+    pub dyn_trait: Option<Option<syn::Ident>>,
+    /// `warn_mut_self`: Warn on cached methods taking `&mut self`.
+    pub warn_mut_self: bool,
+    /// `strict`: Reject cached methods taking `&mut self`.
+    pub strict: bool,
+    /// `verify`: Compare cache hits with fresh values in debug builds.
+    pub verify: bool,
+    /// `stats`: Wrap caches by `Counted` to count hits and misses.
+    pub stats: bool,
+    /// `switch`: Wrap caches by `Switchable` so that they can be disabled.
+    pub switch: bool,
+    /// `test_util`: Generate accessors to counts of computations and injectors of values if the
+    /// `test-util` feature is enabled.
+    pub test_util: bool,
+    /// `single_thread`: Hold caches in the default cell, which is not thread-safe, even if the
+    /// crate gives another one.
+    pub single_thread: bool,
+}
+
+impl ImplArgs {
+    pub(crate) fn parse(args: &TokenStream) -> syn::Result<Self> {
+        let mut ret = ImplArgs::default();
+        syn::meta::parser(|meta| {
+            if meta.path.is_ident("target") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                ret.target = Some(s.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("field") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                ret.field = Some(s.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("for") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                ret.for_ = Some(s.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("cell_path") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                ret.cell_path = Some(s.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("inline") {
+                ret.inline = true;
+                Ok(())
+            } else if meta.path.is_ident("single_thread") {
+                ret.single_thread = true;
+                Ok(())
+            } else if meta.path.is_ident("dyn_trait") {
+                ret.dyn_trait = Some(if meta.input.peek(syn::Token![=]) {
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    Some(s.parse()?)
+                } else {
+                    None
+                });
+                Ok(())
+            } else if meta.path.is_ident("assert_send_sync") {
+                ret.assert_send_sync = true;
+                Ok(())
+            } else if meta.path.is_ident("doc_note") {
+                ret.doc_note = true;
+                Ok(())
+            } else if meta.path.is_ident("warn_mut_self") {
+                ret.warn_mut_self = true;
+                Ok(())
+            } else if meta.path.is_ident("strict") {
+                ret.strict = true;
+                Ok(())
+            } else if meta.path.is_ident("verify") {
+                ret.verify = true;
+                Ok(())
+            } else if meta.path.is_ident("stats") {
+                if !cfg!(feature = "stats") {
+                    return Err(
+                        meta.error("`stats` requires the `stats` feature of `struct_cache_field`")
+                    );
+                }
+                ret.stats = true;
+                Ok(())
+            } else if meta.path.is_ident("switch") {
+                if !cfg!(feature = "switch") {
+                    return Err(meta
+                        .error("`switch` requires the `switch` feature of `struct_cache_field`"));
+                }
+                ret.switch = true;
+                Ok(())
+            } else if meta.path.is_ident("test_util") {
+                // Without the feature, e.g. in builds other than tests, it is a no-op.
+                ret.test_util = cfg!(feature = "test-util");
+                Ok(())
+            } else if meta.path.is_ident("warm_caches") {
+                if !cfg!(feature = "rayon") {
+                    return Err(meta.error(
+                        "`warm_caches` requires the `rayon` feature of `struct_cache_field`",
+                    ));
+                }
+                ret.warm_caches = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown argument of `struct_cache_field::impl_cached_method`"))
+            }
+        })
+        .parse2(args.clone())?;
+        if let (Some(for_), Some(_)) = (&ret.for_, &ret.target) {
+            return Err(syn::Error::new_spanned(
+                for_,
+                "`for` and `target` can't be given together",
+            ));
+        }
+        if let (Some(cell_path), true) = (&ret.cell_path, ret.single_thread) {
+            return Err(syn::Error::new_spanned(
+                cell_path,
+                "`cell_path` and `single_thread` can't be given together",
+            ));
+        }
+        Ok(ret)
+    }
+}
+
+/// Arguments of `#[cached_ext(...)]`.
+#[derive(Default)]
+pub(crate) struct ExtArgs {
+    /// `name = "DurationExt"`: Name of the extension trait.
+    pub name: Option<syn::Ident>,
+    /// `vis = "pub"`: Visibility of the extension trait and the cache struct.
+    pub vis: Option<syn::Visibility>,
+    /// `side_table`: Define methods for `Rc<T>` storing values in side tables.
+    pub side_table: bool,
+    /// `verify`: Compare cache hits with fresh values in debug builds.
+    pub verify: bool,
+    /// `stats`: Wrap caches by `Counted` to count hits and misses.
+    pub stats: bool,
+    /// `test_util`: Generate accessors to counts of computations and injectors of values if the
+    /// `test-util` feature is enabled.
+    pub test_util: bool,
+}
+
+impl ExtArgs {
+    pub(crate) fn parse(args: &TokenStream) -> syn::Result<Self> {
+        let mut ret = ExtArgs::default();
+        syn::meta::parser(|meta| {
+            if meta.path.is_ident("name") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                ret.name = Some(s.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("vis") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                ret.vis = Some(s.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("side_table") {
+                if !cfg!(feature = "std") {
+                    return Err(meta
+                        .error("`side_table` requires the `std` feature of `struct_cache_field`"));
+                }
+                ret.side_table = true;
+                Ok(())
+            } else if meta.path.is_ident("verify") {
+                ret.verify = true;
+                Ok(())
+            } else if meta.path.is_ident("stats") {
+                if !cfg!(feature = "stats") {
+                    return Err(
+                        meta.error("`stats` requires the `stats` feature of `struct_cache_field`")
+                    );
+                }
+                ret.stats = true;
+                Ok(())
+            } else if meta.path.is_ident("test_util") {
+                // Without the feature, e.g. in builds other than tests, it is a no-op.
+                ret.test_util = cfg!(feature = "test-util");
+                Ok(())
+            } else {
+                Err(meta.error("unknown argument of `struct_cache_field::cached_ext`"))
+            }
+        })
+        .parse2(args.clone())?;
+        Ok(ret)
+    }
+}
+
+/// Arguments of `#[add_cache_field(...)]`.
+#[derive(Default)]
+pub(crate) struct StructArgs {
+    /// `vis = "pub(crate)"`: Visibility of the cache struct and the injected field.
+    pub vis: Option<syn::Visibility>,
+    /// `name = "HogeCaches"`: Name of the cache struct.
+    pub name: Option<syn::Ident>,
+    /// `field = "caches"`: Name of the injected field. Must coincide with `#[impl_cached_method]`.
+    pub field: Option<syn::Ident>,
+    /// `from = "crate::model::Hoge"`: Key pairing with `#[impl_cached_method(for = "...")]`.
+    pub from: Option<syn::Path>,
+    /// `new` or `new = "from_fields"`: Generate a constructor with the name.
+    pub new: Option<syn::Ident>,
+    /// `init` or `init = "HogeData"`: Generate a struct without the cache field and a conversion
+    /// from it. The name defaults to `HogeInit`.
+    pub init: Option<Option<syn::Ident>>,
+    /// `rkyv_skip`: Mark the injected field with `#[with(Skip)]`. Automatically enabled if
+    /// the struct derives `Archive`.
+    pub rkyv_skip: bool,
+    /// `derive(Debug, Clone)`: Derives for the cache struct.
+    pub derive: Vec<syn::Path>,
+    /// `clone = "preserve"` or `clone = "reset"`: Implement `Clone` for the cache struct.
+    pub clone: Option<CloneMode>,
+    /// `debug_values`: Show values of filled caches in the generated `Debug` impl.
+    pub debug_values: bool,
+    /// `graph`: Generate `const CACHE_GRAPH_DOT: &str`.
+    pub graph: bool,
+    /// `heap_size`: Generate `fn cache_heap_size(&self) -> usize`.
+    pub heap_size: bool,
+    /// `derive_default`: Implement `Default` for the struct.
+    pub derive_default: bool,
+    /// `builder`: Mark the injected field with `#[builder(default, setter(skip))]`. Automatically
+    /// enabled if the struct derives `TypedBuilder` or `Builder`.
+    pub builder: bool,
+    /// `snapshot` or `snapshot = "HogeState"`: Generate a struct holding clones of cached values,
+    /// and methods to take and restore it. The name defaults to `HogeCacheSnapshot`.
+    pub snapshot: Option<Option<syn::Ident>>,
+    /// `serde_skip`: Mark the injected field with `#[serde(skip)]`. Automatically enabled if the
+    /// struct derives `Serialize` or `Deserialize`.
+    pub serde_skip: bool,
+    /// `serde_persist`: Implement `Serialize`/`Deserialize` for the cache struct so that computed
+    /// values are serialized with the struct.
+    pub serde_persist: bool,
+    /// `expose`: Show the cache struct and the injected field in docs instead of
+    /// `#[doc(hidden)]`.
+    pub expose: bool,
+    /// `field_first`: Inject the field before the other fields rather than after them.
+    pub field_first: bool,
+    /// `bound = "T: ToOwned"`: Bounds of the cache struct in addition to the ones it needs to name
+    /// associated types.
+    pub bound: Vec<syn::WherePredicate>,
+    /// `tolerant`: Give an empty cache struct with a warning if no cached methods are registered.
+    pub tolerant: bool,
+}
+
+impl StructArgs {
+    pub(crate) fn parse(args: &TokenStream) -> syn::Result<Self> {
+        let mut ret = StructArgs::default();
+        syn::meta::parser(|meta| {
+            if meta.path.is_ident("vis") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                ret.vis = Some(s.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("name") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                ret.name = Some(s.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("field") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                ret.field = Some(s.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("from") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                ret.from = Some(s.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("new") {
+                ret.new = Some(if meta.input.peek(syn::Token![=]) {
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    s.parse()?
+                } else {
+                    meta.path.get_ident().unwrap().clone()
+                });
+                Ok(())
+            } else if meta.path.is_ident("builder") {
+                ret.builder = true;
+                Ok(())
+            } else if meta.path.is_ident("serde_skip") {
+                ret.serde_skip = true;
+                Ok(())
+            } else if meta.path.is_ident("serde_persist") {
+                ret.serde_persist = true;
+                Ok(())
+            } else if meta.path.is_ident("rkyv_skip") {
+                ret.rkyv_skip = true;
+                Ok(())
+            } else if meta.path.is_ident("derive") {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                let paths = Punctuated::<syn::Path, syn::Token![,]>::parse_terminated(&content)?;
+                for path in paths {
+                    if path.is_ident("Default") {
+                        return Err(syn::Error::new_spanned(
+                            path,
+                            "`Default` is always implemented for the cache struct",
+                        ));
+                    }
+                    ret.derive.push(path);
+                }
+                Ok(())
+            } else if meta.path.is_ident("clone") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                ret.clone = Some(match s.value().as_str() {
+                    "preserve" => CloneMode::Preserve,
+                    "reset" => CloneMode::Reset,
+                    _ => {
+                        return Err(syn::Error::new(
+                            s.span(),
+                            "expected `\"preserve\"` or `\"reset\"`",
+                        ))
+                    }
+                });
+                Ok(())
+            } else if meta.path.is_ident("debug_values") {
+                ret.debug_values = true;
+                Ok(())
+            } else if meta.path.is_ident("expose") {
+                ret.expose = true;
+                Ok(())
+            } else if meta.path.is_ident("bound") {
+                let s: syn::LitStr = meta.value()?.parse()?;
+                ret.bound
+                    .extend(s.parse_with(Punctuated::<_, syn::Token![,]>::parse_terminated)?);
+                Ok(())
+            } else if meta.path.is_ident("field_first") {
+                ret.field_first = true;
+                Ok(())
+            } else if meta.path.is_ident("tolerant") {
+                ret.tolerant = true;
+                Ok(())
+            } else if meta.path.is_ident("graph") {
+                ret.graph = true;
+                Ok(())
+            } else if meta.path.is_ident("heap_size") {
+                ret.heap_size = true;
+                Ok(())
+            } else if meta.path.is_ident("derive_default") {
+                ret.derive_default = true;
+                Ok(())
+            } else if meta.path.is_ident("snapshot") {
+                ret.snapshot = Some(if meta.input.peek(syn::Token![=]) {
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    Some(s.parse()?)
+                } else {
+                    None
+                });
+                Ok(())
+            } else if meta.path.is_ident("init") {
+                ret.init = Some(if meta.input.peek(syn::Token![=]) {
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    Some(s.parse()?)
+                } else {
+                    None
+                });
+                Ok(())
+            } else {
+                Err(meta.error("unknown argument of `struct_cache_field::add_cache_field`"))
+            }
+        })
+        .parse2(args.clone())?;
+        if let Some(path) = ret.derive.iter().find(|path| path.is_ident("Clone")) {
+            if ret.clone.is_some() {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    "`derive(Clone)` conflicts with `clone = \"...\"`",
+                ));
+            }
+        }
+        Ok(ret)
+    }
+}
+
+/// How clones of the cache struct treat computed values.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CloneMode {
+    /// Clone computed values.
+    Preserve,
+    /// Start with empty caches.
+    Reset,
+}
+
+/// Arguments of `#[cached(...)]` attached to a method in `#[impl_cached_method]`.
+#[derive(Default)]
+pub(crate) struct MethodArgs {
+    /// `box_dyn = "Trait + Bounds"`: Store the value as `Box<dyn Trait + Bounds>`.
+    pub box_dyn: Option<Punctuated<syn::TypeParamBound, syn::Token![+]>>,
+    /// `timed`: Record how long the computation took.
+    pub timed: bool,
+    /// `trace`: Emit a `tracing` span and event on a cache miss.
+    pub trace: bool,
+    /// `field_attr(...)`: Attributes of the cache field.
+    pub field_attrs: Vec<syn::Meta>,
+    /// `run_once`: Run a method returning `()` only once.
+    pub run_once: bool,
+    /// `capacity = N`: Memoize by arguments, keeping at most `N` entries.
+    pub capacity: Option<syn::Expr>,
+    /// `max_bytes = N`: Evict entries of `capacity` so that their sizes sum up to at most `N`.
+    pub max_bytes: Option<syn::Expr>,
+    /// `size_fn = "path::to::fn"`: Function estimating the size of a value for `max_bytes`.
+    pub size_fn: Option<syn::Path>,
+    /// `hasher = "path::to::BuildHasher"`: Look up entries of `capacity` by hash with the hasher.
+    pub hasher: Option<syn::Path>,
+    /// `backend = "path::to::Map"`: Hold entries of `capacity` in the map implementing `CacheMap`.
+    pub backend: Option<syn::Path>,
+    /// `key = "(self.x, arg.id)"`: Memoize by the expression rather than the arguments.
+    pub key: Option<syn::Expr>,
+    /// `key_type = "(u64, u64)"`: Type of `key` or `shared_by`.
+    pub key_type: Option<syn::Type>,
+    /// `shared_by = "self.id"`: Share values among instances in a process-wide map keyed by the
+    /// expression.
+    pub shared_by: Option<syn::Expr>,
+    /// `unless = "result.is_empty()"`: Don't store values matching the predicate.
+    pub unless: Option<syn::Expr>,
+    /// `produces(a, b)`: Generate accessors to the values of the returned tuple.
+    pub produces: Vec<syn::Ident>,
+    /// `weak`: Store a returned `Rc<T>` or `Arc<T>` as a weak reference.
+    pub weak: bool,
+    /// `resettable`: Store the value in a cell which can be reset through `&self`.
+    pub resettable: bool,
+    /// `scope`: Store the value in the current `CacheScope` rather than the instance.
+    pub scope: bool,
+    /// `recompute_if_changed(self.x, self.y)`: Store clones of the fields with the value, and
+    /// compute it again if they differ.
+    pub recompute_if_changed: Vec<syn::Member>,
+    /// `unchecked_cell`: Hold the cache in `UncheckedCell` rather than the cell of the impl.
+    pub unchecked_cell: bool,
+    /// `fresh`: Generate a variant of the method computing the value without the cache.
+    pub fresh: bool,
+    /// `refresh`: Generate a method computing the value again and overwriting the cache.
+    pub refresh: bool,
+    /// `const`: Evaluate the body at compile time into an associated constant instead of a cache.
+    pub const_: bool,
+    /// `persist = "serde_json"`: Persist values on disk with the serde format.
+    pub persist: Option<syn::Path>,
+    /// `no_verify`: Don't compare cache hits with fresh values with `verify` of the impl.
+    pub no_verify: bool,
+    /// `arena = "self.arena"`: Allocate the value in the arena and store the reference to it.
+    pub arena: Option<syn::Expr>,
+}
+
+impl MethodArgs {
+    pub(crate) fn from_attrs(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut args = MethodArgs::default();
+        for attr in attrs.iter().filter(|attr| is_cached_attr(attr)) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("box_dyn") {
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    args.box_dyn = Some(s.parse_with(Punctuated::parse_separated_nonempty)?);
+                    Ok(())
+                } else if meta.path.is_ident("timed") {
+                    if !cfg!(feature = "std") {
+                        return Err(meta
+                            .error("`timed` requires the `std` feature of `struct_cache_field`"));
+                    }
+                    args.timed = true;
+                    Ok(())
+                } else if meta.path.is_ident("trace") {
+                    if !cfg!(feature = "tracing") {
+                        return Err(meta.error(
+                            "`trace` requires the `tracing` feature of `struct_cache_field`",
+                        ));
+                    }
+                    args.trace = true;
+                    Ok(())
+                } else if meta.path.is_ident("run_once") {
+                    args.run_once = true;
+                    Ok(())
+                } else if meta.path.is_ident("capacity") {
+                    args.capacity = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("max_bytes") {
+                    args.max_bytes = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("size_fn") {
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    args.size_fn = Some(s.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("hasher") {
+                    if !cfg!(feature = "std") {
+                        return Err(meta
+                            .error("`hasher` requires the `std` feature of `struct_cache_field`"));
+                    }
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    args.hasher = Some(s.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("backend") {
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    args.backend = Some(s.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("key") {
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    args.key = Some(s.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("key_type") {
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    args.key_type = Some(s.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("shared_by") {
+                    if !cfg!(feature = "std") {
+                        return Err(meta.error(
+                            "`shared_by` requires the `std` feature of `struct_cache_field`",
+                        ));
+                    }
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    args.shared_by = Some(s.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("unless") {
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    args.unless = Some(s.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("weak") {
+                    if !cfg!(feature = "std") {
+                        return Err(
+                            meta.error("`weak` requires the `std` feature of `struct_cache_field`")
+                        );
+                    }
+                    args.weak = true;
+                    Ok(())
+                } else if meta.path.is_ident("resettable") {
+                    args.resettable = true;
+                    Ok(())
+                } else if meta.path.is_ident("unchecked_cell") {
+                    args.unchecked_cell = true;
+                    Ok(())
+                } else if meta.path.is_ident("scope") {
+                    if !cfg!(feature = "std") {
+                        return Err(meta
+                            .error("`scope` requires the `std` feature of `struct_cache_field`"));
+                    }
+                    args.scope = true;
+                    Ok(())
+                } else if meta.path.is_ident("recompute_if_changed") {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    for expr in content.parse_terminated(syn::Expr::parse, syn::Token![,])? {
+                        match expr {
+                            syn::Expr::Field(syn::ExprField { base, member, .. })
+                                if matches!(&*base, syn::Expr::Path(x) if x.path.is_ident("self")) =>
+                            {
+                                args.recompute_if_changed.push(member);
+                            }
+                            _ => {
+                                return Err(syn::Error::new_spanned(
+                                    expr,
+                                    "expected a field of `self`, e.g. `self.x`",
+                                ))
+                            }
+                        }
+                    }
+                    if args.recompute_if_changed.is_empty() {
+                        return Err(meta.error("`recompute_if_changed` requires fields"));
+                    }
+                    Ok(())
+                } else if meta.path.is_ident("fresh") {
+                    args.fresh = true;
+                    Ok(())
+                } else if meta.path.is_ident("refresh") {
+                    args.refresh = true;
+                    Ok(())
+                } else if meta.path.is_ident("const") {
+                    args.const_ = true;
+                    Ok(())
+                } else if meta.path.is_ident("persist") {
+                    if !cfg!(feature = "persist") {
+                        return Err(meta.error(
+                            "`persist` requires the `persist` feature of `struct_cache_field`",
+                        ));
+                    }
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    args.persist = Some(s.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("no_verify") {
+                    args.no_verify = true;
+                    Ok(())
+                } else if meta.path.is_ident("arena") {
+                    let s: syn::LitStr = meta.value()?.parse()?;
+                    args.arena = Some(s.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("produces") {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    args.produces
+                        .extend(content.parse_terminated(syn::Ident::parse, syn::Token![,])?);
+                    Ok(())
+                } else if meta.path.is_ident("field_attr") {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    args.field_attrs
+                        .extend(content.parse_terminated(syn::Meta::parse, syn::Token![,])?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown argument of `#[cached(...)]`"))
+                }
+            })?;
+        }
+        Ok(args)
+    }
+}
+
+/// Pseudo-option for type parameters of the method.
+const TYPE_PARAMS: &str = "type parameters";
+/// Pseudo-option for a receiver `self: Rc<Self>` or `self: Arc<Self>`.
+const SMART_POINTER: &str = "self: Rc<Self>";
+/// Pseudo-option for a return type `impl Trait`, which is stored as with `box_dyn`.
+const IMPL_TRAIT: &str = "impl Trait";
+
+/// Options of `#[cached(...)]` and the ones each can't be used with. An error is reported at the
+/// first option of the table conflicting with another.
+const CONFLICTS: &[(&str, &[&str])] = &[
+    (
+        "const",
+        &[
+            "timed",
+            "run_once",
+            "capacity",
+            "shared_by",
+            "weak",
+            "resettable",
+            "scope",
+            "recompute_if_changed",
+            "unchecked_cell",
+            "refresh",
+            "fresh",
+            "produces",
+            "box_dyn",
+            "arena",
+            "trace",
+            "persist",
+            TYPE_PARAMS,
+        ],
+    ),
+    // Storage modes, which are mutually exclusive.
+    (
+        "capacity",
+        &[
+            "shared_by",
+            "weak",
+            "resettable",
+            "scope",
+            "recompute_if_changed",
+            "timed",
+            "run_once",
+            "refresh",
+            "produces",
+            "box_dyn",
+            TYPE_PARAMS,
+        ],
+    ),
+    (
+        "shared_by",
+        &[
+            "weak",
+            "resettable",
+            "scope",
+            "recompute_if_changed",
+            "timed",
+            "run_once",
+            "refresh",
+            "produces",
+            "box_dyn",
+            "arena",
+            TYPE_PARAMS,
+        ],
+    ),
+    (
+        "weak",
+        &[
+            "resettable",
+            "scope",
+            "recompute_if_changed",
+            "timed",
+            "produces",
+            "box_dyn",
+            TYPE_PARAMS,
+        ],
+    ),
+    (
+        "resettable",
+        &[
+            "scope",
+            "recompute_if_changed",
+            "timed",
+            "run_once",
+            "produces",
+            "box_dyn",
+            "arena",
+            TYPE_PARAMS,
+        ],
+    ),
+    (
+        "scope",
+        &[
+            "recompute_if_changed",
+            "timed",
+            "run_once",
+            "produces",
+            "box_dyn",
+            "arena",
+            TYPE_PARAMS,
+        ],
+    ),
+    (
+        "recompute_if_changed",
+        &[
+            "timed",
+            "run_once",
+            "produces",
+            "box_dyn",
+            "arena",
+            TYPE_PARAMS,
+        ],
+    ),
+    // Other options.
+    (
+        "produces",
+        &["run_once", "box_dyn", SMART_POINTER, TYPE_PARAMS],
+    ),
+    ("arena", &["box_dyn"]),
+    (
+        "persist",
+        &["run_once", "key", "weak", "arena", "box_dyn", IMPL_TRAIT],
+    ),
+    ("backend", &["hasher", "max_bytes"]),
+    ("timed", &[TYPE_PARAMS]),
+    ("unchecked_cell", &[TYPE_PARAMS]),
+];
+
+/// Options of `#[cached(...)]` and the ones each requires one of.
+const REQUIREMENTS: &[(&str, &[&str])] = &[
+    ("max_bytes", &["capacity"]),
+    ("size_fn", &["max_bytes"]),
+    ("hasher", &["capacity"]),
+    ("backend", &["capacity"]),
+    ("key", &["capacity"]),
+    ("key", &["key_type"]),
+    ("key_type", &["key", "shared_by"]),
+    ("shared_by", &["key_type"]),
+    (
+        "unless",
+        &[
+            "capacity",
+            "shared_by",
+            "resettable",
+            "scope",
+            "recompute_if_changed",
+            "weak",
+        ],
+    ),
+];
+
+/// Options of `#[cached(...)]` with which the method returns a clone of the cached value.
+const OWNED_RETURNS: &[&str] = &[
+    "capacity",
+    "shared_by",
+    "resettable",
+    "scope",
+    "recompute_if_changed",
+];
+
+/// Returns the option quoted for an error message, e.g. `` `capacity` ``.
+fn describe(option: &str) -> String {
+    match option {
+        TYPE_PARAMS => option.to_owned(),
+        SMART_POINTER => "`self: Rc<Self>` or `self: Arc<Self>`".to_owned(),
+        _ => format!("`{option}`"),
+    }
+}
+
+/// Returns the options joined for an error message, e.g. `` `a`, `b` or `c` ``.
+fn describe_any(options: &[&str]) -> String {
+    match options {
+        [] => String::new(),
+        [option] => describe(option),
+        [init @ .., last] => format!(
+            "{} or {}",
+            init.iter()
+                .map(|x| describe(x))
+                .collect::<Vec<_>>()
+                .join(", "),
+            describe(last),
+        ),
+    }
+}
+
+impl MethodArgs {
+    /// Returns the options given to the method, including pseudo-options of its signature, with
+    /// tokens to report errors at.
+    fn options(
+        &self,
+        sig: &syn::Signature,
+        return_ty: &syn::Type,
+    ) -> Vec<(&'static str, TokenStream)> {
+        let ident = || sig.ident.to_token_stream();
+        let ret = || return_ty.to_token_stream();
+        [
+            ("const", self.const_.then(ident)),
+            (
+                "capacity",
+                self.capacity.as_ref().map(ToTokens::to_token_stream),
+            ),
+            (
+                "shared_by",
+                self.shared_by.as_ref().map(ToTokens::to_token_stream),
+            ),
+            ("weak", self.weak.then(ret)),
+            ("resettable", self.resettable.then(ret)),
+            ("scope", self.scope.then(ret)),
+            (
+                "recompute_if_changed",
+                (!self.recompute_if_changed.is_empty()).then(ret),
+            ),
+            (
+                "produces",
+                self.produces.first().map(ToTokens::to_token_stream),
+            ),
+            ("arena", self.arena.as_ref().map(ToTokens::to_token_stream)),
+            (
+                "persist",
+                self.persist.as_ref().map(ToTokens::to_token_stream),
+            ),
+            (
+                "backend",
+                self.backend.as_ref().map(ToTokens::to_token_stream),
+            ),
+            (
+                "hasher",
+                self.hasher.as_ref().map(ToTokens::to_token_stream),
+            ),
+            (
+                "max_bytes",
+                self.max_bytes.as_ref().map(ToTokens::to_token_stream),
+            ),
+            (
+                "size_fn",
+                self.size_fn.as_ref().map(ToTokens::to_token_stream),
+            ),
+            ("key", self.key.as_ref().map(ToTokens::to_token_stream)),
+            (
+                "key_type",
+                self.key_type.as_ref().map(ToTokens::to_token_stream),
+            ),
+            (
+                "unless",
+                self.unless.as_ref().map(ToTokens::to_token_stream),
+            ),
+            (
+                "box_dyn",
+                self.box_dyn.as_ref().map(ToTokens::to_token_stream),
+            ),
+            ("timed", self.timed.then(ident)),
+            ("run_once", self.run_once.then(ident)),
+            ("trace", self.trace.then(ident)),
+            ("unchecked_cell", self.unchecked_cell.then(ident)),
+            ("fresh", self.fresh.then(ident)),
+            ("refresh", self.refresh.then(ident)),
+            (
+                TYPE_PARAMS,
+                sig.generics
+                    .type_params()
+                    .next()
+                    .map(ToTokens::to_token_stream),
+            ),
+            (
+                SMART_POINTER,
+                sig.receiver()
+                    .filter(|x| crate::smart_pointer_receiver(x).is_some())
+                    .map(ToTokens::to_token_stream),
+            ),
+            (
+                IMPL_TRAIT,
+                matches!(return_ty, syn::Type::ImplTrait(_)).then(ret),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(option, tokens)| Some((option, tokens?)))
+        .collect()
+    }
+
+    /// Checks the options against each other and the signature of the method returning
+    /// `return_ty`.
+    pub(crate) fn validate(&self, sig: &syn::Signature, return_ty: &syn::Type) -> syn::Result<()> {
+        let options = self.options(sig, return_ty);
+        let given = |option: &str| options.iter().any(|(x, _)| *x == option);
+        for (option, conflicts) in CONFLICTS {
+            let Some((_, tokens)) = options.iter().find(|(x, _)| x == option) else {
+                continue;
+            };
+            if let Some(conflict) = conflicts.iter().find(|x| given(x)) {
+                return Err(syn::Error::new_spanned(
+                    tokens,
+                    format!(
+                        "{} can't be used with {}",
+                        describe(option),
+                        describe(conflict)
+                    ),
+                ));
+            }
+        }
+        for (option, requirements) in REQUIREMENTS {
+            let Some((_, tokens)) = options.iter().find(|(x, _)| x == option) else {
+                continue;
+            };
+            if !requirements.iter().any(|x| given(x)) {
+                return Err(syn::Error::new_spanned(
+                    tokens,
+                    format!(
+                        "{} requires {}",
+                        describe(option),
+                        describe_any(requirements)
+                    ),
+                ));
+            }
+        }
+        if let Some(option) = OWNED_RETURNS.iter().find(|x| given(x)) {
+            if matches!(return_ty, syn::Type::Reference(_) | syn::Type::ImplTrait(_)) {
+                return Err(syn::Error::new_spanned(
+                    return_ty,
+                    format!(
+                        "method with {} returns a clone of the cached value, which must be an owned type",
+                        describe(option),
+                    ),
+                ));
+            }
+        }
+        if self.run_once && !matches!(return_ty, syn::Type::Tuple(x) if x.elems.is_empty()) {
+            return Err(syn::Error::new_spanned(
+                return_ty,
+                "`run_once` requires the method to return `()`",
+            ));
+        }
+        let keys = sig
+            .inputs
+            .iter()
+            .find(|arg| matches!(arg, syn::FnArg::Typed(_)));
+        match (&self.capacity, keys) {
+            (None, Some(arg)) => {
+                return Err(syn::Error::new_spanned(
+                    arg,
+                    "cached method can't take arguments other than `self`. maybe forgot `#[cached(capacity = N)]` to memoize by arguments?",
+                ));
+            }
+            (Some(capacity), None) if self.key.is_none() => {
+                return Err(syn::Error::new_spanned(
+                    capacity,
+                    "`capacity` requires the method to take arguments to memoize by, or `key = \"...\"`",
+                ));
+            }
+            _ => {}
+        }
+        if let Some(param) = sig.generics.const_params().next() {
+            return Err(syn::Error::new_spanned(
+                param,
+                "cached method can't take const parameters",
+            ));
+        }
+        if let Some(param) = sig.generics.type_params().next() {
+            if !cfg!(feature = "alloc") {
+                return Err(syn::Error::new_spanned(
+                    param,
+                    "cached method with type parameters requires the `alloc` feature of `struct_cache_field`",
+                ));
+            }
+        }
+        if !self.produces.is_empty() {
+            let len = match return_ty {
+                syn::Type::Tuple(x) => x.elems.len(),
+                _ => 0,
+            };
+            if len != self.produces.len() {
+                return Err(syn::Error::new_spanned(
+                    return_ty,
+                    format!(
+                        "`produces` requires the method to return a tuple of {} values",
+                        self.produces.len()
+                    ),
+                ));
+            }
+        }
+        if self.weak {
+            let pointer = match return_ty {
+                syn::Type::Path(x) => x.path.segments.last().map(|x| &x.ident),
+                _ => None,
+            };
+            if !pointer.map_or(false, |x| x == "Rc" || x == "Arc") {
+                return Err(syn::Error::new_spanned(
+                    return_ty,
+                    "`weak` requires the method to return `Rc<T>` or `Arc<T>`",
+                ));
+            }
+        }
+        if self.arena.is_some() {
+            let named = match return_ty {
+                syn::Type::Reference(x) => {
+                    x.mutability.is_none() && x.lifetime.as_ref().map_or(false, |x| x.ident != "_")
+                }
+                _ => false,
+            };
+            if !named {
+                return Err(syn::Error::new_spanned(
+                    return_ty,
+                    "`arena` requires the method to return `&'a T` with the lifetime of the arena",
+                ));
+            }
+        }
+        if let Some(key_type) = self.key_type.as_ref().filter(|_| self.shared_by.is_some()) {
+            for ty in [key_type, return_ty] {
+                if crate::mentions_self_type(ty.to_token_stream()) {
+                    return Err(syn::Error::new_spanned(
+                        ty,
+                        "`shared_by` requires `key_type` and the return type not to name `Self`, as the map is a `static`, which can't refer to the impl",
+                    ));
+                }
+            }
+        }
+        if self.refresh {
+            let receiver = sig.receiver();
+            if receiver.map_or(true, |x| x.reference.is_none()) {
+                return Err(syn::Error::new_spanned(
+                    receiver.map_or_else(|| sig.to_token_stream(), |x| x.to_token_stream()),
+                    "`refresh` requires the method to take `&self` or `&mut self`",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn is_cached_attr(attr: &syn::Attribute) -> bool {
+    attr.path().is_ident("cached")
+}
+
+/// Returns the paths in `#[derive(...)]`.
+pub(crate) fn derived_traits(attrs: &[syn::Attribute]) -> syn::Result<Vec<syn::Path>> {
+    let mut ret = vec![];
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        let paths =
+            attr.parse_args_with(Punctuated::<syn::Path, syn::Token![,]>::parse_terminated)?;
+        ret.extend(paths);
+    }
+    Ok(ret)
+}
+
+/// Returns the last ident of a path, e.g. `Serialize` for `serde::Serialize`.
+pub(crate) fn last_ident(path: &syn::Path) -> &syn::Ident {
+    &path.segments.last().unwrap().ident
+}
@@ -0,0 +1,130 @@
+//! Defaults of a crate, given by environment variables at compile time.
+//!
+//! They are meant to be set by a build script with `cargo:rustc-env`, so that Cargo compiles the
+//! crate again when they change.
+
+use crate::args;
+use proc_macro2::Span;
+
+/// Path of the cell holding caches, used if `cell_path` is not given.
+const CELL_PATH: &str = "STRUCT_CACHE_FIELD_CELL_PATH";
+/// Name of the field to be injected, used if `field` is not given.
+const FIELD: &str = "STRUCT_CACHE_FIELD_FIELD";
+/// Whether to count hits and misses as with `#[impl_cached_method(stats)]`.
+const STATS: &str = "STRUCT_CACHE_FIELD_STATS";
+/// Whether to generate test utilities as with `#[impl_cached_method(test_util)]`.
+const TEST_UTIL: &str = "STRUCT_CACHE_FIELD_TEST_UTIL";
+/// Whether structs without cached methods get empty caches as with `#[add_cache_field(tolerant)]`.
+const TOLERANT: &str = "STRUCT_CACHE_FIELD_TOLERANT";
+
+fn var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|x| !x.trim().is_empty())
+}
+
+fn parse<T: syn::parse::Parse>(name: &str, value: &str) -> syn::Result<T> {
+    syn::parse_str(value).map_err(|e| {
+        syn::Error::new(
+            Span::call_site(),
+            format!("invalid `{name}={value}` of the environment: {e}"),
+        )
+    })
+}
+
+fn parse_bool(name: &str, value: &str) -> syn::Result<bool> {
+    match value.trim() {
+        "1" | "true" | "on" => Ok(true),
+        "0" | "false" | "off" => Ok(false),
+        _ => Err(syn::Error::new(
+            Span::call_site(),
+            format!("invalid `{name}={value}` of the environment: expected `true` or `false`"),
+        )),
+    }
+}
+
+/// Returns the cell holding caches if `cell_path` is not given.
+pub(crate) fn cell_path() -> syn::Result<syn::Path> {
+    if let Some(value) = var(CELL_PATH) {
+        return parse(CELL_PATH, &value);
+    }
+    Ok(unsync_cell_path())
+}
+
+/// Returns the cell holding caches by default, which is not thread-safe.
+pub(crate) fn unsync_cell_path() -> syn::Path {
+    if cfg!(feature = "once_cell") {
+        syn::parse_quote!(::struct_cache_field::__private::once_cell::unsync::OnceCell)
+    } else {
+        syn::parse_quote!(::core::cell::OnceCell)
+    }
+}
+
+/// Returns the name of the field to be injected if `field` is not given.
+pub(crate) fn field() -> syn::Result<syn::Ident> {
+    match var(FIELD) {
+        Some(value) => parse(FIELD, &value),
+        None => Ok(syn::Ident::new(args::DEFAULT_FIELD, Span::call_site())),
+    }
+}
+
+/// Returns `true` if caches are wrapped by `Counted` even without `stats` of the impl.
+///
+/// It is off unless turned on, so that the `stats` feature enabled by another crate in the
+/// dependency graph doesn't change caches of this one.
+pub(crate) fn stats() -> syn::Result<bool> {
+    let Some(value) = var(STATS) else {
+        return Ok(false);
+    };
+    let stats = parse_bool(STATS, &value)?;
+    if stats && !cfg!(feature = "stats") {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            format!("`{STATS}={value}` requires the `stats` feature"),
+        ));
+    }
+    Ok(stats)
+}
+
+/// Returns `true` if test utilities are generated even without `test_util` of the impl.
+///
+/// It is off unless turned on, so that the `test-util` feature enabled by another crate in the
+/// dependency graph, e.g. in its `[dev-dependencies]`, doesn't change caches of this one. As
+/// `test_util`, it is a no-op without the feature.
+pub(crate) fn test_util() -> syn::Result<bool> {
+    match var(TEST_UTIL) {
+        Some(value) => Ok(parse_bool(TEST_UTIL, &value)? && cfg!(feature = "test-util")),
+        None => Ok(false),
+    }
+}
+
+/// Returns `true` if structs without cached methods get empty caches even without `tolerant` of
+/// the struct.
+pub(crate) fn tolerant() -> syn::Result<bool> {
+    match var(TOLERANT) {
+        Some(value) => parse_bool(TOLERANT, &value),
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let path: syn::Path = parse(CELL_PATH, "::std::sync::OnceLock").unwrap();
+        assert_eq!(path, syn::parse_quote!(::std::sync::OnceLock));
+        let ident: syn::Ident = parse(FIELD, "caches").unwrap();
+        assert_eq!(ident, "caches");
+        let e = parse::<syn::Ident>(FIELD, "cache fields").unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("invalid `STRUCT_CACHE_FIELD_FIELD=cache fields` of the environment"));
+    }
+
+    #[test]
+    fn test_parse_bool() {
+        assert!(parse_bool(STATS, "on").unwrap());
+        assert!(!parse_bool(STATS, " 0 ").unwrap());
+        assert!(parse_bool(STATS, "no").is_err());
+    }
+}
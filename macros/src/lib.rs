@@ -0,0 +1,4198 @@
+//! Procedural macros of [`struct_cache_field`](https://docs.rs/struct_cache_field).
+//!
+//! Use them through `struct_cache_field`, as generated code refers to it.
+
+mod args;
+mod config;
+mod storage;
+
+use itertools::{multiunzip, Itertools};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens, TokenStreamExt};
+use std::collections::HashSet;
+use syn::ext::IdentExt;
+use syn::parse::Parser;
+use syn::parse_macro_input;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use syn::visit_mut::VisitMut;
+
+#[proc_macro_attribute]
+pub fn impl_cached_method(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let mut input = parse_macro_input!(input as syn::Item);
+
+    match impl_cached_method_aux(&args.into(), &input) {
+        Ok(x) => x.into(),
+        Err(e) => {
+            // Remove inert attributes so that the original errors are not buried.
+            if let syn::Item::Impl(impl_) = &mut input {
+                for item in &mut impl_.items {
+                    if let syn::ImplItem::Fn(fn_) = item {
+                        fn_.attrs.retain(|attr| !args::is_cached_attr(attr));
+                    }
+                }
+            }
+            TokenStream::from_iter([e.into_compile_error(), (quote! { #input })]).into()
+        }
+    }
+}
+
+fn impl_cached_method_aux(args: &TokenStream, input: &syn::Item) -> syn::Result<TokenStream> {
+    let args = args::ImplArgs::parse(args)?;
+
+    let syn::Item::Impl(impl_) = input else {
+        return Err(syn::Error::new(input.span(), "expected `impl ...`"));
+    };
+    if let Some((_, path, for_)) = &impl_.trait_ {
+        let mut spans = TokenStream::new();
+        spans.append_all([path]);
+        spans.append_all([for_]);
+        return Err(syn::Error::new_spanned(
+            spans,
+            "expected `impl ...` without trait",
+        ));
+    }
+
+    let field = match args.field {
+        Some(x) => x,
+        None => config::field()?,
+    };
+    check_duplicate_methods(&impl_.items)?;
+    check_shared_by_generics(impl_)?;
+    let cell_path = match (args.cell_path, args.single_thread) {
+        (Some(x), _) => x,
+        (None, true) => config::unsync_cell_path(),
+        (None, false) => config::cell_path()?,
+    };
+    let stats = args.stats || config::stats()?;
+    let test_util = args.test_util || config::test_util()?;
+    let (items, fields, test_util_items): (
+        Vec<syn::ImplItem>,
+        Vec<Option<TokenStream>>,
+        Vec<Vec<syn::ImplItem>>,
+    ) = multiunzip(
+        impl_
+            .items
+            .iter()
+            .map(|item| {
+                rewrite_cached_method(
+                    item,
+                    &field,
+                    &cell_path,
+                    stats,
+                    test_util,
+                    args.verify,
+                    args.switch,
+                )
+            })
+            .collect::<syn::Result<Vec<_>>>()?,
+    );
+    let dependencies = impl_
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::ImplItem::Fn(fn_) if is_cached_fn(fn_) => Some((
+                fn_.sig.ident.unraw().to_string(),
+                collect_self_accesses(fn_),
+            )),
+            _ => None,
+        })
+        .collect_vec();
+    let return_types = impl_
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::ImplItem::Fn(fn_) if is_cached_fn(fn_) => Some((
+                fn_.sig.ident.unraw().to_string(),
+                match &fn_.sig.output {
+                    syn::ReturnType::Default => String::from("()"),
+                    syn::ReturnType::Type(_, ty) => type_to_string(ty),
+                },
+            )),
+            _ => None,
+        })
+        .collect_vec();
+    let accessors = impl_
+        .items
+        .iter()
+        .map(|item| gen_compute_time_accessor(item, &field))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let invalidators = impl_
+        .items
+        .iter()
+        .map(|item| gen_invalidator(item, &field))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let produced = impl_
+        .items
+        .iter()
+        .map(gen_produced_accessors)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let fresh = impl_
+        .items
+        .iter()
+        .map(gen_fresh)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let refreshers = items
+        .iter()
+        .zip(&impl_.items)
+        .map(|(item, original)| gen_refresher(item, original, &field, stats))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let assert_caches_fresh = gen_assert_caches_fresh(&impl_.items, &field)?;
+    let assert_send_sync = if args.assert_send_sync {
+        Some(gen_assert_send_sync(&impl_.items, &fields)?)
+    } else {
+        None
+    };
+    let warm_caches = if args.warm_caches {
+        gen_warm_caches(impl_, &dependencies)
+    } else {
+        quote! {}
+    };
+    let items = items
+        .into_iter()
+        .zip(&impl_.items)
+        .map(|(mut item, original)| {
+            if let (syn::ImplItem::Fn(fn_), syn::ImplItem::Fn(original)) = (&mut item, original) {
+                let warnings = [
+                    gen_unused_self_warning(original)?,
+                    gen_mut_self_warning(original, args.warn_mut_self, args.strict)?,
+                ];
+                for warning in warnings.into_iter().flatten() {
+                    fn_.block
+                        .stmts
+                        .insert(0, syn::Stmt::Item(syn::Item::Verbatim(warning)));
+                }
+            }
+            Ok(item)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let items = if args.doc_note {
+        items
+            .into_iter()
+            .zip(&impl_.items)
+            .map(|(mut item, original)| {
+                if let (syn::ImplItem::Fn(fn_), syn::ImplItem::Fn(original)) = (&mut item, original)
+                {
+                    if is_cached_fn(original) {
+                        fn_.attrs.extend(gen_doc_note(original)?);
+                    }
+                }
+                Ok(item)
+            })
+            .collect::<syn::Result<Vec<_>>>()?
+    } else {
+        items
+    };
+    let dyn_trait = match &args.dyn_trait {
+        Some(name) => gen_dyn_trait(impl_, &items, name.as_ref())?,
+        None => quote! {},
+    };
+    let mut impl_ = impl_.clone();
+    impl_.items = items;
+    impl_.items.extend(accessors.into_iter().flatten());
+    impl_.items.extend(invalidators.into_iter().flatten());
+    impl_.items.extend(produced.into_iter().flatten());
+    impl_.items.extend(fresh.into_iter().flatten());
+    impl_.items.extend(refreshers.into_iter().flatten());
+    impl_.items.extend(test_util_items.into_iter().flatten());
+    impl_.items.push(assert_caches_fresh);
+    impl_.items.extend(assert_send_sync);
+    if args.inline {
+        for item in &mut impl_.items {
+            if let syn::ImplItem::Fn(fn_) = item {
+                if !fn_.attrs.iter().any(|attr| attr.path().is_ident("inline")) {
+                    fn_.attrs.push(syn::parse_quote! { #[inline] });
+                }
+            }
+        }
+    }
+    // `Self` in the cache struct would mean itself, so name the type instead, e.g.
+    // `<Hoge<T> as Trait>::Assoc` for `<Self as Trait>::Assoc`.
+    let fields = fields
+        .into_iter()
+        .flatten()
+        .map(|field| replace_self(field, &impl_.self_ty))
+        .collect_vec();
+    let bounds = projection_bounds(&impl_.generics, &impl_.self_ty, &fields)?;
+    let key = match (args.for_, args.target) {
+        (Some(for_), _) => for_.to_token_stream(),
+        (None, Some(target)) => target.to_token_stream(),
+        (None, None) => {
+            let syn::Type::Path(ty) = impl_.self_ty.as_ref() else {
+                return Err(syn::Error::new(
+                    impl_.self_ty.span(),
+                    "expected TypePath like `path::to::Type`",
+                ));
+            };
+            // Use only the last ident as key, which is what `#[add_cache_field]` knows. Type
+            // arguments like `Hoge<{ N + 1 }>` are dropped.
+            ty.path.segments.last().unwrap().ident.to_token_stream()
+        }
+    };
+    let fields = fields
+        .into_iter()
+        .map(|field| syn::Field::parse_named.parse2(field))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let fields = merge_cfg_alternatives(fields)?;
+    let bounds = bounds
+        .into_iter()
+        .map(syn::parse2)
+        .collect::<syn::Result<Vec<_>>>()?;
+    storage::register_cache_fields(
+        &key,
+        storage::Site::of(&impl_),
+        &impl_.generics,
+        storage::CacheFields {
+            field,
+            fields,
+            bounds,
+            dependencies,
+            return_types,
+            stats,
+            switch: args.switch,
+            test_util,
+        },
+    )?;
+
+    Ok(quote! {
+        #impl_
+        #warm_caches
+        #dyn_trait
+    })
+}
+
+/// Generates a dyn-safe trait of cached methods, named `CachedHoge` by default, and implements it
+/// for the struct.
+///
+/// Methods not callable via `dyn`, i.e. ones with type parameters or without `&self`, are left out.
+/// The trait has the widest visibility of the methods and the generics of the impl.
+fn gen_dyn_trait(
+    impl_: &syn::ItemImpl,
+    items: &[syn::ImplItem],
+    name: Option<&syn::Ident>,
+) -> syn::Result<TokenStream> {
+    let self_name = match impl_.self_ty.as_ref() {
+        syn::Type::Path(ty) => ty.path.segments.last().unwrap().ident.to_string(),
+        _ => String::from("Value"),
+    };
+    let name = name
+        .cloned()
+        .unwrap_or_else(|| syn::Ident::new(&format!("Cached{self_name}"), Span::call_site()));
+    let methods = items
+        .iter()
+        .filter_map(|item| match item {
+            syn::ImplItem::Fn(fn_) => Some(fn_),
+            _ => None,
+        })
+        .filter(|fn_| {
+            let by_ref = matches!(
+                fn_.sig.receiver(),
+                Some(x) if x.reference.is_some() && x.mutability.is_none() && x.colon_token.is_none()
+            );
+            by_ref
+                && is_cached_fn(fn_)
+                && fn_.sig.asyncness.is_none()
+                && fn_.sig.generics.type_params().next().is_none()
+                && fn_.sig.generics.const_params().next().is_none()
+        })
+        .collect_vec();
+    let mut trait_items = vec![];
+    let mut impl_items = vec![];
+    for fn_ in &methods {
+        // Patterns like `mut y` aren't allowed in methods without bodies, so arguments are renamed.
+        let mut sig = fn_.sig.clone();
+        let mut args = vec![];
+        for (i, arg) in sig.inputs.iter_mut().enumerate() {
+            if let syn::FnArg::Typed(x) = arg {
+                let ident = syn::Ident::new(&format!("arg{i}"), Span::call_site());
+                *x.pat = syn::parse_quote! { #ident };
+                args.push(ident);
+            }
+        }
+        let ident = &sig.ident;
+        let cfgs = cfg_attrs(&fn_.attrs);
+        let docs = fn_.attrs.iter().filter(|attr| attr.path().is_ident("doc"));
+        trait_items.push(quote! {
+            #cfgs
+            #(#docs)*
+            #sig;
+        });
+        let call = quote! { Self::#ident(self, #(#args),*) };
+        let call = if sig.unsafety.is_some() {
+            quote! { unsafe { #call } }
+        } else {
+            call
+        };
+        impl_items.push(quote! {
+            #cfgs
+            #sig {
+                #call
+            }
+        });
+    }
+    let vis = methods
+        .iter()
+        .map(|fn_| &fn_.vis)
+        .max_by_key(|vis| match vis {
+            syn::Visibility::Public(_) => 3,
+            syn::Visibility::Restricted(x) if x.path.is_ident("crate") => 2,
+            syn::Visibility::Restricted(_) => 1,
+            syn::Visibility::Inherited => 0,
+        })
+        .cloned()
+        .unwrap_or(syn::Visibility::Inherited);
+    let doc = format!(" Dyn-safe accessors of cached methods of [`{self_name}`].");
+    let (impl_generics, ty_generics, where_clause) = impl_.generics.split_for_impl();
+    let self_ty = &impl_.self_ty;
+    Ok(quote! {
+        #[doc = #doc]
+        #vis trait #name #impl_generics #where_clause {
+            #(#trait_items)*
+        }
+
+        impl #impl_generics #name #ty_generics for #self_ty #where_clause {
+            #(#impl_items)*
+        }
+    })
+}
+
+/// Generates `impl WarmCaches` computing caches of methods taking only `&self`.
+fn gen_warm_caches(impl_: &syn::ItemImpl, dependencies: &[(String, Vec<String>)]) -> TokenStream {
+    let methods = impl_
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::ImplItem::Fn(fn_) => Some(fn_),
+            _ => None,
+        })
+        .filter(|fn_| {
+            let by_ref = matches!(
+                fn_.sig.receiver(),
+                Some(x) if x.reference.is_some() && x.mutability.is_none() && x.colon_token.is_none()
+            );
+            by_ref
+                && is_cached_fn(fn_)
+                && fn_.sig.unsafety.is_none()
+                && fn_.sig.inputs.len() == 1
+                && fn_.sig.generics.params.is_empty()
+        })
+        .collect_vec();
+    let names = dependencies
+        .iter()
+        .map(|(method, _)| method.as_str())
+        .collect::<HashSet<_>>();
+    // Methods calling other cached methods run after the parallel ones, so that they find the values
+    // computed.
+    let (independent, dependent): (Vec<&syn::ImplItemFn>, Vec<_>) =
+        methods.iter().partition(|fn_| {
+            let name = fn_.sig.ident.unraw().to_string();
+            dependencies
+                .iter()
+                .find(|(method, _)| *method == name)
+                .map_or(true, |(_, deps)| {
+                    deps.iter()
+                        .all(|dep| *dep == name || !names.contains(dep.as_str()))
+                })
+        });
+    let call = |fn_: &syn::ImplItemFn| {
+        let ident = &fn_.sig.ident;
+        let cfgs = cfg_attrs(&fn_.attrs);
+        quote! {
+            #cfgs
+            {
+                let _ = Self::#ident(self);
+            }
+        }
+    };
+    let calls = methods.iter().map(|fn_| call(fn_)).collect_vec();
+    let spawns = independent
+        .iter()
+        .map(|fn_| {
+            let ident = &fn_.sig.ident;
+            let cfgs = cfg_attrs(&fn_.attrs);
+            quote! {
+                #cfgs
+                {
+                    scope.spawn(move |_| {
+                        let _ = Self::#ident(self);
+                    });
+                }
+            }
+        })
+        .collect_vec();
+    let dependent_calls = dependent.iter().map(|fn_| call(fn_)).collect_vec();
+    let (impl_generics, _, where_clause) = impl_.generics.split_for_impl();
+    let self_ty = &impl_.self_ty;
+    quote! {
+        impl #impl_generics ::struct_cache_field::WarmCaches for #self_ty #where_clause {
+            fn warm_caches(&self) {
+                #(#calls)*
+            }
+
+            fn warm_caches_par(&self) {
+                ::struct_cache_field::__private::rayon::scope(|scope| {
+                    #(#spawns)*
+                });
+                #(#dependent_calls)*
+            }
+        }
+    }
+}
+
+fn check_duplicate_methods(items: &[syn::ImplItem]) -> syn::Result<()> {
+    let mut names = HashSet::new();
+    for item in items {
+        if let syn::ImplItem::Fn(fn_) = item {
+            let key = (&fn_.sig.ident, cfg_attrs(&fn_.attrs).to_string());
+            if is_cached_fn(fn_) && !names.insert(key) {
+                return Err(syn::Error::new_spanned(
+                    &fn_.sig.ident,
+                    format!("duplicate cache field `{}`", fn_.sig.ident),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Merges cache fields of alternatives of a method under different `#[cfg(...)]`, e.g.
+/// `#[cfg(feature = "x")]` and `#[cfg(not(feature = "x"))]`, into one under `#[cfg(any(...))]`.
+///
+/// Alternatives with different types of caches are kept apart, which is fine as long as their
+/// conditions are exclusive, as the methods are.
+fn merge_cfg_alternatives(fields: Vec<syn::Field>) -> syn::Result<Vec<syn::Field>> {
+    // Returns the conjunction of `#[cfg(...)]`, and the other attributes.
+    fn split_cfgs(field: &syn::Field) -> syn::Result<(TokenStream, Vec<&syn::Attribute>)> {
+        let mut cfgs = vec![];
+        let mut others = vec![];
+        for attr in &field.attrs {
+            if attr.path().is_ident("cfg") {
+                cfgs.push(attr.parse_args::<syn::Meta>()?);
+            } else {
+                others.push(attr);
+            }
+        }
+        Ok((quote! { all(#(#cfgs),*) }, others))
+    }
+
+    let mut merged: Vec<(syn::Field, Vec<TokenStream>)> = vec![];
+    for field in fields {
+        let (cfg, _) = split_cfgs(&field)?;
+        let mut same = None;
+        for (i, (x, _)) in merged.iter().enumerate() {
+            if x.ident == field.ident
+                && x.ty.to_token_stream().to_string() == field.ty.to_token_stream().to_string()
+                && split_cfgs(x)?.1 == split_cfgs(&field)?.1
+            {
+                same = Some(i);
+                break;
+            }
+        }
+        match same {
+            Some(i) => merged[i].1.push(cfg),
+            None => merged.push((field, vec![cfg])),
+        }
+    }
+    Ok(merged
+        .into_iter()
+        .map(|(mut field, cfgs)| {
+            if cfgs.len() > 1 {
+                field.attrs.retain(|attr| !attr.path().is_ident("cfg"));
+                field
+                    .attrs
+                    .insert(0, syn::parse_quote! { #[cfg(any(#(#cfgs),*))] });
+            }
+            field
+        })
+        .collect())
+}
+
+/// Rejects `shared_by` in an impl with type parameters, as the `static` map in the method would be
+/// shared by all instantiations of the impl.
+fn check_shared_by_generics(impl_: &syn::ItemImpl) -> syn::Result<()> {
+    let generics = &impl_.generics;
+    if generics.type_params().next().is_none() && generics.const_params().next().is_none() {
+        return Ok(());
+    }
+    for item in &impl_.items {
+        if let syn::ImplItem::Fn(fn_) = item {
+            if is_cached_fn(fn_)
+                && args::MethodArgs::from_attrs(&fn_.attrs)?
+                    .shared_by
+                    .is_some()
+            {
+                return Err(syn::Error::new_spanned(
+                    generics,
+                    format!(
+                        "`shared_by` of `{}` can't be used in an impl with type parameters, as the map is a `static` shared by all instantiations of the impl",
+                        fn_.sig.ident.unraw(),
+                    ),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn rewrite_cached_method(
+    item: &syn::ImplItem,
+    field: &syn::Ident,
+    cell_path: &syn::Path,
+    stats: bool,
+    test_util: bool,
+    verify: bool,
+    switch: bool,
+) -> syn::Result<(syn::ImplItem, Option<TokenStream>, Vec<syn::ImplItem>)> {
+    let syn::ImplItem::Fn(fn_) = item else {
+        return Ok((item.clone(), None, vec![]));
+    };
+    if !is_cached_fn(fn_) {
+        if let Some(attr) = fn_.attrs.iter().find(|attr| args::is_cached_attr(attr)) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`extern` fn is passed through untouched and can't have `#[cached(...)]`",
+            ));
+        }
+        return Ok((item.clone(), None, vec![]));
+    }
+    let ident = &fn_.sig.ident;
+    let block = &fn_.block;
+    let mut args = args::MethodArgs::from_attrs(&fn_.attrs)?;
+    // With `unchecked_cell`, hold the cache in `UncheckedCell` instead, documenting on the field why
+    // it is sound.
+    let unchecked_cell_path: syn::Path = syn::parse_quote!(::struct_cache_field::UncheckedCell);
+    let cell_path = if args.unchecked_cell {
+        let safety = format!(
+            " Cache of `{}()` in `UncheckedCell`, read without a check once it is filled. It is \
+             sound as the cell is not `Sync`, so only one thread accesses it, and a value once \
+             stored is not overwritten until `CacheCell::reset()` taking `&mut self`.",
+            ident.unraw(),
+        );
+        args.field_attrs.push(syn::parse_quote!(doc = #safety));
+        &unchecked_cell_path
+    } else {
+        cell_path
+    };
+    let verifiable = is_verifiable(fn_, &args);
+    let unit: Box<syn::Type> = syn::parse_quote! { () };
+    let return_ty = match &fn_.sig.output {
+        syn::ReturnType::Type(_, return_ty) => return_ty,
+        syn::ReturnType::Default if args.run_once => &unit,
+        syn::ReturnType::Default => {
+            return Err(syn::Error::new_spanned(
+                fn_.sig.clone(),
+                "cache-generator method must have return type. maybe forgot `#[cached(run_once)]` to run it only once?",
+            ));
+        }
+    };
+    args.validate(&fn_.sig, return_ty)?;
+    let keys = fn_
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::FnArg::Typed(x) => Some(x),
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect_vec();
+    // With `const`, the body is evaluated at compile time into an associated constant, which the
+    // method returns. It has no cache.
+    if args.const_ {
+        let constant = syn::Ident::new(
+            &format!(
+                "__STRUCT_CACHE_FIELD_{}",
+                ident.unraw().to_string().to_uppercase()
+            ),
+            ident.span(),
+        );
+        let cfgs = cfg_attrs(&fn_.attrs);
+        let mut new_fn = fn_.clone();
+        new_fn.attrs.retain(|attr| !args::is_cached_attr(attr));
+        new_fn.block = syn::parse_quote! {{ Self::#constant }};
+        let constant: syn::ImplItem = syn::parse_quote! {
+            #[doc(hidden)]
+            #cfgs
+            const #constant: #return_ty = #block;
+        };
+        return Ok((new_fn.into(), None, vec![constant]));
+    }
+    // Methods with type parameters store a value per instantiation, keyed by `TypeId`.
+    let type_params = fn_
+        .sig
+        .generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect_vec();
+    // With `key`, arguments are not stored but only used in the expression.
+    for key in keys.iter().filter(|_| args.key.is_none()) {
+        if !matches!(key.pat.as_ref(), syn::Pat::Ident(x) if x.by_ref.is_none() && x.subpat.is_none())
+        {
+            return Err(syn::Error::new_spanned(
+                &key.pat,
+                "argument of cached method must be an identifier",
+            ));
+        }
+        match key.ty.as_ref() {
+            syn::Type::Reference(x) if x.mutability.is_some() => {
+                return Err(syn::Error::new_spanned(
+                    &key.ty,
+                    "argument of cached method is stored as a key, which must be an owned type or a shared reference, e.g. `String` or `&str`",
+                ));
+            }
+            syn::Type::Reference(_) if !cfg!(feature = "alloc") => {
+                return Err(syn::Error::new_spanned(
+                    &key.ty,
+                    "argument of cached method by reference requires the `alloc` feature of `struct_cache_field`, as it is stored as an owned key",
+                ));
+            }
+            _ => {}
+        }
+    }
+    // `self: Rc<Self>` and `self: Arc<Self>` own the pointer, so return a clone of the value rather
+    // than a reference to it.
+    let smart_pointer = fn_.sig.receiver().and_then(smart_pointer_receiver);
+    if let Some(pointer) = smart_pointer {
+        if args.box_dyn.is_some() || matches!(return_ty.as_ref(), syn::Type::ImplTrait(_)) {
+            return Err(syn::Error::new_spanned(
+                return_ty,
+                format!("`self: {pointer}<Self>` requires `Clone` for the value, which can't be used with `box_dyn`"),
+            ));
+        }
+        if pointer == "Arc" && args.unchecked_cell {
+            return Err(syn::Error::new_spanned(
+                fn_.sig.receiver().unwrap(),
+                "`self: Arc<Self>` can't be used with `unchecked_cell`, which is not thread-safe",
+            ));
+        }
+        if pointer == "Arc" && !is_thread_safe_cell(cell_path) {
+            return Err(syn::Error::new_spanned(
+                fn_.sig.receiver().unwrap(),
+                "`self: Arc<Self>` requires a thread-safe cell. maybe forgot to add `cell_path = \"std::sync::OnceLock\"` to `#[struct_cache_field::impl_cached_method]`?",
+            ));
+        }
+    }
+    // `impl Trait` can't be a field type. Store it as `Box<dyn Trait>` and return `&dyn Trait`.
+    let boxed_bounds = match (args.box_dyn, return_ty.as_ref()) {
+        (Some(bounds), _) => Some(bounds),
+        (None, syn::Type::ImplTrait(x)) => Some(x.bounds.clone()),
+        (None, _) => None,
+    };
+    // `&T` borrowing `self`, e.g. `&str`, can't be a field type. Cache the owned value, e.g. `String`,
+    // which the body returns, and return a reference to it.
+    let borrowed = match return_ty.as_ref() {
+        syn::Type::Reference(x)
+            if boxed_bounds.is_none()
+                && x.mutability.is_none()
+                && x.lifetime.as_ref().map_or(true, |x| x.ident == "_") =>
+        {
+            Some(&x.elem)
+        }
+        _ => None,
+    };
+    let (cache_ty, output, init) = match (boxed_bounds, borrowed) {
+        (Some(bounds), _) => (
+            quote! { ::struct_cache_field::__private::Box<dyn #bounds> },
+            quote! { -> &(dyn #bounds) },
+            quote! { ::struct_cache_field::__private::Box::new(#block) },
+        ),
+        (None, Some(elem)) => (
+            quote! { <#elem as ::struct_cache_field::__private::ToOwned>::Owned },
+            quote! { -> &#elem },
+            quote! { #block },
+        ),
+        (None, None) => (
+            quote! { #return_ty },
+            quote! { -> &#return_ty },
+            quote! { #block },
+        ),
+    };
+    // With `arena`, the body returns `T`, which is allocated in the arena outliving `self`. Cache
+    // `&'a T` and return a copy of it.
+    let init = match &args.arena {
+        Some(arena) => quote! {{
+            let value: #return_ty = (#arena).alloc((|| #block)());
+            value
+        }},
+        None => init,
+    };
+    let init = if args.trace {
+        let name = ident.unraw().to_string();
+        quote! {{
+            let span = ::struct_cache_field::__private::tracing::debug_span!(
+                "cache_miss",
+                ty = ::core::any::type_name::<Self>(),
+                method = #name,
+            );
+            let _enter = span.enter();
+            let start = ::std::time::Instant::now();
+            let value = (|| #init)();
+            ::struct_cache_field::__private::tracing::debug!(
+                elapsed = ?start.elapsed(),
+                "computed cache",
+            );
+            value
+        }}
+    } else {
+        init
+    };
+    // With `test_util`, count runs of the computation.
+    let mut test_util_items: Vec<syn::ImplItem> = vec![];
+    let init = if test_util {
+        let vis = &fn_.vis;
+        let name = ident.unraw().to_string();
+        // Errors on a missing field are reported at the method as ones on the cache.
+        let init_counts = init_counts_field(ident.span());
+        let accessor = syn::Ident::new(&format!("{name}_init_count"), ident.span());
+        let doc = format!("Returns how many times the computation of [`Self::{ident}`] ran.");
+        let cfgs = cfg_attrs(&fn_.attrs);
+        test_util_items.push(syn::parse_quote! {
+            #[doc = #doc]
+            #cfgs
+            #vis fn #accessor(&self) -> usize {
+                ::struct_cache_field::InitCounts::get(&self.#field.#init_counts, #name)
+            }
+        });
+        quote! {{
+            ::struct_cache_field::InitCounts::record(&self.#field.#init_counts, #name);
+            #init
+        }}
+    } else {
+        init
+    };
+    // With `persist`, look up the value on disk by the hash of `self` and arguments before computing
+    // it, and write it back. A value found on disk is not counted as a computation. The hash also
+    // covers the version of the crate, the type of the value and the body, so that values persisted
+    // by another version of the method are not loaded.
+    let init = match &args.persist {
+        Some(format) => {
+            let name = ident.unraw().to_string();
+            let fingerprint = format!("{cache_ty} {}", block.to_token_stream());
+            let arg_idents = keys.iter().map(|key| &key.pat);
+            quote! {{
+                let hash = ::struct_cache_field::__private::persist::content_hash(&(
+                    ::core::env!("CARGO_PKG_VERSION"),
+                    #fingerprint,
+                    ::core::any::type_name::<Self>(),
+                    ::core::any::type_name::<(#(#type_params,)*)>(),
+                    #name,
+                    &*self,
+                    (#(&#arg_idents,)*),
+                ));
+                match ::struct_cache_field::__private::persist::load(#name, hash)
+                    .and_then(|bytes| #format::from_slice::<#cache_ty>(&bytes).ok())
+                {
+                    ::core::option::Option::Some(value) => value,
+                    ::core::option::Option::None => {
+                        let value: #cache_ty = (|| #init)();
+                        if let ::core::result::Result::Ok(bytes) = #format::to_vec(&value) {
+                            ::struct_cache_field::__private::persist::store(#name, hash, &bytes);
+                        }
+                        value
+                    }
+                }
+            }}
+        }
+        None => init,
+    };
+    // With `verify`, compare a cache hit with a fresh value in debug builds.
+    let verify = verify && verifiable;
+    let verify_hit = |cached: TokenStream| {
+        if !verify {
+            return quote! {};
+        }
+        let name = ident.unraw().to_string();
+        quote! {
+            if cfg!(debug_assertions) {
+                use ::struct_cache_field::__private::{VerifyEq as _, VerifySkip as _};
+                let fresh = (|| #block)();
+                (&::struct_cache_field::__private::Verify(#cached, &fresh, #name)).verify();
+            }
+        }
+    };
+    let mut new_fn = fn_.clone();
+    new_fn.attrs.retain(|attr| !args::is_cached_attr(attr));
+    // `TypeId::of()` requires `'static`.
+    if !type_params.is_empty() {
+        let where_clause = new_fn.sig.generics.make_where_clause();
+        for param in &type_params {
+            where_clause
+                .predicates
+                .push(syn::parse_quote! { #param: 'static });
+        }
+    }
+    // A reference through `self: Pin<&mut Self>` would borrow the local `Pin`. Convert it into
+    // `&Self` with the same lifetime, which is safe as the cache field is not pinned.
+    let (unpin, this) = match fn_.sig.receiver().and_then(unpin_receiver) {
+        Some(unpin) => (quote! { let this: &Self = #unpin; }, quote! { this }),
+        None => (quote! {}, quote! { self }),
+    };
+    // With `switch`, compute the value on every call while the cache is disabled.
+    let switchable = |this: &TokenStream| {
+        if stats {
+            quote! { ::struct_cache_field::Counted::inner(&#this.#field.#ident) }
+        } else {
+            quote! { &#this.#field.#ident }
+        }
+    };
+    let bypass = if switch {
+        let cell = switchable(&quote! { self });
+        quote! {
+            if !::struct_cache_field::Switchable::is_enabled(#cell) {
+                return (|| #init)();
+            }
+        }
+    } else {
+        quote! {}
+    };
+    // With `unless`, return a value matching the predicate without storing it.
+    let unless = match &args.unless {
+        Some(unless) => quote! {
+            if (|result: &#return_ty| #unless)(&value) {
+                return value;
+            }
+        },
+        None => quote! {},
+    };
+    // With `shared_by`, look up the value in a `static` map shared by all instances instead. The
+    // instance has no cache.
+    if let (Some(shared_by), Some(key_type)) = (&args.shared_by, &args.key_type) {
+        let verify_hit = verify_hit(quote! { &value });
+        new_fn.block = syn::parse2(quote! {{
+            static SHARED: ::struct_cache_field::SharedMap<#key_type, #return_ty> =
+                ::struct_cache_field::SharedMap::new();
+            let key: #key_type = #shared_by;
+            if let ::core::option::Option::Some(value) =
+                ::struct_cache_field::SharedMap::get(&SHARED, &key)
+            {
+                #verify_hit
+                return value;
+            }
+            let value: #return_ty = (|| #init)();
+            #unless
+            ::struct_cache_field::SharedMap::get_or_insert(&SHARED, key, value)
+        }})
+        .unwrap();
+        return Ok((new_fn.into(), None, test_util_items));
+    }
+    // With `capacity`, look up the value by arguments in a map instead. Values are returned by clone,
+    // as an entry can be evicted later.
+    if let Some(capacity) = &args.capacity {
+        let verify_hit = verify_hit(quote! { &value });
+        // With `hasher`, look up entries by hash rather than linearly.
+        let map_path = match &args.hasher {
+            Some(_) => quote! { ::struct_cache_field::HashedMap },
+            None => quote! { ::struct_cache_field::FixedMap },
+        };
+        // With `key`, the key is given by the expression over `self` and arguments instead, which
+        // is evaluated even on a hit.
+        let (key_ty, key_before, get, key_after) = match (&args.key, &args.key_type) {
+            (Some(key), Some(key_type)) => (
+                quote! { #key_type },
+                quote! { let key: #key_type = #key; },
+                quote! { #map_path::get(map, &key) },
+                quote! {},
+            ),
+            _ => {
+                let key_idents = keys
+                    .iter()
+                    .map(|key| match key.pat.as_ref() {
+                        syn::Pat::Ident(x) => &x.ident,
+                        _ => unreachable!(),
+                    })
+                    .collect_vec();
+                // Arguments by reference like `&str` are stored as owned keys like `String`, which are made
+                // only on a miss.
+                let (key_tys, owned_keys, eqs, hashed): (Vec<_>, Vec<_>, Vec<_>, Vec<_>) = keys
+                    .iter()
+                    .zip(&key_idents)
+                    .enumerate()
+                    .map(|(i, (key, ident))| {
+                        let i = syn::Index::from(i);
+                        match key.ty.as_ref() {
+                            syn::Type::Reference(x) => {
+                                let ty = &x.elem;
+                                (
+                                    quote! { <#ty as ::struct_cache_field::__private::ToOwned>::Owned },
+                                    quote! { ::struct_cache_field::__private::ToOwned::to_owned(#ident) },
+                                    quote! {
+                                        <_ as ::core::borrow::Borrow<#ty>>::borrow(&key.#i) == #ident
+                                    },
+                                    quote! { #ident },
+                                )
+                            }
+                            ty => (
+                                quote! { #ty },
+                                quote! { ::core::clone::Clone::clone(&#ident) },
+                                quote! { key.#i == #ident },
+                                quote! { &#ident },
+                            ),
+                        }
+                    })
+                    .multiunzip();
+                let get = match &args.hasher {
+                    Some(_) => quote! {
+                        #map_path::get_by(map, &(#(#hashed,)*), |key| #(#eqs)&&*)
+                    },
+                    None => quote! { #map_path::get_by(map, |key| #(#eqs)&&*) },
+                };
+                (
+                    quote! { (#(#key_tys,)*) },
+                    quote! {},
+                    get,
+                    quote! { let key: (#(#key_tys,)*) = (#(#owned_keys,)*); },
+                )
+            }
+        };
+        let cache_ty = match &args.hasher {
+            Some(hasher) => quote! {
+                #map_path<#key_ty, #return_ty, #hasher, { #capacity }>
+            },
+            None => quote! { #map_path<#key_ty, #return_ty, { #capacity }> },
+        };
+        let map = quote! {
+            ::struct_cache_field::CacheCell::get_or_init(
+                &#this.#field.#ident,
+                #map_path::new,
+            )
+        };
+        // With `backend`, hold entries in the given `CacheMap` instead, looked up by an owned key.
+        let (cache_ty, key_before, get, key_after, map) = match &args.backend {
+            Some(backend) => (
+                quote! { #backend<#key_ty, #return_ty> },
+                quote! { #key_before #key_after },
+                quote! { ::struct_cache_field::CacheMap::get(map, &key) },
+                quote! {},
+                quote! {
+                    ::struct_cache_field::CacheCell::get_or_init(&#this.#field.#ident, || {
+                        ::struct_cache_field::CacheMap::with_capacity(#capacity)
+                    })
+                },
+            ),
+            None => (cache_ty, key_before, get, key_after, map),
+        };
+        // With `max_bytes`, the size is given by `size_fn` or `HeapSize`.
+        let insert = match &args.max_bytes {
+            Some(max_bytes) => {
+                let size = match &args.size_fn {
+                    Some(size_fn) => quote! { #size_fn(&value) },
+                    None => quote! {
+                        ::core::mem::size_of_val(&value)
+                            + ::struct_cache_field::HeapSize::heap_size(&value)
+                    },
+                };
+                quote! {
+                    #map_path::insert_sized(
+                        #map,
+                        key,
+                        ::core::clone::Clone::clone(&value),
+                        #size,
+                        #max_bytes,
+                    );
+                }
+            }
+            None if args.backend.is_some() => quote! {
+                ::struct_cache_field::CacheMap::insert(
+                    #map,
+                    key,
+                    ::core::clone::Clone::clone(&value),
+                );
+            },
+            None => quote! {
+                #map_path::insert(
+                    #map,
+                    key,
+                    ::core::clone::Clone::clone(&value),
+                );
+            },
+        };
+        // Compute before borrowing the cell as well as the other cached methods.
+        new_fn.block = syn::parse2(quote! {{
+            #bypass
+            #key_before
+            if let ::core::option::Option::Some(map) =
+                ::struct_cache_field::CacheCell::get(&self.#field.#ident)
+            {
+                if let ::core::option::Option::Some(value) = #get {
+                    #verify_hit
+                    return value;
+                }
+            }
+            #key_after
+            let value: #return_ty = (|| #init)();
+            #unless
+            #unpin
+            #insert
+            value
+        }})
+        .unwrap();
+        return Ok((
+            new_fn.into(),
+            Some(cache_field(
+                fn_,
+                &args.field_attrs,
+                cell_path,
+                stats,
+                switch,
+                cache_ty,
+            )),
+            test_util_items,
+        ));
+    }
+    // With `weak`, store a weak reference and compute again if it is dropped.
+    if args.weak {
+        let cache_ty = quote! { ::struct_cache_field::WeakCache<#return_ty> };
+        new_fn.block = syn::parse2(quote! {{
+            #bypass
+            if let ::core::option::Option::Some(cache) =
+                ::struct_cache_field::CacheCell::get(&self.#field.#ident)
+            {
+                if let ::core::option::Option::Some(value) =
+                    ::struct_cache_field::WeakCache::get(cache)
+                {
+                    return value;
+                }
+            }
+            let value: #return_ty = (|| #init)();
+            #unless
+            #unpin
+            ::struct_cache_field::WeakCache::get_or_insert(
+                ::struct_cache_field::CacheCell::get_or_init(
+                    &#this.#field.#ident,
+                    ::struct_cache_field::WeakCache::new,
+                ),
+                value,
+            )
+        }})
+        .unwrap();
+        return Ok((
+            new_fn.into(),
+            Some(cache_field(
+                fn_,
+                &args.field_attrs,
+                cell_path,
+                stats,
+                switch,
+                cache_ty,
+            )),
+            test_util_items,
+        ));
+    }
+    // With `resettable`, store the value in a `RefCell` so that it can be reset through `&self`.
+    // Values are returned by clone, as the value can be reset later.
+    if args.resettable {
+        let cache_ty = quote! { ::struct_cache_field::Resettable<#return_ty> };
+        let verify_hit = verify_hit(quote! { &value });
+        new_fn.block = syn::parse2(quote! {{
+            #bypass
+            if let ::core::option::Option::Some(cache) =
+                ::struct_cache_field::CacheCell::get(&self.#field.#ident)
+            {
+                if let ::core::option::Option::Some(value) =
+                    ::struct_cache_field::Resettable::get(cache)
+                {
+                    #verify_hit
+                    return value;
+                }
+            }
+            let value: #return_ty = (|| #init)();
+            #unless
+            #unpin
+            ::struct_cache_field::Resettable::get_or_insert(
+                ::struct_cache_field::CacheCell::get_or_init(
+                    &#this.#field.#ident,
+                    ::struct_cache_field::Resettable::new,
+                ),
+                value,
+            )
+        }})
+        .unwrap();
+        return Ok((
+            new_fn.into(),
+            Some(cache_field(
+                fn_,
+                &args.field_attrs,
+                cell_path,
+                stats,
+                switch,
+                cache_ty,
+            )),
+            test_util_items,
+        ));
+    }
+    // With `scope`, store the value in the current `CacheScope`, which drops it when it ends. Values
+    // are returned by clone, as the scope can end later.
+    if args.scope {
+        let cache_ty = quote! { ::struct_cache_field::ScopedCache<#return_ty> };
+        let verify_hit = verify_hit(quote! { &value });
+        new_fn.block = syn::parse2(quote! {{
+            #bypass
+            if let ::core::option::Option::Some(cache) =
+                ::struct_cache_field::CacheCell::get(&self.#field.#ident)
+            {
+                if let ::core::option::Option::Some(value) =
+                    ::struct_cache_field::ScopedCache::get(cache)
+                {
+                    #verify_hit
+                    return value;
+                }
+            }
+            let value: #return_ty = (|| #init)();
+            #unless
+            #unpin
+            ::struct_cache_field::ScopedCache::get_or_insert(
+                ::struct_cache_field::CacheCell::get_or_init(
+                    &#this.#field.#ident,
+                    ::struct_cache_field::ScopedCache::new,
+                ),
+                value,
+            )
+        }})
+        .unwrap();
+        return Ok((
+            new_fn.into(),
+            Some(cache_field(
+                fn_,
+                &args.field_attrs,
+                cell_path,
+                stats,
+                switch,
+                cache_ty,
+            )),
+            test_util_items,
+        ));
+    }
+    // With `recompute_if_changed`, store clones of the fields with the value, and compute it again
+    // if they differ. Values are returned by clone, as the value can be replaced later.
+    if !args.recompute_if_changed.is_empty() {
+        let members = &args.recompute_if_changed;
+        let key_ty = field_types_placeholder(members);
+        let cache_ty = quote! { ::struct_cache_field::Tracked<#key_ty, #return_ty> };
+        let unchanged = unchanged_closure(members);
+        let verify_hit = verify_hit(quote! { &value });
+        new_fn.block = syn::parse2(quote! {{
+            #bypass
+            if let ::core::option::Option::Some(cache) =
+                ::struct_cache_field::CacheCell::get(&self.#field.#ident)
+            {
+                if let ::core::option::Option::Some(value) =
+                    ::struct_cache_field::Tracked::get(cache, #unchanged)
+                {
+                    #verify_hit
+                    return value;
+                }
+            }
+            // Clone the fields before the body, which may mutate them through `&mut self`.
+            let key = (#(::core::clone::Clone::clone(&self.#members),)*);
+            let value: #return_ty = (|| #init)();
+            #unless
+            #unpin
+            ::struct_cache_field::Tracked::insert(
+                ::struct_cache_field::CacheCell::get_or_init(
+                    &#this.#field.#ident,
+                    ::struct_cache_field::Tracked::new,
+                ),
+                key,
+                value,
+            )
+        }})
+        .unwrap();
+        return Ok((
+            new_fn.into(),
+            Some(cache_field(
+                fn_,
+                &args.field_attrs,
+                cell_path,
+                stats,
+                switch,
+                cache_ty,
+            )),
+            test_util_items,
+        ));
+    }
+    // With `stats`, also notify the observer.
+    let get_or_init = |f: TokenStream| {
+        if stats {
+            let key = cache_key(ident);
+            // Bind once, so that `T` is inferred from the cell rather than the return type.
+            quote! {{
+                let value = ::struct_cache_field::Counted::get_or_init_observed(
+                    &#this.#field.#ident,
+                    #key,
+                    #f,
+                );
+                value
+            }}
+        } else {
+            quote! {
+                ::struct_cache_field::CacheCell::get_or_init(&#this.#field.#ident, #f)
+            }
+        }
+    };
+    // With `test_util`, also allow to replace the value, unless it is stored per instantiation.
+    if test_util && type_params.is_empty() && !args.run_once {
+        let vis = &fn_.vis;
+        let injector = syn::Ident::new(&format!("inject_{}", ident.unraw()), ident.span());
+        let doc = format!(
+            "Fills the cache of [`Self::{ident}`] with the value, e.g. a fake one. Panics if it is already filled."
+        );
+        let message = format!("cache of `{}` is already filled", ident.unraw());
+        let cfgs = cfg_attrs(&fn_.attrs);
+        let value = if args.timed {
+            quote! { (value, ::core::time::Duration::ZERO) }
+        } else {
+            quote! { value }
+        };
+        test_util_items.push(syn::parse_quote! {
+            #[doc = #doc]
+            #cfgs
+            #vis fn #injector(&self, value: #cache_ty) {
+                let mut value = ::core::option::Option::Some(#value);
+                ::struct_cache_field::CacheCell::get_or_init(&self.#field.#ident, || {
+                    value.take().unwrap()
+                });
+                ::core::assert!(value.is_none(), #message);
+            }
+        });
+    }
+    // With `timed`, store the elapsed time next to the value.
+    let (cache_ty, init) = if args.timed {
+        (
+            quote! { (#cache_ty, ::core::time::Duration) },
+            // The closure confines `return` in the body.
+            quote! {
+                let start = ::std::time::Instant::now();
+                let value: #cache_ty = (|| #init)();
+                (value, start.elapsed())
+            },
+        )
+    } else {
+        (cache_ty, init)
+    };
+    // Compute before borrowing the cell, so that the body can borrow `self` mutably and can call
+    // cached methods, even itself. If the cell is filled meanwhile, the value computed first wins.
+    // A panic in the body leaves the cell untouched, so it is neither poisoned nor half-initialized.
+    let verify_hit = |lookup: TokenStream| {
+        let cached = if args.timed {
+            quote! { &cached.0 }
+        } else {
+            quote! { cached }
+        };
+        let verify_hit = verify_hit(cached);
+        if verify {
+            quote! {
+                if value.is_none() {
+                    if let ::core::option::Option::Some(cached) = #lookup {
+                        #verify_hit
+                    }
+                }
+            }
+        } else {
+            quote! {}
+        }
+    };
+    let get_or_init = if args.unchecked_cell && !stats && !switch {
+        // `UncheckedCell` is not wrapped, so a hit is read without checking again. Other cells are
+        // checked again by `get_or_init()`.
+        let verify_hit =
+            verify_hit(quote! { ::struct_cache_field::CacheCell::get(&self.#field.#ident) });
+        quote! {{
+            let value: ::core::option::Option<#cache_ty> =
+                if ::struct_cache_field::CacheCell::get(&self.#field.#ident).is_none() {
+                ::core::option::Option::Some((|| { #init })())
+            } else {
+                ::core::option::Option::None
+            };
+            #verify_hit
+            #unpin
+            match value {
+                ::core::option::Option::Some(value) => {
+                    ::struct_cache_field::UncheckedCell::insert(&#this.#field.#ident, value)
+                }
+                // SAFETY: The cell was filled above, and is not cleared while `self` is borrowed.
+                ::core::option::Option::None => unsafe {
+                    ::struct_cache_field::UncheckedCell::get_unchecked(&#this.#field.#ident)
+                },
+            }
+        }}
+    } else if type_params.is_empty() {
+        let get_or_init = get_or_init(quote! { move || value.unwrap() });
+        let verify_hit =
+            verify_hit(quote! { ::struct_cache_field::CacheCell::get(&self.#field.#ident) });
+        quote! {{
+            let value: ::core::option::Option<#cache_ty> =
+                if ::struct_cache_field::CacheCell::get(&self.#field.#ident).is_none() {
+                ::core::option::Option::Some((|| { #init })())
+            } else {
+                ::core::option::Option::None
+            };
+            #verify_hit
+            #unpin
+            #get_or_init
+        }}
+    } else {
+        let verify_hit = verify_hit(quote! {
+            ::struct_cache_field::CacheCell::get(&self.#field.#ident)
+                .and_then(|map| ::struct_cache_field::TypeMap::get(map, key))
+        });
+        quote! {{
+            let key = ::core::any::TypeId::of::<(#(#type_params,)*)>();
+            let value: ::core::option::Option<#cache_ty> =
+                if ::struct_cache_field::CacheCell::get(&self.#field.#ident)
+                    .and_then(|map| ::struct_cache_field::TypeMap::get(map, key))
+                    .is_none()
+            {
+                ::core::option::Option::Some((|| { #init })())
+            } else {
+                ::core::option::Option::None
+            };
+            #verify_hit
+            #unpin
+            ::struct_cache_field::TypeMap::get_or_init(
+                ::struct_cache_field::CacheCell::get_or_init(
+                    &#this.#field.#ident,
+                    ::struct_cache_field::TypeMap::new,
+                ),
+                key,
+                move || value.unwrap(),
+            )
+        }}
+    };
+    // Values computed while disabled are kept in the scratch, so that references to them stay valid.
+    let get_or_init = if switch {
+        let cell = switchable(&quote! { self });
+        let scratch_cell = switchable(&this);
+        let scratch = if type_params.is_empty() {
+            quote! { ::struct_cache_field::Switchable::push_scratch(cell, value) }
+        } else {
+            quote! {
+                ::struct_cache_field::TypeMap::get_or_init(
+                    ::struct_cache_field::Switchable::push_scratch(
+                        cell,
+                        ::struct_cache_field::TypeMap::new(),
+                    ),
+                    ::core::any::TypeId::of::<(#(#type_params,)*)>(),
+                    move || value,
+                )
+            }
+        };
+        quote! {{
+            if ::struct_cache_field::Switchable::is_enabled(#cell) {
+                #get_or_init
+            } else {
+                let value: #cache_ty = (|| { #init })();
+                #unpin
+                // Bind first, so that `T` is inferred from the cell rather than the return type.
+                let cell = #scratch_cell;
+                let value = #scratch;
+                value
+            }
+        }}
+    } else {
+        get_or_init
+    };
+    let body = match (args.timed, smart_pointer) {
+        (true, None) => quote! { &#get_or_init.0 },
+        (false, None) => get_or_init,
+        (true, Some(_)) => quote! { ::core::clone::Clone::clone(&#get_or_init.0) },
+        (false, Some(_)) => quote! { ::core::clone::Clone::clone(#get_or_init) },
+    };
+    let body = match (borrowed, &args.arena) {
+        (_, Some(_)) if smart_pointer.is_none() => quote! { *#body },
+        (Some(elem), _) => quote! { ::core::borrow::Borrow::<#elem>::borrow(#body) },
+        _ => body,
+    };
+    // With `run_once`, return `()` rather than `&()`.
+    let body = if args.run_once {
+        quote! { #body; }
+    } else {
+        body
+    };
+    new_fn.block = syn::parse2(quote! {{ #body }}).unwrap();
+    if smart_pointer.is_none() && !args.run_once && args.arena.is_none() {
+        new_fn.sig.output = syn::parse2(output).unwrap();
+    }
+    let cache_ty = if type_params.is_empty() {
+        cache_ty
+    } else {
+        quote! { ::struct_cache_field::TypeMap<#cache_ty> }
+    };
+    Ok((
+        new_fn.into(),
+        Some(cache_field(
+            fn_,
+            &args.field_attrs,
+            cell_path,
+            stats,
+            switch,
+            cache_ty,
+        )),
+        test_util_items,
+    ))
+}
+
+/// Returns `true` if the tokens mention `Self`.
+fn mentions_self_type(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == "Self",
+        proc_macro2::TokenTree::Group(group) => mentions_self_type(group.stream()),
+        _ => false,
+    })
+}
+
+/// Returns `false` for `extern "C" fn`, which is passed through untouched, as a method called across
+/// the ABI can't return a reference to the cache.
+fn is_cached_fn(fn_: &syn::ImplItemFn) -> bool {
+    fn_.sig.abi.is_none()
+}
+
+/// Returns a warning if the body of a cached method doesn't mention `self`, as every instance then
+/// caches the same value.
+///
+/// Methods with `const`, `shared_by` or `run_once` are not per-instance caches of a value, and ones
+/// taking arguments or type parameters cache a value per key, which `const` can't, so they are left
+/// as is. The warning is in the body, so `#[allow(deprecated)]` or `#[deny(deprecated)]`
+/// on the method or an outer item changes its level.
+fn gen_unused_self_warning(fn_: &syn::ImplItemFn) -> syn::Result<Option<TokenStream>> {
+    fn mentions_self(tokens: TokenStream) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ident) => ident == "self",
+            proc_macro2::TokenTree::Group(group) => mentions_self(group.stream()),
+            _ => false,
+        })
+    }
+
+    if !is_cached_fn(fn_) || fn_.sig.receiver().is_none() {
+        return Ok(None);
+    }
+    if fn_
+        .sig
+        .inputs
+        .iter()
+        .any(|arg| matches!(arg, syn::FnArg::Typed(_)))
+        || fn_.sig.generics.type_params().next().is_some()
+    {
+        return Ok(None);
+    }
+    let args = args::MethodArgs::from_attrs(&fn_.attrs)?;
+    if args.const_ || args.shared_by.is_some() || args.run_once {
+        return Ok(None);
+    }
+    if mentions_self(fn_.block.to_token_stream()) {
+        return Ok(None);
+    }
+    let message = format!(
+        "`{}` doesn't use `self`, so every instance caches the same value. Consider `#[cached(const)]` \
+         or a `static`, e.g. `std::sync::OnceLock`, instead.",
+        fn_.sig.ident.unraw(),
+    );
+    Ok(Some(gen_warning(&message, fn_.sig.ident.span())))
+}
+
+/// Returns a warning with `warn_mut_self`, or an error with `strict`, if a cached method takes
+/// `&mut self`, as mutations in the body are skipped on cache hits.
+///
+/// `run_once` is left as is, as skipping the body on later calls is what it is for.
+fn gen_mut_self_warning(
+    fn_: &syn::ImplItemFn,
+    warn: bool,
+    strict: bool,
+) -> syn::Result<Option<TokenStream>> {
+    if !(warn || strict) || !is_cached_fn(fn_) {
+        return Ok(None);
+    }
+    let Some(receiver) = fn_.sig.receiver() else {
+        return Ok(None);
+    };
+    if !matches!(receiver.ty.as_ref(), syn::Type::Reference(x) if x.mutability.is_some()) {
+        return Ok(None);
+    }
+    if args::MethodArgs::from_attrs(&fn_.attrs)?.run_once {
+        return Ok(None);
+    }
+    let message = format!(
+        "`{}` takes `&mut self`, but its body runs only until the value is cached, so later calls \
+         skip its mutations. Take `&self`, or use `#[cached(run_once)]` if the mutation is the \
+         point.",
+        fn_.sig.ident.unraw(),
+    );
+    if strict {
+        return Err(syn::Error::new_spanned(receiver, message));
+    }
+    Ok(Some(gen_warning(&message, fn_.sig.ident.span())))
+}
+
+/// Name of the placeholder of the type of a field in a cache field, which `#[add_cache_field]`
+/// replaces with the type in the struct.
+const FIELD_TYPE_PLACEHOLDER: &str = "__struct_cache_field_field_type";
+
+/// Returns the tuple type of the fields for `recompute_if_changed`, e.g. `(u64, String)`.
+///
+/// `#[impl_cached_method]` doesn't know the types of the fields, so they are written as
+/// placeholders, which `#[add_cache_field]` replaces.
+fn field_types_placeholder(members: &[syn::Member]) -> TokenStream {
+    let placeholder = syn::Ident::new(FIELD_TYPE_PLACEHOLDER, Span::call_site());
+    quote! { (#(#placeholder!(#members),)*) }
+}
+
+/// Replaces placeholders of `field_types_placeholder()` in cache fields with the types of the fields
+/// of the struct.
+fn replace_field_types(
+    mut cache_fields: Vec<syn::Field>,
+    fields: &syn::FieldsNamed,
+) -> syn::Result<Vec<syn::Field>> {
+    struct Replacer<'a> {
+        fields: &'a syn::FieldsNamed,
+        error: Option<syn::Error>,
+    }
+
+    impl VisitMut for Replacer<'_> {
+        fn visit_type_mut(&mut self, ty: &mut syn::Type) {
+            let syn::Type::Macro(x) = ty else {
+                return syn::visit_mut::visit_type_mut(self, ty);
+            };
+            if !x.mac.path.is_ident(FIELD_TYPE_PLACEHOLDER) {
+                return;
+            }
+            let member: syn::Member = match x.mac.parse_body() {
+                Ok(member) => member,
+                Err(e) => return self.error = Some(e),
+            };
+            let found = self.fields.named.iter().find(|field| match &member {
+                syn::Member::Named(ident) => field.ident.as_ref() == Some(ident),
+                syn::Member::Unnamed(_) => false,
+            });
+            match found {
+                Some(field) => *ty = field.ty.clone(),
+                None => {
+                    self.error = Some(syn::Error::new(
+                        Span::call_site(),
+                        format!(
+                            "no field `{}` of `recompute_if_changed(...)` in the struct",
+                            quote! { #member },
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut replacer = Replacer {
+        fields,
+        error: None,
+    };
+    for field in &mut cache_fields {
+        replacer.visit_type_mut(&mut field.ty);
+    }
+    match replacer.error {
+        Some(e) => Err(e),
+        None => Ok(cache_fields),
+    }
+}
+
+/// Returns a closure taking the stored clones of the fields for `recompute_if_changed` and
+/// returning `true` if they equal the fields.
+fn unchanged_closure(members: &[syn::Member]) -> TokenStream {
+    let indices = (0..members.len()).map(syn::Index::from);
+    quote! { |key| #(key.#indices == self.#members)&&* }
+}
+
+/// Returns `true` if a cached value of the method can be compared with a fresh one.
+///
+/// Only `&self` is allowed, as computing again through `&mut self` or an owned pointer may have
+/// other effects. Boxed values and ones in an arena differ from what the body returns.
+fn is_verifiable(fn_: &syn::ImplItemFn, args: &args::MethodArgs) -> bool {
+    !args.no_verify
+        && !args.const_
+        && !args.run_once
+        && args.arena.is_none()
+        && args.box_dyn.is_none()
+        && !matches!(&fn_.sig.output, syn::ReturnType::Type(_, ty) if matches!(ty.as_ref(), syn::Type::ImplTrait(_)))
+        && matches!(fn_.sig.receiver(), Some(x) if x.reference.is_some() && x.mutability.is_none())
+}
+
+/// Generates a hidden method called by `assert_caches_fresh!`, which panics if any cached value
+/// differs from a fresh one.
+///
+/// Methods taking arguments or type parameters, whose values are not single, and ones not
+/// [`is_verifiable()`] are skipped.
+fn gen_assert_caches_fresh(
+    items: &[syn::ImplItem],
+    field: &syn::Ident,
+) -> syn::Result<syn::ImplItem> {
+    let mut checks = vec![];
+    for item in items {
+        let syn::ImplItem::Fn(fn_) = item else {
+            continue;
+        };
+        let args = args::MethodArgs::from_attrs(&fn_.attrs)?;
+        if !is_cached_fn(fn_)
+            || !is_verifiable(fn_, &args)
+            || fn_.sig.unsafety.is_some()
+            || args.weak
+            || args.shared_by.is_some()
+            || fn_.sig.inputs.len() != 1
+            || !fn_.sig.generics.params.is_empty()
+        {
+            continue;
+        }
+        let ident = &fn_.sig.ident;
+        let name = ident.unraw().to_string();
+        let cfgs = cfg_attrs(&fn_.attrs);
+        let block = &fn_.block;
+        let cached = if args.timed {
+            quote! { &cached.0 }
+        } else if args.resettable || args.scope || !args.recompute_if_changed.is_empty() {
+            quote! { &cached }
+        } else {
+            quote! { cached }
+        };
+        let lookup = if args.resettable {
+            quote! {
+                ::struct_cache_field::CacheCell::get(&self.#field.#ident)
+                    .and_then(::struct_cache_field::Resettable::get)
+            }
+        } else if args.scope {
+            quote! {
+                ::struct_cache_field::CacheCell::get(&self.#field.#ident)
+                    .and_then(::struct_cache_field::ScopedCache::get)
+            }
+        } else if !args.recompute_if_changed.is_empty() {
+            let unchanged = unchanged_closure(&args.recompute_if_changed);
+            quote! {
+                ::struct_cache_field::CacheCell::get(&self.#field.#ident)
+                    .and_then(|cache| ::struct_cache_field::Tracked::get(cache, #unchanged))
+            }
+        } else {
+            quote! { ::struct_cache_field::CacheCell::get(&self.#field.#ident) }
+        };
+        checks.push(quote! {
+            #cfgs
+            if let ::core::option::Option::Some(cached) = #lookup {
+                let fresh = (|| #block)();
+                (&::struct_cache_field::__private::Verify(#cached, &fresh, #name)).verify();
+            }
+        });
+    }
+    Ok(syn::parse_quote! {
+        #[doc(hidden)]
+        pub fn __struct_cache_field_assert_caches_fresh(&self) {
+            #[allow(unused_imports)]
+            use ::struct_cache_field::__private::{VerifyEq as _, VerifySkip as _};
+            #(#checks)*
+        }
+    })
+}
+
+/// Generates a function asserting that the cache of each cached method is `Send + Sync`, so that
+/// the error names the cache making the struct thread-unsafe, e.g. `OnceCell<u64>`.
+fn gen_assert_send_sync(
+    items: &[syn::ImplItem],
+    fields: &[Option<TokenStream>],
+) -> syn::Result<syn::ImplItem> {
+    let mut checks = vec![];
+    for (item, field) in items.iter().zip(fields) {
+        let (syn::ImplItem::Fn(fn_), Some(field)) = (item, field) else {
+            continue;
+        };
+        let field = syn::Field::parse_named.parse2(field.clone())?;
+        // Cells wrapped with `stats` or `switch` are `Send + Sync` if the inner cell is, which is
+        // named in the error instead, so that it doesn't depend on features.
+        let mut ty = &field.ty;
+        while let syn::Type::Path(path) = ty {
+            let last = path.path.segments.last().unwrap();
+            match &last.arguments {
+                syn::PathArguments::AngleBracketed(args)
+                    if last.ident == "Counted" || last.ident == "Switchable" =>
+                {
+                    let Some(syn::GenericArgument::Type(inner)) = args.args.last() else {
+                        break;
+                    };
+                    ty = inner;
+                }
+                _ => break,
+            }
+        }
+        let cfgs = cfg_attrs(&fn_.attrs);
+        // The argument is spanned by the method, so that the error points at it.
+        let cache = quote_spanned! { fn_.sig.ident.span() => cache };
+        checks.push(quote! {
+            #cfgs
+            let _ = |#cache: &#ty| assert_send_sync(#cache);
+        });
+    }
+    Ok(syn::parse_quote! {
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        fn __struct_cache_field_assert_send_sync() {
+            fn assert_send_sync<T>(_: &T)
+            where
+                T: ?::core::marker::Sized + ::core::marker::Send + ::core::marker::Sync,
+            {
+            }
+            #(#checks)*
+        }
+    })
+}
+
+/// Generates the cache field of a cached method.
+fn cache_field(
+    fn_: &syn::ImplItemFn,
+    field_attrs: &[syn::Meta],
+    cell_path: &syn::Path,
+    stats: bool,
+    switch: bool,
+    cache_ty: TokenStream,
+) -> TokenStream {
+    let ident = &fn_.sig.ident;
+    // With `switch`, wrap the cell so that it can be disabled.
+    let cell_ty = if switch {
+        quote! { ::struct_cache_field::Switchable<#cache_ty, #cell_path<#cache_ty>> }
+    } else {
+        quote! { #cell_path<#cache_ty> }
+    };
+    // With `stats`, wrap the cell to count hits and misses.
+    let cell_ty = if stats {
+        quote! { ::struct_cache_field::Counted<#cache_ty, #cell_ty> }
+    } else {
+        cell_ty
+    };
+    // The cache exists only if the method does.
+    let cfgs = cfg_attrs(&fn_.attrs);
+    quote! {
+        #cfgs
+        #(#[#field_attrs])*
+        #ident: #cell_ty
+    }
+}
+
+/// Returns the field of the cache struct counting runs of computations with `test_util`.
+fn init_counts_field(span: Span) -> syn::Ident {
+    syn::Ident::new("__init_counts__", span)
+}
+
+/// Returns fields of the cache struct other than caches and phantoms, which have `Default`.
+fn hidden_fields(test_util: bool) -> (Vec<syn::Ident>, Vec<TokenStream>) {
+    if test_util {
+        let ident = init_counts_field(Span::call_site());
+        let field = quote! { #ident: ::struct_cache_field::InitCounts };
+        (vec![ident], vec![field])
+    } else {
+        (vec![], vec![])
+    }
+}
+
+/// Returns `false` for `OnceCell` of `core` or `once_cell::unsync` and `UncheckedCell`, but `true`
+/// for `once_cell::sync::OnceCell` and the other cells.
+fn is_thread_safe_cell(cell_path: &syn::Path) -> bool {
+    let ident = args::last_ident(cell_path);
+    ident != "UncheckedCell" && ident != "OnceCell"
+        || cell_path
+            .segments
+            .iter()
+            .any(|segment| segment.ident == "sync")
+}
+
+/// Returns `Rc` or `Arc` if the receiver is `self: Rc<Self>` or `self: Arc<Self>`.
+fn smart_pointer_receiver(receiver: &syn::Receiver) -> Option<&syn::Ident> {
+    receiver.colon_token?;
+    let syn::Type::Path(ty) = receiver.ty.as_ref() else {
+        return None;
+    };
+    let ident = &ty.path.segments.last()?.ident;
+    (ident == "Rc" || ident == "Arc").then_some(ident)
+}
+
+/// Returns an expression converting `self: Pin<&mut Self>` or `self: Pin<&Self>` into `&Self`.
+fn unpin_receiver(receiver: &syn::Receiver) -> Option<TokenStream> {
+    receiver.colon_token?;
+    let syn::Type::Path(ty) = receiver.ty.as_ref() else {
+        return None;
+    };
+    let segment = ty.path.segments.last()?;
+    if segment.ident != "Pin" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    let Some(syn::GenericArgument::Type(syn::Type::Reference(reference))) = args.args.first()
+    else {
+        return None;
+    };
+    Some(if reference.mutability.is_some() {
+        quote! { ::core::pin::Pin::get_ref(::core::pin::Pin::into_ref(self)) }
+    } else {
+        quote! { ::core::pin::Pin::get_ref(self) }
+    })
+}
+
+/// `CacheKey` of the method, used with `stats`.
+fn cache_key(method: &syn::Ident) -> TokenStream {
+    let method = method.unraw().to_string();
+    quote! {
+        ::struct_cache_field::CacheKey {
+            ty: ::core::any::type_name::<Self>(),
+            method: #method,
+        }
+    }
+}
+
+/// Returns a statement resetting the cache of the method, which also notifies the observer with
+/// `stats`.
+fn reset_cache(field: &syn::Ident, method: &syn::Ident, stats: bool) -> TokenStream {
+    if stats {
+        let key = cache_key(method);
+        quote! {
+            ::struct_cache_field::Counted::reset_observed(&mut self.#field.#method, #key);
+        }
+    } else {
+        quote! { ::struct_cache_field::CacheCell::reset(&mut self.#field.#method); }
+    }
+}
+
+/// Generates `fn {method}_compute_time(&self) -> Option<Duration>` for a method with
+/// `#[cached(timed)]`.
+fn gen_compute_time_accessor(
+    item: &syn::ImplItem,
+    field: &syn::Ident,
+) -> syn::Result<Option<syn::ImplItem>> {
+    let syn::ImplItem::Fn(fn_) = item else {
+        return Ok(None);
+    };
+    if !args::MethodArgs::from_attrs(&fn_.attrs)?.timed {
+        return Ok(None);
+    }
+    let vis = &fn_.vis;
+    let ident = &fn_.sig.ident;
+    let accessor = syn::Ident::new(&format!("{}_compute_time", ident.unraw()), ident.span());
+    let doc = format!("Returns how long the computation of [`Self::{ident}`] took, if cached.");
+    let cfgs = cfg_attrs(&fn_.attrs);
+    Ok(Some(syn::parse_quote! {
+        #[doc = #doc]
+        #cfgs
+        #vis fn #accessor(&self) -> ::core::option::Option<::core::time::Duration> {
+            ::struct_cache_field::CacheCell::get(&self.#field.#ident).map(|(_, elapsed)| *elapsed)
+        }
+    }))
+}
+
+/// Generates docs appended to a cached method with `doc_note`, describing when the value is
+/// computed and invalidated.
+fn gen_doc_note(fn_: &syn::ImplItemFn) -> syn::Result<Vec<syn::Attribute>> {
+    let args = args::MethodArgs::from_attrs(&fn_.attrs)?;
+    let name = fn_.sig.ident.unraw();
+    let computed = if args.const_ {
+        String::from("The value is evaluated at compile time, and the method returns it.")
+    } else if args.run_once {
+        String::from("The body runs only on the first call. Later calls do nothing.")
+    } else if let Some(capacity) = &args.capacity {
+        format!(
+            "The value is memoized by arguments, keeping values for at most `{}` distinct arguments, \
+             and a clone of it is returned.",
+            quote! { #capacity }
+        )
+    } else if args.shared_by.is_some() {
+        String::from(
+            "The value is shared by instances with the same key in a process-wide map, and a \
+             clone of it is returned.",
+        )
+    } else if args.weak {
+        String::from("The value is cached as a weak reference, and computed again once no one else holds it.")
+    } else if args.resettable {
+        String::from(
+            "The value is computed on the first call, and a clone of it is returned afterwards.",
+        )
+    } else if args.scope {
+        String::from(
+            "The value is computed on the first call in the current `CacheScope`, and a clone of \
+             it is returned afterwards. Without a scope, it is computed on every call.",
+        )
+    } else if !args.recompute_if_changed.is_empty() {
+        String::from(
+            "The value is computed on the first call, and a clone of it is returned afterwards.",
+        )
+    } else if fn_.sig.generics.type_params().next().is_some() {
+        String::from(
+            "The value is computed on the first call per instantiation of the type parameters, \
+           and a reference to it is returned afterwards.",
+        )
+    } else if fn_
+        .sig
+        .receiver()
+        .and_then(smart_pointer_receiver)
+        .is_some()
+    {
+        String::from(
+            "The value is computed on the first call, and a clone of it is returned afterwards.",
+        )
+    } else {
+        String::from("The value is computed on the first call, and a reference to it is returned afterwards.")
+    };
+    let invalidated = if args.shared_by.is_some() {
+        String::from(
+            "It is not updated on changes of the fields, and `HasCacheFields::reset_caches()` \
+             doesn't affect it.",
+        )
+    } else if args.const_ {
+        String::from("It has no cache, so `HasCacheFields::reset_caches()` doesn't affect it.")
+    } else if args.scope {
+        String::from(
+            "It is not updated on changes of the fields, but computed again after the scope ends \
+             or `HasCacheFields::reset_caches()`.",
+        )
+    } else if !args.recompute_if_changed.is_empty() {
+        let fields = args
+            .recompute_if_changed
+            .iter()
+            .map(|member| format!("`{}`", quote! { #member }))
+            .join(", ");
+        format!(
+            "It is computed again when any of {fields} changes, or after \
+             `HasCacheFields::reset_caches()`."
+        )
+    } else if args.resettable {
+        format!(
+            "It is not updated on changes of the fields, but computed again after \
+             `invalidate_{name}()` or `HasCacheFields::reset_caches()`."
+        )
+    } else {
+        String::from(
+            "It is not updated on changes of the fields, but computed again after \
+           `HasCacheFields::reset_caches()`.",
+        )
+    };
+    let mut docs = vec![];
+    if fn_.attrs.iter().any(|attr| attr.path().is_ident("doc")) {
+        docs.push(String::from(""));
+    }
+    docs.extend([
+        String::from(" # Caching"),
+        String::from(""),
+        format!(" {computed} {invalidated}"),
+    ]);
+    Ok(docs
+        .into_iter()
+        .map(|doc| syn::parse_quote! { #[doc = #doc] })
+        .collect())
+}
+
+/// Generates `fn invalidate_{method}(&self)` for a method with `#[cached(resettable)]`.
+fn gen_invalidator(item: &syn::ImplItem, field: &syn::Ident) -> syn::Result<Option<syn::ImplItem>> {
+    let syn::ImplItem::Fn(fn_) = item else {
+        return Ok(None);
+    };
+    if !args::MethodArgs::from_attrs(&fn_.attrs)?.resettable {
+        return Ok(None);
+    }
+    let vis = &fn_.vis;
+    let ident = &fn_.sig.ident;
+    let invalidator = syn::Ident::new(&format!("invalidate_{}", ident.unraw()), ident.span());
+    let doc = format!(
+        "Resets the cache of [`Self::{ident}`] through a shared reference, so that it is computed again on the next call."
+    );
+    let cfgs = cfg_attrs(&fn_.attrs);
+    Ok(Some(syn::parse_quote! {
+        #[doc = #doc]
+        #cfgs
+        #vis fn #invalidator(&self) {
+            if let ::core::option::Option::Some(cache) =
+                ::struct_cache_field::CacheCell::get(&self.#field.#ident)
+            {
+                ::struct_cache_field::Resettable::reset(cache);
+            }
+        }
+    }))
+}
+
+/// Generates `fn {method}_fresh(...)` for a method with `#[cached(fresh)]`, which runs the body
+/// with the same arguments and returns its value, neither reading nor filling the cache.
+fn gen_fresh(item: &syn::ImplItem) -> syn::Result<Option<syn::ImplItem>> {
+    let syn::ImplItem::Fn(fn_) = item else {
+        return Ok(None);
+    };
+    let args = args::MethodArgs::from_attrs(&fn_.attrs)?;
+    if !args.fresh {
+        return Ok(None);
+    }
+    let ident = &fn_.sig.ident;
+    let block = &fn_.block;
+    // Return what the body returns, e.g. `String` for `&str`, rather than a reference to the cache.
+    let (output, block) = match (&fn_.sig.output, &args.arena) {
+        (syn::ReturnType::Type(_, return_ty), Some(arena)) => (
+            quote! { -> #return_ty },
+            quote! {{
+                let value: #return_ty = (#arena).alloc((|| #block)());
+                value
+            }},
+        ),
+        (syn::ReturnType::Type(_, return_ty), None) => match return_ty.as_ref() {
+            syn::Type::Reference(x)
+                if args.box_dyn.is_none()
+                    && x.mutability.is_none()
+                    && x.lifetime.as_ref().map_or(true, |x| x.ident == "_") =>
+            {
+                let elem = &x.elem;
+                (
+                    quote! { -> <#elem as ::struct_cache_field::__private::ToOwned>::Owned },
+                    quote! { #block },
+                )
+            }
+            _ => (quote! { -> #return_ty }, quote! { #block }),
+        },
+        (syn::ReturnType::Default, _) => (quote! {}, quote! { #block }),
+    };
+    let vis = &fn_.vis;
+    let mut sig = fn_.sig.clone();
+    sig.ident = syn::Ident::new(&format!("{}_fresh", ident.unraw()), ident.span());
+    sig.output = syn::parse2(output)?;
+    let doc = format!(
+        "Computes the value of [`Self::{ident}`] without the cache, neither reading nor filling it."
+    );
+    let cfgs = cfg_attrs(&fn_.attrs);
+    Ok(Some(syn::parse_quote! {
+        #[doc = #doc]
+        #cfgs
+        #vis #sig #block
+    }))
+}
+
+/// Generates `fn {method}_refresh(&mut self)` for a method with `#[cached(refresh)]`, which resets
+/// the cache and calls the rewritten method `item`, returning what it returns.
+fn gen_refresher(
+    item: &syn::ImplItem,
+    original: &syn::ImplItem,
+    field: &syn::Ident,
+    stats: bool,
+) -> syn::Result<Option<syn::ImplItem>> {
+    let (syn::ImplItem::Fn(fn_), syn::ImplItem::Fn(original)) = (item, original) else {
+        return Ok(None);
+    };
+    let args = args::MethodArgs::from_attrs(&original.attrs)?;
+    if !args.refresh {
+        return Ok(None);
+    }
+    let vis = &fn_.vis;
+    let ident = &fn_.sig.ident;
+    let mut sig = fn_.sig.clone();
+    sig.ident = syn::Ident::new(&format!("{}_refresh", ident.unraw()), ident.span());
+    sig.inputs = syn::parse_quote! { &mut self };
+    let params = fn_
+        .sig
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            syn::GenericParam::Type(x) => Some(&x.ident),
+            syn::GenericParam::Const(x) => Some(&x.ident),
+            syn::GenericParam::Lifetime(_) => None,
+        })
+        .collect_vec();
+    let turbofish = if params.is_empty() {
+        quote! {}
+    } else {
+        quote! { ::<#(#params),*> }
+    };
+    let reset = reset_cache(field, ident, stats);
+    let call = quote! { self.#ident #turbofish() };
+    let call = if fn_.sig.unsafety.is_some() {
+        quote! { unsafe { #call } }
+    } else {
+        call
+    };
+    let doc = format!("Computes the value of [`Self::{ident}`] again, overwriting the cache.");
+    let cfgs = cfg_attrs(&fn_.attrs);
+    Ok(Some(syn::parse_quote! {
+        #[doc = #doc]
+        #cfgs
+        #vis #sig {
+            #reset
+            #call
+        }
+    }))
+}
+
+/// Generates an accessor for each value of a method with `#[cached(produces(...))]`, which returns
+/// a reference to the element of the cached tuple.
+fn gen_produced_accessors(item: &syn::ImplItem) -> syn::Result<Vec<syn::ImplItem>> {
+    let syn::ImplItem::Fn(fn_) = item else {
+        return Ok(vec![]);
+    };
+    let produces = args::MethodArgs::from_attrs(&fn_.attrs)?.produces;
+    if produces.is_empty() {
+        return Ok(vec![]);
+    }
+    // Checked by `rewrite_cached_method()`.
+    let syn::ReturnType::Type(_, return_ty) = &fn_.sig.output else {
+        unreachable!();
+    };
+    let syn::Type::Tuple(tuple) = return_ty.as_ref() else {
+        unreachable!();
+    };
+    let method = &fn_.sig.ident;
+    let cfgs = cfg_attrs(&fn_.attrs);
+    let accessors = produces
+        .iter()
+        .zip(&tuple.elems)
+        .enumerate()
+        .map(|(i, (ident, ty))| {
+            let index = syn::Index::from(i);
+            let doc = format!("Returns `{ident}` computed by [`Self::{method}`].");
+            let mut accessor = fn_.clone();
+            accessor.attrs = syn::Attribute::parse_outer
+                .parse2(quote! {
+                    #[doc = #doc]
+                    #cfgs
+                })
+                .unwrap();
+            accessor.sig.ident = ident.clone();
+            accessor.sig.output = syn::parse_quote! { -> &#ty };
+            accessor.block = syn::parse_quote! {{
+                &Self::#method(self).#index
+            }};
+            accessor.into()
+        })
+        .collect();
+    Ok(accessors)
+}
+
+#[proc_macro_attribute]
+pub fn add_cache_field(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as syn::Item);
+
+    match add_cache_field_aux(&args.into(), &input) {
+        Ok(x) => x.into(),
+        Err(e) => TokenStream::from_iter([e.into_compile_error(), (quote! { #input })]).into(),
+    }
+}
+
+/// Returns `#[repr(...)]` containing `packed` or `packed(N)` if any.
+fn find_packed_repr(attrs: &[syn::Attribute]) -> syn::Result<Option<&syn::Attribute>> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        let mut packed = false;
+        attr.parse_nested_meta(|meta| {
+            packed |= meta.path.is_ident("packed");
+            // Skip arguments like `align(64)`.
+            if meta.input.peek(syn::token::Paren) {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<TokenStream>()?;
+            }
+            Ok(())
+        })?;
+        if packed {
+            return Ok(Some(attr));
+        }
+    }
+    Ok(None)
+}
+
+fn add_cache_field_aux(args: &TokenStream, input: &syn::Item) -> syn::Result<TokenStream> {
+    let args = args::StructArgs::parse(args)?;
+
+    let syn::Item::Struct(struct_) = input else {
+        return Err(syn::Error::new(input.span(), "expected `struct ...`"));
+    };
+    let syn::Fields::Named(fields) = &struct_.fields else {
+        return Err(syn::Error::new(
+            struct_.fields.span(),
+            "expected named fields",
+        ));
+    };
+
+    // Cached methods take references into the injected field, which may be unaligned in a packed
+    // struct.
+    if let Some(attr) = find_packed_repr(&struct_.attrs)? {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "`#[repr(packed)]` can't be used with `#[add_cache_field]`, as cached methods take references into the cache field, which may be unaligned. maybe wrap it with `Cached` and use `#[cached_ext]` instead?",
+        ));
+    }
+
+    // Define a new struct holding caches. This makes initialization easy.
+    let cache_fields_struct_name = args.name.unwrap_or_else(|| {
+        syn::Ident::new(
+            &format!(
+                "__struct_cache_field__{}CacheFields",
+                &struct_.ident.to_string()
+            ),
+            Span::call_site(),
+        )
+    });
+    // `#[non_exhaustive]` structs can't be constructed literally in other crates.
+    let non_exhaustive = struct_
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("non_exhaustive"));
+    // By default, follow the struct if all fields are as visible as the struct itself, so that the
+    // struct can be constructed wherever its fields can be. For `#[non_exhaustive]` ones, that is
+    // at most within the crate.
+    let vis = args.vis.unwrap_or_else(|| {
+        let struct_vis = struct_.vis.to_token_stream().to_string();
+        if fields
+            .named
+            .iter()
+            .all(|field| field.vis.to_token_stream().to_string() == struct_vis)
+        {
+            match &struct_.vis {
+                syn::Visibility::Public(_) if non_exhaustive => syn::parse_quote! { pub(crate) },
+                vis => vis.clone(),
+            }
+        } else {
+            syn::Visibility::Inherited
+        }
+    });
+    let withdrawn = match &args.from {
+        Some(from) => storage::withdraw_cache_fields(from, &struct_.generics)?,
+        None => storage::withdraw_cache_fields(&struct_.ident, &struct_.generics)?,
+    };
+    // With `tolerant`, the struct gets an empty cache struct, e.g. if only it is expanded by
+    // `cargo expand`.
+    let tolerant = args.tolerant || config::tolerant()?;
+    let not_defined_warning = match (&withdrawn, tolerant) {
+        (Some(_), _) => quote! {},
+        (None, true) => gen_warning(
+            "cached methods not defined, so the cache struct is empty. maybe forgot to `#[struct_cache_field::impl_cached_method]`?",
+            Span::call_site(),
+        ),
+        (None, false) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "cached methods not defined. maybe forgot to `#[struct_cache_field::impl_cached_method]`?",
+            ))
+        }
+    };
+    let storage::CacheFields {
+        field,
+        fields: cache_fields,
+        bounds,
+        dependencies,
+        return_types,
+        stats,
+        switch,
+        test_util,
+    } = match withdrawn {
+        Some(x) => x,
+        None => storage::CacheFields {
+            field: match &args.field {
+                Some(x) => x.clone(),
+                None => config::field()?,
+            },
+            fields: vec![],
+            bounds: vec![],
+            dependencies: vec![],
+            return_types: vec![],
+            stats: false,
+            switch: false,
+            test_util: false,
+        },
+    };
+    // Cache fields naming associated types, e.g. `T::Output`, need the bounds of the impl, e.g.
+    // `T: Op`, on both the struct and the cache struct.
+    let mut struct_with_bounds;
+    let struct_ = if bounds.is_empty() {
+        struct_
+    } else {
+        struct_with_bounds = struct_.clone();
+        struct_with_bounds
+            .generics
+            .make_where_clause()
+            .predicates
+            .extend(bounds);
+        &struct_with_bounds
+    };
+    if let Some(arg_field) = &args.field {
+        if *arg_field != field {
+            return Err(syn::Error::new(
+                arg_field.span(),
+                format!(
+                    "field name differs from `#[struct_cache_field::impl_cached_method]`, which uses `{field}`. maybe forgot to add `field = \"{arg_field}\"` to it?"
+                ),
+            ));
+        }
+    }
+    if let Some(existing) = fields
+        .named
+        .iter()
+        .find(|x| x.ident.as_ref() == Some(&field))
+    {
+        let mut err = syn::Error::new_spanned(
+            existing.ident.as_ref().unwrap(),
+            format!("field `{field}` collides with the cache field injected by `#[struct_cache_field::add_cache_field]`. maybe rename it or give another name with `field = \"...\"`?"),
+        );
+        err.combine(syn::Error::new(
+            Span::call_site(),
+            format!("cache field `{field}` is injected here"),
+        ));
+        return Err(err);
+    }
+    let cache_fields = replace_field_types(cache_fields, fields)?;
+    // Extract type parameter and and make phantom fields for the struct.
+    //
+    // It is easier to use phantom fields rather than checking each type parameter is actually used.
+    // We use them for `syn::GenericParam::Type` and for lifetimes that appear in cache fields, e.g.
+    // `&'a str`. Other lifetimes are dropped.
+    //
+    // Type parameters are wrapped as `PhantomData<fn() -> T>` so that the phantom doesn't affect
+    // auto traits (`Send`, `Sync`) and drop check of the original struct.
+    //
+    // Bounds of type parameters are dropped, so that naming the cache struct doesn't require
+    // bounds the caches don't need, except ones to name associated types, e.g. `T: Op` for
+    // `T::Output`, ones of allocators, `?Sized`, and ones given by `bound = "..."`. They are moved to the where-clause,
+    // as the cache struct is defined with `ty_generics`.
+    let mut bases = projection_bases(&struct_.generics, &cache_fields);
+    // Types like `Box<T, A>` need `A: Allocator` to be named.
+    bases.extend(allocator_params(&struct_.generics));
+    let mut generics = struct_.generics.clone();
+    let mut inline_bounds: Vec<syn::WherePredicate> = vec![];
+    generics.params = generics
+        .params
+        .into_iter()
+        .filter_map(|param| match param {
+            syn::GenericParam::Lifetime(x) => Some(syn::GenericParam::Lifetime(x)),
+            syn::GenericParam::Const(_) => None,
+            syn::GenericParam::Type(mut x) => {
+                let ident = &x.ident;
+                let bounds = std::mem::take(&mut x.bounds)
+                    .into_iter()
+                    .filter(|bound| {
+                        bases.contains(&ident.to_string())
+                            || matches!(
+                                bound,
+                                syn::TypeParamBound::Trait(syn::TraitBound {
+                                    modifier: syn::TraitBoundModifier::Maybe(_),
+                                    ..
+                                })
+                            )
+                    })
+                    .collect::<Punctuated<_, syn::Token![+]>>();
+                if !bounds.is_empty() {
+                    inline_bounds.push(syn::parse_quote! { #ident: #bounds });
+                }
+                Some(syn::GenericParam::Type(x))
+            }
+        })
+        .collect();
+    let where_clause = generics.make_where_clause();
+    let predicates = inline_bounds
+        .into_iter()
+        .chain(
+            std::mem::take(&mut where_clause.predicates)
+                .into_iter()
+                .filter(|pred| match pred {
+                    syn::WherePredicate::Lifetime(_) => true,
+                    pred => bounds_base(pred, &bases),
+                }),
+        )
+        .collect_vec();
+    // Lifetimes are kept if cache fields, kept trait bounds, e.g. `T: Parse<'a>` for `T::Output`,
+    // or `bound = "..."` use them. Bounds on the others, e.g. `'a: 'b` or `T: 'b`, are dropped with
+    // them.
+    let mut used_lifetimes = collect_lifetimes(&cache_fields);
+    used_lifetimes.extend(collect_bound_lifetimes(&predicates));
+    let mut collector = LifetimeCollector(used_lifetimes);
+    for pred in &args.bound {
+        collector.visit_where_predicate(pred);
+    }
+    let used_lifetimes = collector.0;
+    let predicates = predicates
+        .into_iter()
+        .filter_map(|pred| match pred {
+            syn::WherePredicate::Lifetime(mut x) => {
+                x.bounds = std::mem::take(&mut x.bounds)
+                    .into_iter()
+                    .filter(|bound| used_lifetimes.contains(bound))
+                    .collect();
+                (used_lifetimes.contains(&x.lifetime) && !x.bounds.is_empty())
+                    .then_some(syn::WherePredicate::Lifetime(x))
+            }
+            syn::WherePredicate::Type(mut x) => {
+                x.bounds = std::mem::take(&mut x.bounds)
+                    .into_iter()
+                    .filter(|bound| match bound {
+                        syn::TypeParamBound::Lifetime(lifetime) => {
+                            used_lifetimes.contains(lifetime)
+                        }
+                        _ => true,
+                    })
+                    .collect();
+                (!x.bounds.is_empty()).then_some(syn::WherePredicate::Type(x))
+            }
+            pred => Some(pred),
+        })
+        .collect_vec();
+    generics.params = std::mem::take(&mut generics.params)
+        .into_iter()
+        .filter_map(|param| match param {
+            syn::GenericParam::Lifetime(mut x) => {
+                x.bounds = std::mem::take(&mut x.bounds)
+                    .into_iter()
+                    .filter(|bound| used_lifetimes.contains(bound))
+                    .collect();
+                if x.bounds.is_empty() {
+                    x.colon_token = None;
+                }
+                used_lifetimes
+                    .contains(&x.lifetime)
+                    .then_some(syn::GenericParam::Lifetime(x))
+            }
+            param => Some(param),
+        })
+        .collect();
+    generics.make_where_clause().predicates = predicates
+        .into_iter()
+        .chain(args.bound.iter().cloned())
+        .collect();
+    let phantom_idents = (0..generics.params.len())
+        .map(|i| syn::Ident::new(&format!("_phantom{i}"), Span::call_site()))
+        .collect_vec();
+    let phantom_fields = generics
+        .params
+        .iter()
+        .zip(&phantom_idents)
+        .map(|(param, ident)| {
+            let ty = match param {
+                syn::GenericParam::Lifetime(x) => {
+                    let lifetime = &x.lifetime;
+                    quote! { &#lifetime () }
+                }
+                syn::GenericParam::Type(x) => {
+                    let ident = &x.ident;
+                    quote! { fn() -> #ident }
+                }
+                syn::GenericParam::Const(_) => unreachable!(),
+            };
+            quote! {
+                #ident: ::core::marker::PhantomData<#ty>
+            }
+        })
+        .collect_vec();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let cache_field_idents = cache_fields.iter().map(|x| x.ident.clone()).collect_vec();
+    let cache_field_cfgs = cache_fields
+        .iter()
+        .map(|x| cfg_attrs(&x.attrs))
+        .collect_vec();
+    let snapshot = match &args.snapshot {
+        Some(snapshot) => {
+            let snapshot = snapshot.clone().unwrap_or_else(|| {
+                syn::Ident::new(
+                    &format!("{}CacheSnapshot", struct_.ident),
+                    Span::call_site(),
+                )
+            });
+            Some(gen_snapshot(
+                struct_,
+                &field,
+                &snapshot,
+                &generics,
+                &cache_fields,
+                &phantom_idents,
+                &phantom_fields,
+            )?)
+        }
+        None => None,
+    };
+    let serde_persist = if args.serde_persist {
+        Some(gen_serde_persist(
+            &cache_fields_struct_name,
+            &generics,
+            &cache_fields,
+        )?)
+    } else {
+        None
+    };
+    let (struct_attrs, cache_fields, phantom_attrs, serde_module) = match serde_persist {
+        Some(x) => (
+            x.struct_attrs,
+            x.cache_fields,
+            quote! { #[serde(skip)] },
+            x.module,
+        ),
+        None => (quote! {}, cache_fields, quote! {}, quote! {}),
+    };
+    let derive = &args.derive;
+    let derive = if derive.is_empty() {
+        quote! {}
+    } else {
+        quote! { #[derive(#(#derive),*)] }
+    };
+    let (hidden_idents, hidden_fields) = hidden_fields(test_util);
+    let clone_impl = match args.clone {
+        Some(clone) => gen_clone(
+            &cache_fields_struct_name,
+            &generics,
+            &cache_fields,
+            &phantom_idents,
+            &hidden_idents,
+            clone,
+        )?,
+        None => quote! {},
+    };
+    let eq_hash_impls = gen_eq_hash(&cache_fields_struct_name, &generics, &args.derive);
+    let debug_impl = if args
+        .derive
+        .iter()
+        .any(|path| args::last_ident(path) == "Debug")
+    {
+        quote! {}
+    } else {
+        gen_debug(
+            &cache_fields_struct_name,
+            &generics,
+            &cache_fields,
+            args.debug_values,
+        )?
+    };
+    let names = cache_field_idents
+        .iter()
+        .map(|x| x.as_ref().unwrap().unraw().to_string())
+        .collect_vec();
+    // They are implementation details unless exposed, which documents them instead.
+    let (struct_doc, field_doc) = if args.expose {
+        let struct_doc = format!(" Caches of cached methods of [`{}`].", struct_.ident);
+        (
+            quote! { #[doc = #struct_doc] },
+            quote! { #[doc = " Caches of cached methods."] },
+        )
+    } else {
+        (quote! { #[doc(hidden)] }, quote! { #[doc(hidden)] })
+    };
+    // Implement `Default` by hand as `#[derive(Default)]` requires `T: Default` for type parameters.
+    let cache_fields_struct = quote! {
+        #struct_doc
+        #derive
+        #struct_attrs
+        #vis struct #cache_fields_struct_name #ty_generics #where_clause {
+            #(#cache_fields,)*
+            #(#phantom_attrs #phantom_fields,)*
+            #(#phantom_attrs #hidden_fields,)*
+        }
+
+        impl #impl_generics ::core::default::Default for #cache_fields_struct_name #ty_generics #where_clause {
+            fn default() -> Self {
+                Self {
+                    #(#cache_field_cfgs #cache_field_idents: ::core::default::Default::default(),)*
+                    #(#phantom_idents: ::core::marker::PhantomData,)*
+                    #(#hidden_idents: ::core::default::Default::default(),)*
+                }
+            }
+        }
+
+        impl #impl_generics #cache_fields_struct_name #ty_generics #where_clause {
+            /// Returns pairs of a method name and whether its cache is filled, in the declared order.
+            #vis fn occupancy(&self) -> impl ::core::iter::Iterator<Item = (&'static str, bool)> {
+                [
+                    #(
+                        #cache_field_cfgs
+                        (
+                            #names,
+                            ::struct_cache_field::CacheCell::get(&self.#cache_field_idents).is_some(),
+                        ),
+                    )*
+                ]
+                .into_iter()
+            }
+        }
+
+        #serde_module
+
+        #clone_impl
+
+        #eq_hash_impls
+
+        #debug_impl
+    };
+
+    // Add the above struct to original struct.
+    let derived_traits = args::derived_traits(&struct_.attrs)?;
+    let derives = |name: &str| {
+        derived_traits
+            .iter()
+            .any(|path| args::last_ident(path) == name)
+    };
+    let mut embedding_attrs = vec![];
+    if args.builder || derives("TypedBuilder") || derives("Builder") {
+        // Accepted by both `typed-builder` and `derive_builder`.
+        embedding_attrs.push(quote! { #[builder(default, setter(skip))] });
+    }
+    // `rkyv` also provides `Serialize` and `Deserialize`. Unqualified ones are regarded as of `serde`
+    // unless the struct derives `Archive`.
+    let derives_serde = derived_traits.iter().any(|path| {
+        let first = &path.segments.first().unwrap().ident;
+        let last = args::last_ident(path);
+        (last == "Serialize" || last == "Deserialize")
+            && if path.segments.len() == 1 {
+                !derives("Archive")
+            } else {
+                first == "serde"
+            }
+    });
+    if !args.serde_persist && (args.serde_skip || derives_serde) {
+        // Caches are not serialized and start empty on deserialization.
+        embedding_attrs.push(quote! { #[serde(skip)] });
+    }
+    if args.rkyv_skip || derives("Archive") {
+        // Archives omit caches, and they are recreated empty on deserialization.
+        embedding_attrs.push(quote! { #[with(::rkyv::with::Skip)] });
+    }
+    let embedding = syn::Field::parse_named
+        .parse2(quote! {
+            #field_doc
+            #(#embedding_attrs)*
+            #vis #field: #cache_fields_struct_name #ty_generics
+        })
+        .unwrap();
+    let mut items = vec![
+        gen_has_cache_fields(struct_, &field, &cache_fields, stats, switch),
+        gen_cached_methods(struct_, &cache_fields, &return_types),
+    ];
+    // Other crates have no way to construct `#[non_exhaustive]` structs but a constructor.
+    let new = args
+        .new
+        .or_else(|| non_exhaustive.then(|| syn::Ident::new("new", Span::call_site())));
+    if let Some(new) = &new {
+        items.push(gen_constructor(struct_, &fields.named, &field, new));
+    }
+    if args.graph {
+        items.push(gen_cache_graph(struct_, &fields.named, &dependencies));
+    }
+    if args.heap_size {
+        items.push(gen_heap_size(struct_, &field, &cache_fields)?);
+    }
+    // `#[derive(Default)]` below covers the injected field by itself.
+    if args.derive_default && !derives("Default") {
+        items.push(gen_default(struct_, &fields.named, &field));
+    }
+    items.extend(snapshot);
+    if let Some(init) = &args.init {
+        let init = init.clone().unwrap_or_else(|| {
+            syn::Ident::new(&format!("{}Init", struct_.ident), Span::call_site())
+        });
+        items.push(gen_init_struct(struct_, &field, &init));
+    }
+    // The field is appended last unless `field_first`, so that the layout of the other fields
+    // doesn't change with `#[repr(C)]`.
+    let mut fields = fields.clone();
+    if args.field_first {
+        fields.named.insert(0, embedding);
+    } else {
+        fields.named.push(embedding);
+    }
+    let mut struct_ = struct_.clone();
+    struct_.fields = syn::Fields::Named(fields);
+
+    Ok(quote! {
+        #not_defined_warning
+
+        #struct_
+
+        #cache_fields_struct
+
+        #(#items)*
+    })
+}
+
+/// Generates an item emitting `message` as a warning, as proc macros can't emit warnings on stable.
+fn gen_warning(message: &str, span: Span) -> TokenStream {
+    quote_spanned! {span=>
+        const _: () = {
+            #[deprecated(note = #message)]
+            struct Warning;
+            let _ = Warning;
+        };
+    }
+}
+
+fn gen_has_cache_fields(
+    struct_: &syn::ItemStruct,
+    field: &syn::Ident,
+    cache_fields: &[syn::Field],
+    stats: bool,
+    switch: bool,
+) -> TokenStream {
+    let ident = &struct_.ident;
+    let (impl_generics, ty_generics, where_clause) = struct_.generics.split_for_impl();
+    let cache_field_idents = cache_fields.iter().map(|x| &x.ident).collect_vec();
+    let names = cache_field_idents
+        .iter()
+        .map(|x| x.as_ref().unwrap().unraw().to_string())
+        .collect_vec();
+    // Entries are pushed one by one so that each can be under `#[cfg(...)]` of its method.
+    let cfgs = cache_fields
+        .iter()
+        .map(|x| cfg_attrs(&x.attrs))
+        .collect_vec();
+    let resets = cache_field_idents
+        .iter()
+        .map(|x| reset_cache(field, x.as_ref().unwrap(), stats));
+    let reset_caches = quote! { #(#cfgs #resets)* };
+    let cache_stats = if stats {
+        quote! {
+            fn cache_stats(&self) -> ::struct_cache_field::CacheStats {
+                #[allow(unused_mut)]
+                let mut entries = ::struct_cache_field::__private::Vec::new();
+                #(
+                    #cfgs
+                    entries.push((
+                        #names,
+                        ::struct_cache_field::Counted::stat(&self.#field.#cache_field_idents),
+                    ));
+                )*
+                ::struct_cache_field::CacheStats::new(entries)
+            }
+        }
+    } else if cfg!(feature = "stats") {
+        // The feature requires the method even if the impl doesn't count.
+        quote! {
+            fn cache_stats(&self) -> ::struct_cache_field::CacheStats {
+                ::struct_cache_field::CacheStats::new(::struct_cache_field::__private::Vec::new())
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let set_caching_enabled = if switch {
+        let cells = cache_field_idents.iter().map(|ident| {
+            if stats {
+                quote! { ::struct_cache_field::Counted::inner_mut(&mut self.#field.#ident) }
+            } else {
+                quote! { &mut self.#field.#ident }
+            }
+        });
+        quote! {
+            fn set_caching_enabled(&mut self, enabled: bool) {
+                #(#cfgs ::struct_cache_field::Switchable::set_enabled(#cells, enabled);)*
+            }
+        }
+    } else if cfg!(feature = "switch") {
+        // The feature requires the method even if the impl can't disable caches.
+        let message = format!(
+            "caches of `{ident}` can't be disabled. maybe forgot to add `switch` to `#[struct_cache_field::impl_cached_method]`?"
+        );
+        quote! {
+            fn set_caching_enabled(&mut self, enabled: bool) {
+                ::core::assert!(enabled, #message);
+                ::struct_cache_field::HasCacheFields::reset_caches(self);
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let cache_occupancy = if cfg!(feature = "alloc") {
+        quote! {
+            fn cache_occupancy(&self) -> ::struct_cache_field::CacheOccupancy {
+                ::struct_cache_field::CacheOccupancy::new(
+                    ::core::iter::Iterator::collect(self.#field.occupancy()),
+                )
+            }
+        }
+    } else {
+        quote! {}
+    };
+    quote! {
+        impl #impl_generics ::struct_cache_field::HasCacheFields for #ident #ty_generics #where_clause {
+            fn reset_caches(&mut self) {
+                #reset_caches
+            }
+
+            fn cached_methods(&self) -> &'static [::struct_cache_field::CachedMethod] {
+                Self::CACHED_METHODS
+            }
+
+            #cache_occupancy
+
+            #cache_stats
+
+            #set_caching_enabled
+        }
+    }
+}
+
+/// Generates `CACHED_METHODS`, the names and the return types of cached methods.
+fn gen_cached_methods(
+    struct_: &syn::ItemStruct,
+    cache_fields: &[syn::Field],
+    return_types: &[(String, String)],
+) -> TokenStream {
+    let ident = &struct_.ident;
+    let vis = &struct_.vis;
+    let (impl_generics, ty_generics, where_clause) = struct_.generics.split_for_impl();
+    let methods = cache_fields.iter().map(|field| {
+        let name = field.ident.as_ref().unwrap().unraw().to_string();
+        let return_type = return_types
+            .iter()
+            .find(|(method, _)| *method == name)
+            .map_or("", |(_, ty)| ty.as_str());
+        let cfgs = cfg_attrs(&field.attrs);
+        quote! {
+            #cfgs
+            ::struct_cache_field::CachedMethod {
+                name: #name,
+                return_type: #return_type,
+            }
+        }
+    });
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Cached methods in the declared order.
+            #vis const CACHED_METHODS: &'static [::struct_cache_field::CachedMethod] = &[#(#methods),*];
+        }
+    }
+}
+
+/// Returns the type as written, with spaces only where needed, e.g. `Vec<&'a str>`.
+fn type_to_string(ty: &syn::Type) -> String {
+    fn push_tokens(ret: &mut String, tokens: TokenStream) {
+        for token in tokens {
+            if ret.ends_with([',', ';']) {
+                ret.push(' ');
+            }
+            match token {
+                proc_macro2::TokenTree::Group(group) => {
+                    let (open, close) = match group.delimiter() {
+                        proc_macro2::Delimiter::Parenthesis => ("(", ")"),
+                        proc_macro2::Delimiter::Bracket => ("[", "]"),
+                        proc_macro2::Delimiter::Brace => ("{ ", " }"),
+                        proc_macro2::Delimiter::None => ("", ""),
+                    };
+                    ret.push_str(open);
+                    push_tokens(ret, group.stream());
+                    ret.push_str(close);
+                }
+                proc_macro2::TokenTree::Punct(punct) => match punct.as_char() {
+                    '+' | '=' => ret.push_str(&format!(" {} ", punct.as_char())),
+                    '-' => ret.push_str(" -"),
+                    '>' if ret.ends_with(" -") => ret.push_str("> "),
+                    c => ret.push(c),
+                },
+                token => {
+                    if ret.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                        ret.push(' ');
+                    }
+                    ret.push_str(&token.to_string());
+                }
+            }
+        }
+    }
+
+    let mut ret = String::new();
+    push_tokens(&mut ret, ty.to_token_stream());
+    ret
+}
+
+/// Generates `CACHE_GRAPH_DOT`, the graph of dependencies of caches on fields and other caches.
+///
+/// Dependencies are collected syntactically from `self.field` and `self.method(...)` in the bodies
+/// of cached methods.
+fn gen_cache_graph(
+    struct_: &syn::ItemStruct,
+    fields: &Punctuated<syn::Field, syn::Token![,]>,
+    dependencies: &[(String, Vec<String>)],
+) -> TokenStream {
+    let ident = &struct_.ident;
+    let vis = &struct_.vis;
+    let (impl_generics, ty_generics, where_clause) = struct_.generics.split_for_impl();
+    let fields = fields
+        .iter()
+        .map(|x| x.ident.as_ref().unwrap().unraw().to_string())
+        .collect_vec();
+    let mut dot = format!("digraph \"{ident}\" {{\n");
+    for field in &fields {
+        dot += &format!("    \"{field}\";\n");
+    }
+    for (method, _) in dependencies {
+        dot += &format!("    \"{method}\" [shape=box];\n");
+    }
+    for (method, deps) in dependencies {
+        for dep in deps {
+            if fields.contains(dep) || dependencies.iter().any(|(x, _)| x == dep) {
+                dot += &format!("    \"{method}\" -> \"{dep}\";\n");
+            }
+        }
+    }
+    dot += "}\n";
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Dependencies of caches on fields and other caches in Graphviz DOT format.
+            #vis const CACHE_GRAPH_DOT: &'static str = #dot;
+        }
+    }
+}
+
+fn gen_heap_size(
+    struct_: &syn::ItemStruct,
+    field: &syn::Ident,
+    cache_fields: &[syn::Field],
+) -> syn::Result<TokenStream> {
+    let ident = &struct_.ident;
+    let vis = &struct_.vis;
+    let mut generics = struct_.generics.clone();
+    let value_tys = generic_value_types(cache_fields, &generics)?;
+    let where_clause = generics.make_where_clause();
+    for ty in value_tys {
+        where_clause
+            .predicates
+            .push(syn::parse_quote! { #ty: ::struct_cache_field::HeapSize });
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let cache_field_idents = cache_fields.iter().map(|x| &x.ident);
+    let cfgs = cache_fields.iter().map(|x| cfg_attrs(&x.attrs));
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Returns the size of heap memory owned by cached values, in bytes.
+            #vis fn cache_heap_size(&self) -> usize {
+                #[allow(unused_mut)]
+                let mut size = 0;
+                #(
+                    #cfgs
+                    {
+                        size += ::struct_cache_field::CacheCell::get(&self.#field.#cache_field_idents)
+                            .map_or(0, ::struct_cache_field::HeapSize::heap_size);
+                    }
+                )*
+                size
+            }
+        }
+    })
+}
+
+/// Generates a struct holding `Option<T>` clones of cached values, and methods to take and restore
+/// it.
+fn gen_snapshot(
+    struct_: &syn::ItemStruct,
+    field: &syn::Ident,
+    snapshot: &syn::Ident,
+    generics: &syn::Generics,
+    cache_fields: &[syn::Field],
+    phantom_idents: &[syn::Ident],
+    phantom_fields: &[TokenStream],
+) -> syn::Result<TokenStream> {
+    let ident = &struct_.ident;
+    let vis = &struct_.vis;
+    let cache_field_idents = cache_fields.iter().map(|x| &x.ident).collect_vec();
+    let value_tys = cache_fields
+        .iter()
+        .map(|x| cell_value_type(&x.ty))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let cfgs = cache_fields
+        .iter()
+        .map(|x| cfg_attrs(&x.attrs))
+        .collect_vec();
+    let (_, snapshot_ty_generics, snapshot_where_clause) = generics.split_for_impl();
+    let mut impl_generics = struct_.generics.clone();
+    let where_clause = impl_generics.make_where_clause();
+    for ty in generic_value_types(cache_fields, generics)? {
+        where_clause
+            .predicates
+            .push(syn::parse_quote! { #ty: ::core::clone::Clone });
+    }
+    let (impl_generics, ty_generics, where_clause) = impl_generics.split_for_impl();
+    let doc = format!("Cached values of [`{ident}`], taken by `cache_snapshot()`.");
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Clone)]
+        #vis struct #snapshot #snapshot_ty_generics #snapshot_where_clause {
+            #(#cfgs #cache_field_idents: ::core::option::Option<#value_tys>,)*
+            #(#phantom_fields,)*
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Returns clones of cached values, which can be restored by `restore_cache()`.
+            #vis fn cache_snapshot(&self) -> #snapshot #snapshot_ty_generics {
+                #snapshot {
+                    #(
+                        #cfgs
+                        #cache_field_idents: ::struct_cache_field::CacheCell::get(
+                            &self.#field.#cache_field_idents,
+                        )
+                        .cloned(),
+                    )*
+                    #(#phantom_idents: ::core::marker::PhantomData,)*
+                }
+            }
+
+            /// Replaces caches with the snapshot. Caches empty in it are reset.
+            #vis fn restore_cache(&mut self, snapshot: #snapshot #snapshot_ty_generics) {
+                ::struct_cache_field::HasCacheFields::reset_caches(self);
+                #(
+                    #cfgs
+                    if let ::core::option::Option::Some(value) = snapshot.#cache_field_idents {
+                        ::struct_cache_field::CacheCell::get_or_init(
+                            &self.#field.#cache_field_idents,
+                            move || value,
+                        );
+                    }
+                )*
+            }
+        }
+    })
+}
+
+fn gen_constructor(
+    struct_: &syn::ItemStruct,
+    fields: &Punctuated<syn::Field, syn::Token![,]>,
+    field: &syn::Ident,
+    new: &syn::Ident,
+) -> TokenStream {
+    let ident = &struct_.ident;
+    let vis = &struct_.vis;
+    let (impl_generics, ty_generics, where_clause) = struct_.generics.split_for_impl();
+    let idents = fields.iter().map(|x| &x.ident).collect_vec();
+    let tys = fields.iter().map(|x| &x.ty);
+    quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Creates a value with empty caches.
+            #[allow(clippy::too_many_arguments)]
+            #vis fn #new(#(#idents: #tys),*) -> Self {
+                Self {
+                    #(#idents,)*
+                    #field: ::core::default::Default::default(),
+                }
+            }
+        }
+    }
+}
+
+fn gen_default(
+    struct_: &syn::ItemStruct,
+    fields: &Punctuated<syn::Field, syn::Token![,]>,
+    field: &syn::Ident,
+) -> TokenStream {
+    let ident = &struct_.ident;
+    // Same bounds as `#[derive(Default)]`.
+    let mut generics = struct_.generics.clone();
+    let type_params = generics
+        .type_params()
+        .map(|x| x.ident.clone())
+        .collect_vec();
+    let where_clause = generics.make_where_clause();
+    for param in type_params {
+        where_clause
+            .predicates
+            .push(syn::parse_quote! { #param: ::core::default::Default });
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let idents = fields.iter().map(|x| &x.ident);
+    quote! {
+        impl #impl_generics ::core::default::Default for #ident #ty_generics #where_clause {
+            fn default() -> Self {
+                Self {
+                    #(#idents: ::core::default::Default::default(),)*
+                    #field: ::core::default::Default::default(),
+                }
+            }
+        }
+    }
+}
+
+fn gen_init_struct(
+    struct_: &syn::ItemStruct,
+    field: &syn::Ident,
+    init: &syn::Ident,
+) -> TokenStream {
+    let ident = &struct_.ident;
+    let syn::Fields::Named(fields) = &struct_.fields else {
+        unreachable!();
+    };
+    let idents = fields.named.iter().map(|x| &x.ident).collect_vec();
+    let (impl_generics, ty_generics, where_clause) = struct_.generics.split_for_impl();
+    // Inherit attributes like `#[derive(...)]` and `#[serde(...)]`, but not docs.
+    let mut init_struct = struct_.clone();
+    init_struct.ident = init.clone();
+    init_struct
+        .attrs
+        .retain(|attr| !attr.path().is_ident("doc"));
+    let doc = format!("Plain data of [`{ident}`] without caches.");
+    quote! {
+        #[doc = #doc]
+        #init_struct
+
+        impl #impl_generics ::core::convert::From<#init #ty_generics> for #ident #ty_generics #where_clause {
+            fn from(init: #init #ty_generics) -> Self {
+                Self {
+                    #(#idents: init.#idents,)*
+                    #field: ::core::default::Default::default(),
+                }
+            }
+        }
+
+        // The caches are dropped.
+        impl #impl_generics ::core::convert::From<#ident #ty_generics> for #init #ty_generics #where_clause {
+            fn from(value: #ident #ty_generics) -> Self {
+                Self {
+                    #(#idents: value.#idents,)*
+                }
+            }
+        }
+    }
+}
+
+/// Implements `PartialEq`, `Eq`, and `Hash` for the cache struct, ignoring caches.
+///
+/// All instances are equal and hash nothing, so that derived impls of the original struct compare
+/// only its data. Traits given by `derive(...)` are left to it.
+fn gen_eq_hash(
+    cache_fields_struct_name: &syn::Ident,
+    generics: &syn::Generics,
+    derive: &[syn::Path],
+) -> TokenStream {
+    let is_derived = |name: &str| derive.iter().any(|path| args::last_ident(path) == name);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut ret = quote! {};
+    if !is_derived("PartialEq") {
+        ret.append_all(quote! {
+            impl #impl_generics ::core::cmp::PartialEq for #cache_fields_struct_name #ty_generics #where_clause {
+                fn eq(&self, _other: &Self) -> bool {
+                    true
+                }
+            }
+        });
+    }
+    // `Eq` follows `PartialEq`, whose derived impl has bounds.
+    if !is_derived("PartialEq") && !is_derived("Eq") {
+        ret.append_all(quote! {
+            impl #impl_generics ::core::cmp::Eq for #cache_fields_struct_name #ty_generics #where_clause {}
+        });
+    }
+    if !is_derived("Hash") {
+        ret.append_all(quote! {
+            impl #impl_generics ::core::hash::Hash for #cache_fields_struct_name #ty_generics #where_clause {
+                fn hash<H: ::core::hash::Hasher>(&self, _state: &mut H) {}
+            }
+        });
+    }
+    ret
+}
+
+/// Implements `Debug` for the cache struct, showing which caches are filled.
+///
+/// With `debug_values`, values are shown as `Some(value)` or `None` and required to be `Debug`.
+fn gen_debug(
+    cache_fields_struct_name: &syn::Ident,
+    generics: &syn::Generics,
+    cache_fields: &[syn::Field],
+    debug_values: bool,
+) -> syn::Result<TokenStream> {
+    let mut generics = generics.clone();
+    let name = cache_fields_struct_name.to_string();
+    let cache_field_idents = cache_fields.iter().map(|x| &x.ident).collect_vec();
+    let cache_field_names = cache_field_idents
+        .iter()
+        .map(|x| x.as_ref().unwrap().unraw().to_string())
+        .collect_vec();
+    let values = if debug_values {
+        let value_tys = generic_value_types(cache_fields, &generics)?;
+        let where_clause = generics.make_where_clause();
+        for ty in value_tys {
+            where_clause
+                .predicates
+                .push(syn::parse_quote! { #ty: ::core::fmt::Debug });
+        }
+        cache_field_idents
+            .iter()
+            .map(|ident| quote! { &::struct_cache_field::CacheCell::get(&self.#ident) })
+            .collect_vec()
+    } else {
+        cache_field_idents
+            .iter()
+            .map(|ident| {
+                quote! {
+                    &::core::format_args!(
+                        "{}",
+                        if ::struct_cache_field::CacheCell::get(&self.#ident).is_some() {
+                            "<filled>"
+                        } else {
+                            "<empty>"
+                        }
+                    )
+                }
+            })
+            .collect_vec()
+    };
+    let cfgs = cache_fields.iter().map(|x| cfg_attrs(&x.attrs));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::core::fmt::Debug for #cache_fields_struct_name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #[allow(unused_mut)]
+                let mut s = f.debug_struct(#name);
+                #(
+                    #cfgs
+                    s.field(#cache_field_names, #values);
+                )*
+                s.finish()
+            }
+        }
+    })
+}
+
+/// Implements `Clone` for the cache struct.
+///
+/// Unlike `#[derive(Clone)]`, this requires `Clone` only for the values of caches, and nothing for
+/// `clone = "reset"`.
+fn gen_clone(
+    cache_fields_struct_name: &syn::Ident,
+    generics: &syn::Generics,
+    cache_fields: &[syn::Field],
+    phantom_idents: &[syn::Ident],
+    hidden_idents: &[syn::Ident],
+    clone: args::CloneMode,
+) -> syn::Result<TokenStream> {
+    let mut generics = generics.clone();
+    let cache_field_idents = cache_fields.iter().map(|x| &x.ident).collect_vec();
+    let values = match clone {
+        args::CloneMode::Preserve => {
+            let value_tys = generic_value_types(cache_fields, &generics)?;
+            let where_clause = generics.make_where_clause();
+            for ty in value_tys {
+                where_clause
+                    .predicates
+                    .push(syn::parse_quote! { #ty: ::core::clone::Clone });
+            }
+            cache_fields
+                .iter()
+                .map(|field| {
+                    let ident = &field.ident;
+                    let ty = &field.ty;
+                    quote! {{
+                        let cell: #ty = ::core::default::Default::default();
+                        if let ::core::option::Option::Some(value) =
+                            ::struct_cache_field::CacheCell::get(&self.#ident)
+                        {
+                            ::struct_cache_field::CacheCell::get_or_init(&cell, || {
+                                ::core::clone::Clone::clone(value)
+                            });
+                        }
+                        cell
+                    }}
+                })
+                .collect_vec()
+        }
+        args::CloneMode::Reset => cache_field_idents
+            .iter()
+            .map(|_| quote! { ::core::default::Default::default() })
+            .collect_vec(),
+    };
+    let hidden_values = hidden_idents.iter().map(|ident| match clone {
+        args::CloneMode::Preserve => quote! { ::core::clone::Clone::clone(&self.#ident) },
+        args::CloneMode::Reset => quote! { ::core::default::Default::default() },
+    });
+    let cfgs = cache_fields.iter().map(|x| cfg_attrs(&x.attrs));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::core::clone::Clone for #cache_fields_struct_name #ty_generics #where_clause {
+            fn clone(&self) -> Self {
+                Self {
+                    #(#cfgs #cache_field_idents: #values,)*
+                    #(#phantom_idents: ::core::marker::PhantomData,)*
+                    #(#hidden_idents: #hidden_values,)*
+                }
+            }
+        }
+    })
+}
+
+struct SerdePersist {
+    struct_attrs: TokenStream,
+    cache_fields: Vec<syn::Field>,
+    module: TokenStream,
+}
+
+/// Makes the cache struct serializable. Each cell is serialized as `Option<T>`.
+///
+/// serde can't (de)serialize cells directly, so we generate a helper module for
+/// `#[serde(with = "...")]`. Bounds are given explicitly because serde doesn't infer bounds for
+/// fields with `with`.
+fn gen_serde_persist(
+    cache_fields_struct_name: &syn::Ident,
+    generics: &syn::Generics,
+    cache_fields: &[syn::Field],
+) -> syn::Result<SerdePersist> {
+    let module_name = syn::Ident::new(
+        &format!("{cache_fields_struct_name}_serde"),
+        Span::call_site(),
+    );
+    let module_name_str = module_name.to_string();
+    let value_tys = generic_value_types(cache_fields, generics)?;
+    let serialize_bound = value_tys
+        .iter()
+        .map(|ty| quote! { #ty: ::serde::Serialize }.to_string())
+        .join(", ");
+    let deserialize_bound = value_tys
+        .iter()
+        .map(|ty| quote! { #ty: ::serde::Deserialize<'de> }.to_string())
+        .join(", ");
+    let cache_fields = cache_fields
+        .iter()
+        .cloned()
+        .map(|mut field| {
+            field
+                .attrs
+                .push(syn::parse_quote! { #[serde(with = #module_name_str)] });
+            field
+        })
+        .collect();
+    let struct_attrs = quote! {
+        #[derive(::serde::Serialize, ::serde::Deserialize)]
+        #[serde(bound(serialize = #serialize_bound, deserialize = #deserialize_bound))]
+    };
+    let module = quote! {
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        mod #module_name {
+            pub fn serialize<T, C, S>(
+                cell: &C,
+                serializer: S,
+            ) -> ::core::result::Result<S::Ok, S::Error>
+            where
+                T: ::serde::Serialize,
+                C: ::struct_cache_field::CacheCell<T>,
+                S: ::serde::Serializer,
+            {
+                ::serde::Serialize::serialize(&::struct_cache_field::CacheCell::get(cell), serializer)
+            }
+
+            pub fn deserialize<'de, T, C, D>(
+                deserializer: D,
+            ) -> ::core::result::Result<C, D::Error>
+            where
+                T: ::serde::Deserialize<'de>,
+                C: ::struct_cache_field::CacheCell<T>,
+                D: ::serde::Deserializer<'de>,
+            {
+                let value: ::core::option::Option<T> =
+                    ::serde::Deserialize::deserialize(deserializer)?;
+                let cell = C::default();
+                if let ::core::option::Option::Some(value) = value {
+                    ::struct_cache_field::CacheCell::get_or_init(&cell, || value);
+                }
+                ::core::result::Result::Ok(cell)
+            }
+        }
+    };
+    Ok(SerdePersist {
+        struct_attrs,
+        cache_fields,
+        module,
+    })
+}
+
+/// Returns `#[cfg(...)]` attributes, which are propagated from a cached method to generated items
+/// for it.
+fn cfg_attrs(attrs: &[syn::Attribute]) -> TokenStream {
+    let cfgs = attrs.iter().filter(|attr| attr.path().is_ident("cfg"));
+    quote! { #(#cfgs)* }
+}
+
+/// Returns types of values of caches that mention type parameters, which need bounds in generated
+/// impls.
+///
+/// Bounds on the other types are trivial, and such types may not exist if the cache is under
+/// `#[cfg(...)]`.
+fn generic_value_types<'a>(
+    cache_fields: &'a [syn::Field],
+    generics: &syn::Generics,
+) -> syn::Result<Vec<&'a syn::Type>> {
+    struct Finder<'a> {
+        params: &'a HashSet<&'a syn::Ident>,
+        found: bool,
+    }
+
+    impl<'ast> Visit<'ast> for Finder<'_> {
+        fn visit_path(&mut self, path: &'ast syn::Path) {
+            if path.leading_colon.is_none() && self.params.contains(&path.segments[0].ident) {
+                self.found = true;
+            }
+            syn::visit::visit_path(self, path);
+        }
+    }
+
+    let params = generics.type_params().map(|x| &x.ident).collect();
+    let mut ret = vec![];
+    for field in cache_fields {
+        let ty = cell_value_type(&field.ty)?;
+        let mut finder = Finder {
+            params: &params,
+            found: false,
+        };
+        finder.visit_type(ty);
+        if finder.found {
+            ret.push(ty);
+        }
+    }
+    Ok(ret)
+}
+
+/// Returns types whose associated types appear in cache fields, e.g. `T` of `T::Output` or
+/// `Hoge<T>` of `<Hoge<T> as Trait>::Assoc`, as strings.
+fn projection_bases(generics: &syn::Generics, fields: &[syn::Field]) -> HashSet<String> {
+    struct Finder<'a> {
+        params: &'a HashSet<&'a syn::Ident>,
+        found: HashSet<String>,
+    }
+
+    impl<'ast> Visit<'ast> for Finder<'_> {
+        fn visit_type_path(&mut self, ty: &'ast syn::TypePath) {
+            match &ty.qself {
+                Some(qself) => {
+                    self.found.insert(qself.ty.to_token_stream().to_string());
+                }
+                None if ty.path.leading_colon.is_none()
+                    && ty.path.segments.len() > 1
+                    && self.params.contains(&ty.path.segments[0].ident) =>
+                {
+                    self.found.insert(ty.path.segments[0].ident.to_string());
+                }
+                None => {}
+            }
+            syn::visit::visit_type_path(self, ty);
+        }
+    }
+
+    let params = generics.type_params().map(|x| &x.ident).collect();
+    let mut finder = Finder {
+        params: &params,
+        found: HashSet::new(),
+    };
+    for field in fields {
+        finder.visit_field(field);
+    }
+    finder.found
+}
+
+/// Returns type parameters bounded by `Allocator`, e.g. `A` of `Box<T, A>`, as strings.
+fn allocator_params(generics: &syn::Generics) -> Vec<String> {
+    let is_allocator = |bound: &syn::TypeParamBound| matches!(bound, syn::TypeParamBound::Trait(x) if args::last_ident(&x.path) == "Allocator");
+    let inline = generics
+        .type_params()
+        .filter(|param| param.bounds.iter().any(is_allocator))
+        .map(|param| param.ident.to_string());
+    let where_ = generics
+        .where_clause
+        .iter()
+        .flat_map(|x| &x.predicates)
+        .filter_map(|pred| match pred {
+            syn::WherePredicate::Type(x) if x.bounds.iter().any(is_allocator) => {
+                Some(x.bounded_ty.to_token_stream().to_string())
+            }
+            _ => None,
+        });
+    inline.chain(where_).collect()
+}
+
+/// Returns whether the where-predicate bounds one of `bases` itself, e.g. `T: Op`.
+fn bounds_base(pred: &syn::WherePredicate, bases: &HashSet<String>) -> bool {
+    match pred {
+        syn::WherePredicate::Type(x) => bases.contains(&x.bounded_ty.to_token_stream().to_string()),
+        _ => false,
+    }
+}
+
+/// Replaces `Self` in the tokens with `self_ty`.
+fn replace_self(tokens: TokenStream, self_ty: &syn::Type) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            proc_macro2::TokenTree::Ident(x) if x == "Self" => self_ty.to_token_stream(),
+            proc_macro2::TokenTree::Group(x) => {
+                let mut group =
+                    proc_macro2::Group::new(x.delimiter(), replace_self(x.stream(), self_ty));
+                group.set_span(x.span());
+                group.into_token_stream()
+            }
+            token => token.into_token_stream(),
+        })
+        .collect()
+}
+
+/// Returns bounds of the impl on types whose associated types appear in cache fields, e.g.
+/// `T: Op` for `T::Output` or `Hoge<T>: Trait` for `<Hoge<T> as Trait>::Assoc`, which the struct
+/// and the cache struct need to name the fields.
+///
+/// Both inline bounds of type parameters and where-clauses bounding the types themselves are
+/// collected, with `Self` replaced by `self_ty`.
+fn projection_bounds(
+    generics: &syn::Generics,
+    self_ty: &syn::Type,
+    fields: &[TokenStream],
+) -> syn::Result<Vec<TokenStream>> {
+    let fields = fields
+        .iter()
+        .map(|field| syn::Field::parse_named.parse2(field.clone()))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let bases = projection_bases(generics, &fields);
+    let inline = generics
+        .type_params()
+        .filter(|param| bases.contains(&param.ident.to_string()) && !param.bounds.is_empty())
+        .map(|param| {
+            let ident = &param.ident;
+            let bounds = &param.bounds;
+            quote! { #ident: #bounds }
+        });
+    let where_ = generics
+        .where_clause
+        .iter()
+        .flat_map(|x| &x.predicates)
+        .map(|pred| syn::parse2(replace_self(pred.to_token_stream(), self_ty)))
+        .filter_ok(|pred| bounds_base(pred, &bases))
+        .map_ok(|pred: syn::WherePredicate| pred.to_token_stream())
+        .collect::<syn::Result<Vec<_>>>()?;
+    Ok(inline.chain(where_).collect())
+}
+
+/// Returns `T` of a cache field type, e.g. `OnceCell<T>`.
+fn cell_value_type(ty: &syn::Type) -> syn::Result<&syn::Type> {
+    if let syn::Type::Path(path) = ty {
+        if let Some(syn::PathArguments::AngleBracketed(args)) =
+            path.path.segments.last().map(|x| &x.arguments)
+        {
+            if let Some(syn::GenericArgument::Type(ty)) = args.args.first() {
+                return Ok(ty);
+            }
+        }
+    }
+    Err(syn::Error::new(ty.span(), "unexpected type of cache field"))
+}
+
+/// Collects names of fields and methods accessed via `self` in the method, in order.
+fn collect_self_accesses(fn_: &syn::ImplItemFn) -> Vec<String> {
+    struct Collector(Vec<String>);
+
+    fn is_self(expr: &syn::Expr) -> bool {
+        matches!(expr, syn::Expr::Path(x) if x.path.is_ident("self"))
+    }
+
+    impl<'ast> Visit<'ast> for Collector {
+        fn visit_expr_field(&mut self, expr: &'ast syn::ExprField) {
+            if let (true, syn::Member::Named(ident)) = (is_self(&expr.base), &expr.member) {
+                self.0.push(ident.unraw().to_string());
+            }
+            syn::visit::visit_expr_field(self, expr);
+        }
+
+        fn visit_expr_method_call(&mut self, expr: &'ast syn::ExprMethodCall) {
+            if is_self(&expr.receiver) {
+                self.0.push(expr.method.unraw().to_string());
+            }
+            syn::visit::visit_expr_method_call(self, expr);
+        }
+    }
+
+    let mut collector = Collector(vec![]);
+    collector.visit_block(&fn_.block);
+    collector.0.into_iter().unique().collect()
+}
+
+fn collect_lifetimes(fields: &[syn::Field]) -> HashSet<syn::Lifetime> {
+    let mut collector = LifetimeCollector(HashSet::new());
+    for field in fields {
+        collector.visit_type(&field.ty);
+    }
+    collector.0
+}
+
+/// Collects lifetimes in visited nodes.
+struct LifetimeCollector(HashSet<syn::Lifetime>);
+
+impl<'ast> Visit<'ast> for LifetimeCollector {
+    fn visit_lifetime(&mut self, lifetime: &'ast syn::Lifetime) {
+        self.0.insert(lifetime.clone());
+    }
+}
+
+/// Returns lifetimes in bounded types and trait bounds of the predicates, e.g. `'a` of
+/// `T: Parse<'a>`, but not lifetime bounds like `T: 'a`.
+fn collect_bound_lifetimes<'a>(
+    predicates: impl IntoIterator<Item = &'a syn::WherePredicate>,
+) -> HashSet<syn::Lifetime> {
+    let mut collector = LifetimeCollector(HashSet::new());
+    for pred in predicates {
+        if let syn::WherePredicate::Type(x) = pred {
+            collector.visit_type(&x.bounded_ty);
+            for bound in &x.bounds {
+                if let syn::TypeParamBound::Trait(bound) = bound {
+                    collector.visit_trait_bound(bound);
+                }
+            }
+        }
+    }
+    collector.0
+}
+
+/// Defines cached methods for `Cached<T>` as an extension trait, for `T` that can't be annotated
+/// with `#[add_cache_field]`.
+///
+/// `#[cached_ext(name = "DurationExt")] impl Cached<Duration> { ... }` generates the trait
+/// `DurationExt`, the cache struct `DurationExtCaches`, and
+/// `impl DurationExt for Cached<Duration, DurationExtCaches>`. With `side_table`,
+/// `impl Rc<Duration> { ... }` generates `impl DurationExt for Rc<Duration>` storing values in
+/// side tables.
+#[proc_macro_attribute]
+pub fn cached_ext(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as syn::Item);
+
+    match cached_ext_aux(&args.into(), &input) {
+        Ok(x) => x.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+fn cached_ext_aux(args: &TokenStream, input: &syn::Item) -> syn::Result<TokenStream> {
+    let args = args::ExtArgs::parse(args)?;
+
+    let wrapper = if args.side_table { "Rc" } else { "Cached" };
+    let syn::Item::Impl(impl_) = input else {
+        return Err(syn::Error::new(
+            input.span(),
+            format!("expected `impl {wrapper}<T> {{ ... }}`"),
+        ));
+    };
+    if impl_.trait_.is_some() || !impl_.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            impl_.impl_token.span,
+            format!("expected `impl {wrapper}<T> {{ ... }}` without trait and generics"),
+        ));
+    }
+    // Take `T` of `Cached<T>` or `Rc<T>`.
+    let inner_ty = match impl_.self_ty.as_ref() {
+        syn::Type::Path(ty) => ty.path.segments.last().and_then(|segment| {
+            let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+                return None;
+            };
+            match (segment.ident == wrapper, args.args.first(), args.args.len()) {
+                (true, Some(syn::GenericArgument::Type(ty)), 1) => Some(ty),
+                _ => None,
+            }
+        }),
+        _ => None,
+    };
+    let Some(inner_ty) = inner_ty else {
+        return Err(syn::Error::new(
+            impl_.self_ty.span(),
+            format!("expected `{wrapper}<T>`"),
+        ));
+    };
+    let name = args.name.unwrap_or_else(|| {
+        let ty = match inner_ty {
+            syn::Type::Path(ty) => ty.path.segments.last().unwrap().ident.to_string(),
+            _ => "Value".to_string(),
+        };
+        syn::Ident::new(&format!("{ty}CachedExt"), Span::call_site())
+    });
+    let vis = args.vis.unwrap_or(syn::Visibility::Inherited);
+    check_duplicate_methods(&impl_.items)?;
+    check_shared_by_generics(impl_)?;
+    // The fields of `T` are not known, so their types can't be named in the cache struct.
+    for item in &impl_.items {
+        if let syn::ImplItem::Fn(fn_) = item {
+            if let Some(member) = args::MethodArgs::from_attrs(&fn_.attrs)?
+                .recompute_if_changed
+                .first()
+            {
+                return Err(syn::Error::new_spanned(
+                    member,
+                    "`recompute_if_changed` can't be used with `#[cached_ext]`",
+                ));
+            }
+        }
+    }
+    if args.side_table {
+        return gen_side_table_ext(impl_, &name, &vis, inner_ty);
+    }
+    let caches_name = syn::Ident::new(&format!("{name}Caches"), Span::call_site());
+
+    let field = syn::Ident::new(args::DEFAULT_FIELD, Span::call_site());
+    let cell_path = config::cell_path()?;
+    let stats = args.stats || config::stats()?;
+    let test_util = args.test_util || config::test_util()?;
+    let (items, fields, test_util_items): (
+        Vec<syn::ImplItem>,
+        Vec<Option<TokenStream>>,
+        Vec<Vec<syn::ImplItem>>,
+    ) = multiunzip(
+        impl_
+            .items
+            .iter()
+            .map(|item| {
+                rewrite_cached_method(
+                    item,
+                    &field,
+                    &cell_path,
+                    stats,
+                    test_util,
+                    args.verify,
+                    // `Cached` has no `HasCacheFields::set_caching_enabled()` to disable caches.
+                    false,
+                )
+            })
+            .collect::<syn::Result<Vec<_>>>()?,
+    );
+    let accessors = impl_
+        .items
+        .iter()
+        .map(|item| gen_compute_time_accessor(item, &field))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let invalidators = impl_
+        .items
+        .iter()
+        .map(|item| gen_invalidator(item, &field))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let produced = impl_
+        .items
+        .iter()
+        .map(gen_produced_accessors)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let fresh = impl_
+        .items
+        .iter()
+        .map(gen_fresh)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let refreshers = items
+        .iter()
+        .zip(&impl_.items)
+        .map(|(item, original)| gen_refresher(item, original, &field, stats))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let fields = fields.into_iter().flatten().collect_vec();
+    let cache_field_idents = fields
+        .iter()
+        .map(|field| {
+            syn::Field::parse_named
+                .parse2(field.clone())
+                .map(|x| x.ident)
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    // Methods of a trait impl have no visibility. Their signatures are declared in the trait.
+    let mut trait_items = vec![];
+    let mut impl_items = vec![];
+    for item in items
+        .into_iter()
+        .chain(accessors.into_iter().flatten())
+        .chain(invalidators.into_iter().flatten())
+        .chain(produced.into_iter().flatten())
+        .chain(fresh.into_iter().flatten())
+        .chain(refreshers.into_iter().flatten())
+        .chain(test_util_items.into_iter().flatten())
+    {
+        let syn::ImplItem::Fn(mut fn_) = item else {
+            return Err(syn::Error::new(item.span(), "expected only methods"));
+        };
+        fn_.vis = syn::Visibility::Inherited;
+        let sig = &fn_.sig;
+        let attrs = fn_
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc") || attr.path().is_ident("cfg"));
+        trait_items.push(quote! {
+            #(#attrs)*
+            #sig;
+        });
+        impl_items.push(fn_);
+    }
+    let (hidden_idents, hidden_fields) = hidden_fields(test_util);
+    let doc = format!("Caches of [`{name}`].");
+
+    Ok(quote! {
+        #vis trait #name {
+            #(#trait_items)*
+        }
+
+        #[doc = #doc]
+        #vis struct #caches_name {
+            #(#fields,)*
+            #(#hidden_fields,)*
+        }
+
+        impl ::core::default::Default for #caches_name {
+            fn default() -> Self {
+                Self {
+                    #(#cache_field_idents: ::core::default::Default::default(),)*
+                    #(#hidden_idents: ::core::default::Default::default(),)*
+                }
+            }
+        }
+
+        impl #name for ::struct_cache_field::Cached<#inner_ty, #caches_name> {
+            #(#impl_items)*
+        }
+    })
+}
+
+/// Generates the extension trait for `Rc<T>` with `#[cached_ext(side_table)]`.
+///
+/// Each method has its own thread-local `SideTable`. The value is computed without borrowing the
+/// table, so that the body can call other cached methods, and a clone of it is returned.
+fn gen_side_table_ext(
+    impl_: &syn::ItemImpl,
+    name: &syn::Ident,
+    vis: &syn::Visibility,
+    inner_ty: &syn::Type,
+) -> syn::Result<TokenStream> {
+    let mut trait_items = vec![];
+    let mut impl_items = vec![];
+    for item in &impl_.items {
+        let syn::ImplItem::Fn(fn_) = item else {
+            return Err(syn::Error::new(item.span(), "expected only methods"));
+        };
+        if let Some(attr) = fn_.attrs.iter().find(|attr| args::is_cached_attr(attr)) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`#[cached(...)]` is not supported with `side_table`",
+            ));
+        }
+        if !matches!(fn_.sig.receiver(), Some(x) if x.reference.is_some() && x.mutability.is_none())
+            || fn_.sig.inputs.len() != 1
+        {
+            return Err(syn::Error::new_spanned(
+                &fn_.sig,
+                "cached method with `side_table` must take only `&self`",
+            ));
+        }
+        let syn::ReturnType::Type(_, return_ty) = &fn_.sig.output else {
+            return Err(syn::Error::new_spanned(
+                &fn_.sig,
+                "cache-generator method must have return type",
+            ));
+        };
+        let sig = &fn_.sig;
+        let block = &fn_.block;
+        let attrs = &fn_.attrs;
+        let trait_attrs = attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc") || attr.path().is_ident("cfg"));
+        trait_items.push(quote! {
+            #(#trait_attrs)*
+            #sig;
+        });
+        impl_items.push(quote! {
+            #(#attrs)*
+            #sig {
+                ::std::thread_local! {
+                    static TABLE: ::struct_cache_field::SideTable<#inner_ty, #return_ty> =
+                        ::struct_cache_field::SideTable::new();
+                }
+                if let ::core::option::Option::Some(value) = TABLE.with(|table| table.get(self)) {
+                    return value;
+                }
+                let value: #return_ty = (|| #block)();
+                TABLE.with(|table| table.get_or_insert(self, value))
+            }
+        });
+    }
+
+    Ok(quote! {
+        #vis trait #name {
+            #(#trait_items)*
+        }
+
+        impl #name for ::std::rc::Rc<#inner_ty> {
+            #(#impl_items)*
+        }
+    })
+}
+
+/// Constructs a struct annotated with `#[add_cache_field]` with empty caches.
+///
+/// `construct!(Hoge { x: 1, t })` expands to `Hoge { x: 1, t, __cache_fields__: Default::default() }`.
+/// Struct update syntax is supported, and caches are reset even in that case.
+/// If the injected field is renamed, pass the name as `construct!(Hoge { x: 1 }, field = caches)`.
+#[proc_macro]
+pub fn construct(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match syn::parse::Parser::parse(construct_aux, input) {
+        Ok(x) => x.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+fn construct_aux(input: syn::parse::ParseStream) -> syn::Result<TokenStream> {
+    let mut expr: syn::ExprStruct = input.parse()?;
+    let field = if input.is_empty() {
+        config::field()?
+    } else {
+        input.parse::<syn::Token![,]>()?;
+        let key: syn::Ident = input.parse()?;
+        if key != "field" {
+            return Err(syn::Error::new(key.span(), "expected `field = ...`"));
+        }
+        input.parse::<syn::Token![=]>()?;
+        let field = input.parse()?;
+        input.parse::<Option<syn::Token![,]>>()?;
+        field
+    };
+    expr.fields.push(syn::parse_quote! {
+        #field: ::core::default::Default::default()
+    });
+    // Necessary before `..rest`.
+    expr.fields.push_punct(Default::default());
+    Ok(quote! { #expr })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_cached_method_1() -> syn::Result<()> {
+        use quote::ToTokens;
+
+        let item = syn::parse2(quote! {
+            pub fn two_times_x() -> u64 {
+                2 * self.x
+            }
+        })?;
+
+        let expected_item: syn::ImplItem = syn::parse2(quote! {
+            pub fn two_times_x() -> &u64 {{
+                let value: ::core::option::Option<u64> = if ::struct_cache_field::CacheCell::get(&self.__cache_fields__.two_times_x).is_none() {
+                    ::core::option::Option::Some((|| {{
+                        2 * self.x
+                    }})())
+                } else {
+                    ::core::option::Option::None
+                };
+                ::struct_cache_field::CacheCell::get_or_init(&self.__cache_fields__.two_times_x, move || value.unwrap())
+            }}
+        })?;
+        let expected_struct_cache_field = quote! {
+            two_times_x: ::core::cell::OnceCell<u64>
+        };
+
+        let Ok((got_item, Some(got_cache_field), _)) = rewrite_cached_method(
+            &item,
+            &syn::parse_quote!(__cache_fields__),
+            &syn::parse_quote!(::core::cell::OnceCell),
+            false,
+            false,
+            false,
+            false,
+        ) else {
+            panic!();
+        };
+        dbg!(got_item.clone().into_token_stream().to_string());
+        dbg!(expected_item.clone().into_token_stream().to_string());
+        assert_eq!(
+            (got_item, Some(got_cache_field.to_string())),
+            (expected_item, Some(expected_struct_cache_field.to_string()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rewrite_cached_method_2() -> syn::Result<()> {
+        use quote::ToTokens;
+
+        let item = syn::parse2(quote! {
+            fn x_plus_1(&mut self) -> u64 {
+                self.x = self.x + 1;
+                self.x
+            }
+        })?;
+
+        let expected_item: syn::ImplItem = syn::parse2(quote! {
+            fn x_plus_1(&mut self) -> &u64 {{
+                let value: ::core::option::Option<u64> = if ::struct_cache_field::CacheCell::get(&self.__cache_fields__.x_plus_1).is_none() {
+                    ::core::option::Option::Some((|| {{
+                        self.x = self.x + 1;
+                        self.x
+                    }})())
+                } else {
+                    ::core::option::Option::None
+                };
+                ::struct_cache_field::CacheCell::get_or_init(&self.__cache_fields__.x_plus_1, move || value.unwrap())
+            }}
+        })?;
+        let expected_struct_cache_field = quote! {
+            x_plus_1: ::core::cell::OnceCell<u64>
+        };
+
+        let Ok((got_item, Some(got_cache_field), _)) = rewrite_cached_method(
+            &item,
+            &syn::parse_quote!(__cache_fields__),
+            &syn::parse_quote!(::core::cell::OnceCell),
+            false,
+            false,
+            false,
+            false,
+        ) else {
+            panic!();
+        };
+        dbg!(got_item.clone().into_token_stream().to_string());
+        dbg!(expected_item.clone().into_token_stream().to_string());
+        assert_eq!(
+            (got_item, Some(got_cache_field.to_string())),
+            (expected_item, Some(expected_struct_cache_field.to_string()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_to_string() {
+        for ty in [
+            "u64",
+            "&'a mut str",
+            "Vec<(u64, &str)>",
+            "(u64,)",
+            "[u8; 4]",
+            "::std::collections::HashMap<String, u64>",
+            "Box<dyn Fn(u64) -> u64 + Send>",
+            "impl Iterator<Item = u64>",
+        ] {
+            assert_eq!(type_to_string(&syn::parse_str(ty).unwrap()), ty);
+        }
+    }
+
+    #[test]
+    fn test_gen_doc_note() -> syn::Result<()> {
+        let fn_: syn::ImplItemFn = syn::parse2(quote! {
+            /// Returns `x` twice.
+            #[cached(resettable)]
+            fn two_times_x(&self) -> u64 {
+                2 * self.x
+            }
+        })?;
+        let docs = gen_doc_note(&fn_)?
+            .into_iter()
+            .map(
+                |attr| match &attr.meta.require_name_value().unwrap().value {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(doc),
+                        ..
+                    }) => doc.value(),
+                    _ => panic!(),
+                },
+            )
+            .collect_vec();
+        assert_eq!(
+            docs,
+            [
+                "",
+                " # Caching",
+                "",
+                " The value is computed on the first call, and a clone of it is returned afterwards. It is not updated on changes of the fields, but computed again after `invalidate_two_times_x()` or `HasCacheFields::reset_caches()`.",
+            ]
+        );
+
+        Ok(())
+    }
+
+    /// Registers a cache of `method` returning `ty` as the impl at `span` would.
+    fn register_method(
+        key: &syn::Ident,
+        span: &str,
+        method: &str,
+        ty: TokenStream,
+    ) -> syn::Result<()> {
+        let ident = syn::Ident::new(method, Span::call_site());
+        storage::register_cache_fields(
+            key,
+            storage::Site {
+                span: String::from(span),
+                text: quote! { impl #key { fn #ident(&self) -> #ty {} } }.to_string(),
+            },
+            &syn::Generics::default(),
+            storage::CacheFields {
+                field: syn::parse_quote!(__cache_fields__),
+                fields: vec![syn::parse_quote! { #ident: ::core::cell::OnceCell<#ty> }],
+                bounds: vec![],
+                dependencies: vec![(String::from(method), vec![])],
+                return_types: vec![],
+                stats: false,
+                switch: false,
+                test_util: false,
+            },
+        )
+    }
+
+    /// Returns the types of the cache fields registered under `key`, withdrawing them.
+    fn withdraw_types(key: &syn::Ident) -> syn::Result<Vec<String>> {
+        let cache_fields = storage::withdraw_cache_fields(key, &syn::Generics::default())?.unwrap();
+        Ok(cache_fields
+            .fields
+            .iter()
+            .map(|x| x.ty.to_token_stream().to_string())
+            .collect_vec())
+    }
+
+    #[test]
+    fn test_register_cache_fields_again() -> syn::Result<()> {
+        let key: syn::Ident = syn::parse_quote!(Reexpanded);
+        // Expanding the same impl again, e.g. by an IDE after edits adding or renaming methods,
+        // replaces the registration.
+        register_method(&key, "bytes(0..10)", "x", quote! { u64 })?;
+        register_method(&key, "bytes(0..10)", "y", quote! { u32 })?;
+        assert_eq!(
+            withdraw_types(&key)?,
+            [quote! { ::core::cell::OnceCell<u32> }.to_string()]
+        );
+        assert!(storage::withdraw_cache_fields(&key, &syn::Generics::default())?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_register_cache_fields_from_other_spans() -> syn::Result<()> {
+        // The same impl shifted by edits elsewhere is expanded at other spans, which must not
+        // matter.
+        let key: syn::Ident = syn::parse_quote!(Shifted);
+        register_method(&key, "bytes(0..10)", "x", quote! { u64 })?;
+        register_method(&key, "bytes(5..15)", "x", quote! { u64 })?;
+        assert_eq!(
+            withdraw_types(&key)?,
+            [quote! { ::core::cell::OnceCell<u64> }.to_string()]
+        );
+
+        // Another impl of the same name conflicts, even if it has the same cached methods.
+        register_method(&key, "bytes(0..10)", "x", quote! { u64 })?;
+        assert!(register_method(&key, "bytes(20..30)", "x", quote! { u32 }).is_err());
+        assert!(register_method(&key, "bytes(20..30)", "y", quote! { u64 }).is_err());
+        assert_eq!(
+            withdraw_types(&key)?,
+            [quote! { ::core::cell::OnceCell<u64> }.to_string()]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_cfg_alternatives() -> syn::Result<()> {
+        let fields = [
+            quote! { #[cfg(feature = "x")] label: ::core::cell::OnceCell<String> },
+            quote! { #[cfg(not(feature = "x"))] label: ::core::cell::OnceCell<String> },
+            quote! { #[cfg(unix)] #[cfg(test)] label: ::core::cell::OnceCell<u64> },
+            quote! { two_times_x: ::core::cell::OnceCell<u64> },
+        ]
+        .into_iter()
+        .map(|field| syn::Field::parse_named.parse2(field))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+        let expected = [
+            quote! {
+                #[cfg(any(all(feature = "x"), all(not(feature = "x"))))]
+                label: ::core::cell::OnceCell<String>
+            },
+            quote! { #[cfg(unix)] #[cfg(test)] label: ::core::cell::OnceCell<u64> },
+            quote! { two_times_x: ::core::cell::OnceCell<u64> },
+        ];
+        assert_eq!(
+            merge_cfg_alternatives(fields)?
+                .iter()
+                .map(|field| field.to_token_stream().to_string())
+                .collect_vec(),
+            expected.iter().map(ToString::to_string).collect_vec(),
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+use itertools::Itertools;
+use proc_macro2::Span;
+use quote::ToTokens;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use syn::parse::Parser;
+
+/// Key of registrations.
+///
+/// The target is included as a proc macro server may outlive a compilation, e.g. one of an IDE,
+/// and expand items of multiple crates. `CARGO_CRATE_NAME` alone is shared by the lib and bin
+/// targets of a package, so the package and the name of a bin are also included.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Key {
+    package: String,
+    krate: String,
+    bin: String,
+    ty: String,
+}
+
+impl Key {
+    fn new(key: &impl ToTokens) -> Self {
+        let var = |name| std::env::var(name).unwrap_or_default();
+        Self {
+            package: var("CARGO_MANIFEST_DIR"),
+            krate: var("CARGO_CRATE_NAME"),
+            bin: var("CARGO_BIN_NAME"),
+            ty: key.to_token_stream().to_string(),
+        }
+    }
+}
+
+/// Site of the `#[impl_cached_method]` registering cache fields.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Site {
+    /// Location of the impl, which edits elsewhere shift.
+    pub span: String,
+    /// Tokens of the impl, which edits of the impl change.
+    pub text: String,
+}
+
+impl Site {
+    pub(crate) fn of(impl_: &syn::ItemImpl) -> Self {
+        Self {
+            span: format!("{:?}", impl_.impl_token.span),
+            text: impl_.to_token_stream().to_string(),
+        }
+    }
+
+    /// Returns `true` if both are of the same impl, i.e. it is at the same location or unchanged.
+    fn is_same_impl(&self, other: &Self) -> bool {
+        self.span == other.span || self.text == other.text
+    }
+}
+
+pub(crate) struct CacheFields {
+    /// Name of the field to be injected.
+    pub field: syn::Ident,
+    pub fields: Vec<syn::Field>,
+    /// Bounds of the impl which the struct needs to name types of cache fields.
+    pub bounds: Vec<syn::WherePredicate>,
+    /// Names of fields and methods that each cached method accesses via `self`.
+    pub dependencies: Vec<(String, Vec<String>)>,
+    /// Return type of each cached method as written.
+    pub return_types: Vec<(String, String)>,
+    /// Whether caches are wrapped by `Counted`.
+    pub stats: bool,
+    /// Whether caches are wrapped by `Switchable`.
+    pub switch: bool,
+    /// Whether computations are counted in the cache struct.
+    pub test_util: bool,
+}
+
+struct Value {
+    /// Site of the `#[impl_cached_method]` which registered this.
+    site: Site,
+    /// Names of type and lifetime parameters. `None` if the impl is for a concrete instantiation.
+    params: Option<Vec<String>>,
+    field: String,
+    cache_fields: Vec<String>,
+    bounds: Vec<String>,
+    dependencies: Vec<(String, Vec<String>)>,
+    return_types: Vec<(String, String)>,
+    stats: bool,
+    switch: bool,
+    test_util: bool,
+}
+
+// Initialized in const context so that the macros don't require `LazyLock`.
+static STORAGE: Mutex<BTreeMap<Key, Value>> = Mutex::new(BTreeMap::new());
+
+/// Locks the storage. Poisoning is ignored, as a panic in one expansion shouldn't break the others
+/// in a long-lived proc macro server.
+fn storage() -> MutexGuard<'static, BTreeMap<Key, Value>> {
+    STORAGE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Registers cache fields under `key`, which is the name of the struct or an explicit path.
+pub(crate) fn register_cache_fields(
+    key: &impl ToTokens,
+    site: Site,
+    generics: &syn::Generics,
+    cache_fields: CacheFields,
+) -> syn::Result<()> {
+    let CacheFields {
+        field,
+        fields,
+        bounds,
+        dependencies,
+        return_types,
+        stats,
+        switch,
+        test_util,
+    } = cache_fields;
+    let key_ = Key::new(key);
+    // Const parameters are irrelevant to cache fields. If no other parameters remain, the impl is for
+    // a concrete instantiation like `Hoge<u64, 3>` and there is nothing to check.
+    let params = params(generics)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect_vec();
+    let params = (!params.is_empty()).then_some(params);
+    let cache_fields = fields
+        .into_iter()
+        .map(|field| field.to_token_stream().to_string())
+        .collect();
+    let bounds = bounds
+        .into_iter()
+        .map(|bound| bound.to_token_stream().to_string())
+        .collect();
+    let value = Value {
+        site,
+        params,
+        field: field.to_string(),
+        cache_fields,
+        bounds,
+        dependencies,
+        return_types,
+        stats,
+        switch,
+        test_util,
+    };
+
+    let mut storage = storage();
+    // The same impl may be expanded again without expanding the struct, e.g. by an IDE on edits or
+    // by a proc macro server outliving a compilation. Then the previous registration is stale and
+    // replaced. The impl is still at the same location if edited, or unchanged if shifted by edits
+    // elsewhere. Another impl of the same name is neither, which conflicts.
+    if storage.get(&key_).map_or(false, |registered| {
+        !registered.site.is_same_impl(&value.site)
+    }) {
+        return Err(syn::Error::new_spanned(
+            key,
+            "type name conflicted, cache fields already registered. maybe someone forgot to add `#[struct_cache_field::add_cache_field]`?",
+        ));
+    }
+
+    storage.insert(key_, value);
+
+    Ok(())
+}
+
+/// Withdraws cache fields registered under `key`. Returns `None` if nothing is registered.
+pub(crate) fn withdraw_cache_fields(
+    key: &impl ToTokens,
+    generics: &syn::Generics,
+) -> syn::Result<Option<CacheFields>> {
+    let Some(value) = storage().remove(&Key::new(key)) else {
+        return Ok(None);
+    };
+
+    let Some(value_params) = &value.params else {
+        return Ok(Some(parse_cache_fields(value)));
+    };
+    // Cache fields refer to parameters by name, so only names matter. Bounds may differ, e.g. the
+    // impl can add ones the struct lacks, and are checked by the compiler.
+    let params = params(generics);
+    // Point at the first parameter which disagrees rather than the whole generics.
+    let mismatch = (0..params.len().max(value_params.len()))
+        .find(|&i| params.get(i).map(|(name, _)| name) != value_params.get(i));
+    if let Some(i) = mismatch {
+        return Err(match (params.get(i), value_params.get(i)) {
+            (Some((name, span)), Some(impl_name)) => syn::Error::new(
+                *span,
+                format!("generic parameter `{name}` differs from `{impl_name}` at the same position in impl cached methods. parameters must have the same names in the same order"),
+            ),
+            (Some((name, span)), None) => syn::Error::new(
+                *span,
+                format!("generic parameter `{name}` is missing in impl cached methods. parameters must have the same names in the same order"),
+            ),
+            (None, Some(impl_name)) => syn::Error::new_spanned(
+                generics.to_token_stream(),
+                format!("generic parameter `{impl_name}` of impl cached methods is missing in struct definition. parameters must have the same names in the same order"),
+            ),
+            (None, None) => unreachable!(),
+        });
+    }
+
+    Ok(Some(parse_cache_fields(value)))
+}
+
+fn parse_cache_fields(value: Value) -> CacheFields {
+    let fields = value
+        .cache_fields
+        .iter()
+        .map(|field| {
+            syn::Field::parse_named
+                .parse2(field.parse().unwrap())
+                .unwrap()
+        })
+        .collect();
+    let bounds = value
+        .bounds
+        .iter()
+        .map(|bound| syn::parse_str(bound).unwrap())
+        .collect();
+    CacheFields {
+        field: syn::Ident::new(&value.field, Span::call_site()),
+        fields,
+        bounds,
+        dependencies: value.dependencies,
+        return_types: value.return_types,
+        stats: value.stats,
+        switch: value.switch,
+        test_util: value.test_util,
+    }
+}
+
+/// Returns names and spans of type and lifetime parameters, e.g. `'a` and `T`.
+fn params(generics: &syn::Generics) -> Vec<(String, Span)> {
+    generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            syn::GenericParam::Lifetime(x) => Some((x.lifetime.to_string(), x.lifetime.span())),
+            syn::GenericParam::Type(x) => Some((x.ident.to_string(), x.ident.span())),
+            syn::GenericParam::Const(_) => None,
+        })
+        .collect()
+}
@@ -0,0 +1,58 @@
+use crate::FixedMap;
+
+/// Map holding values of a method with `#[cached(capacity = N)]` keyed by arguments.
+///
+/// [`FixedMap`] and `HashedMap` implement it. Implement it for a wrapper of an external cache,
+/// e.g. `moka::sync::Cache`, and give it as `#[cached(capacity = N, backend = "path::to::Map")]`
+/// to leave eviction and concurrency to it. The map is created with `N` on the first call.
+pub trait CacheMap<K, V> {
+    /// Creates an empty map holding about `capacity` entries.
+    fn with_capacity(capacity: usize) -> Self;
+
+    /// Returns a clone of the value for the key.
+    fn get(&self, key: &K) -> Option<V>;
+
+    /// Inserts the entry. The map may evict entries, or keep the value stored first if the key
+    /// already exists.
+    fn insert(&self, key: K, value: V);
+}
+
+/// `N` is fixed, so `capacity` is ignored.
+impl<K, V, const N: usize> CacheMap<K, V> for FixedMap<K, V, N>
+where
+    K: PartialEq,
+    V: Clone,
+{
+    fn with_capacity(_capacity: usize) -> Self {
+        Self::new()
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        FixedMap::get(self, key)
+    }
+
+    fn insert(&self, key: K, value: V) {
+        FixedMap::insert(self, key, value);
+    }
+}
+
+/// `N` is fixed, so `capacity` is ignored.
+#[cfg(feature = "std")]
+impl<K, V, S, const N: usize> CacheMap<K, V> for crate::HashedMap<K, V, S, N>
+where
+    K: std::hash::Hash + Eq,
+    V: Clone,
+    S: std::hash::BuildHasher + Default,
+{
+    fn with_capacity(_capacity: usize) -> Self {
+        Self::new()
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        crate::HashedMap::get(self, key)
+    }
+
+    fn insert(&self, key: K, value: V) {
+        crate::HashedMap::insert(self, key, value);
+    }
+}
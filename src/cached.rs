@@ -0,0 +1,82 @@
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+/// Wrapper holding caches for a value whose type can't be annotated with `#[add_cache_field]`, e.g.
+/// of another crate.
+///
+/// Cached methods for it are defined with [`cached_ext`](crate::cached_ext), and `C` is the cache
+/// struct generated by it.
+pub struct Cached<T, C> {
+    value: T,
+    #[doc(hidden)]
+    pub __cache_fields__: C,
+}
+
+impl<T, C> Cached<T, C>
+where
+    C: Default,
+{
+    /// Wraps the value with empty caches.
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            __cache_fields__: C::default(),
+        }
+    }
+
+    /// Clears all caches.
+    pub fn reset_caches(&mut self) {
+        self.__cache_fields__ = C::default();
+    }
+}
+
+impl<T, C> Cached<T, C> {
+    /// Returns the value, dropping caches.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, C> From<T> for Cached<T, C>
+where
+    C: Default,
+{
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T, C> Deref for Cached<T, C> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Caches are not reset by mutation through this, as fields of a struct with `#[add_cache_field]`.
+impl<T, C> DerefMut for Cached<T, C> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+/// Clones only the value. Caches of the clone are empty.
+impl<T, C> Clone for Cached<T, C>
+where
+    T: Clone,
+    C: Default,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.value.clone())
+    }
+}
+
+impl<T, C> fmt::Debug for Cached<T, C>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Cached").field(&self.value).finish()
+    }
+}
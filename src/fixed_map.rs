@@ -0,0 +1,186 @@
+use crate::HeapSize;
+use core::cell::{Cell, RefCell};
+use core::fmt;
+
+/// Map holding at most `N` entries without heap allocation, used by `#[cached(capacity = N)]`.
+///
+/// Entries are stored inline and looked up linearly, which is fast enough for small `N`. If it is
+/// full, the oldest entry is evicted. Values are cloned on lookup, as an entry can be evicted while
+/// a reference to it is alive.
+pub struct FixedMap<K, V, const N: usize> {
+    entries: RefCell<[Option<Entry<K, V>>; N]>,
+    /// Sequence number of the entry to be inserted next, which tells the oldest entry.
+    next_seq: Cell<u64>,
+    /// Sum of sizes of the entries.
+    bytes: Cell<usize>,
+}
+
+#[derive(Clone)]
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    size: usize,
+    seq: u64,
+}
+
+impl<K, V, const N: usize> FixedMap<K, V, N> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self {
+            entries: RefCell::new(core::array::from_fn(|_| None)),
+            next_seq: Cell::new(0),
+            bytes: Cell::new(0),
+        }
+    }
+
+    /// Returns the maximum number of entries, i.e. `N`.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.borrow().iter().filter(|x| x.is_some()).count()
+    }
+
+    /// Returns `true` if the map has no entry.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the sum of sizes given to [`Self::insert_sized()`] of the entries.
+    pub fn bytes(&self) -> usize {
+        self.bytes.get()
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries.get_mut().iter_mut().for_each(|x| *x = None);
+        self.bytes.set(0);
+    }
+}
+
+impl<K, V, const N: usize> FixedMap<K, V, N> {
+    /// Returns a clone of the value for the key matching `eq`, e.g. comparing `String` keys with
+    /// `&str` without an owned key.
+    pub fn get_by<F>(&self, eq: F) -> Option<V>
+    where
+        F: Fn(&K) -> bool,
+        V: Clone,
+    {
+        self.entries
+            .borrow()
+            .iter()
+            .flatten()
+            .find(|x| eq(&x.key))
+            .map(|x| x.value.clone())
+    }
+}
+
+impl<K, V, const N: usize> FixedMap<K, V, N>
+where
+    K: PartialEq,
+{
+    /// Returns a clone of the value for the key.
+    pub fn get(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.get_by(|x| x == key)
+    }
+
+    /// Inserts the entry, evicting the oldest one if full. The value is kept if the key already
+    /// exists, like cells keeping the value stored first.
+    pub fn insert(&self, key: K, value: V) {
+        self.insert_sized(key, value, 0, usize::MAX);
+    }
+
+    /// Same as [`Self::insert()`], but also evicts the oldest entries until the sum of sizes of the
+    /// entries fits in `max_bytes`. The entry is not inserted if `size` exceeds `max_bytes` alone.
+    pub fn insert_sized(&self, key: K, value: V, size: usize, max_bytes: usize) {
+        if N == 0 || size > max_bytes {
+            return;
+        }
+        let mut entries = self.entries.borrow_mut();
+        if entries.iter().flatten().any(|x| x.key == key) {
+            return;
+        }
+        let slot = loop {
+            let free = entries.iter().position(|x| x.is_none());
+            match free {
+                Some(slot) if self.bytes.get() + size <= max_bytes => break slot,
+                _ => {
+                    let oldest = entries
+                        .iter_mut()
+                        .filter(|x| x.is_some())
+                        .min_by_key(|x| x.as_ref().unwrap().seq)
+                        .unwrap();
+                    let evicted = oldest.take().unwrap();
+                    self.bytes.set(self.bytes.get() - evicted.size);
+                }
+            }
+        };
+        let seq = self.next_seq.get();
+        entries[slot] = Some(Entry {
+            key,
+            value,
+            size,
+            seq,
+        });
+        self.next_seq.set(seq + 1);
+        self.bytes.set(self.bytes.get() + size);
+    }
+}
+
+impl<K, V, const N: usize> Default for FixedMap<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, const N: usize> Clone for FixedMap<K, V, N>
+where
+    K: Clone,
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            next_seq: self.next_seq.clone(),
+            bytes: self.bytes.clone(),
+        }
+    }
+}
+
+impl<K, V, const N: usize> fmt::Debug for FixedMap<K, V, N>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.entries
+                    .borrow()
+                    .iter()
+                    .flatten()
+                    .map(|x| (&x.key, &x.value)),
+            )
+            .finish()
+    }
+}
+
+impl<K, V, const N: usize> HeapSize for FixedMap<K, V, N>
+where
+    K: HeapSize,
+    V: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.entries
+            .borrow()
+            .iter()
+            .flatten()
+            .map(|x| x.key.heap_size() + x.value.heap_size())
+            .sum()
+    }
+}
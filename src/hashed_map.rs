@@ -0,0 +1,263 @@
+use crate::HeapSize;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use std::{fmt, mem};
+
+/// Map holding at most `N` entries in a `HashMap` with the hasher `S`, used by
+/// `#[cached(capacity = N, hasher = "...")]`.
+///
+/// It behaves like [`FixedMap`](crate::FixedMap), but looks up entries by hash rather than
+/// linearly, which suits large `N`. If it is full, the oldest entry is evicted. Values are cloned
+/// on lookup, as an entry can be evicted while a reference to it is alive.
+pub struct HashedMap<K, V, S, const N: usize> {
+    hasher: S,
+    inner: RefCell<Inner<K, V>>,
+}
+
+struct Inner<K, V> {
+    /// Entries by hashes of keys. Keys of the same hash share a bucket.
+    buckets: HashMap<u64, Vec<Entry<K, V>>, BuildHasherDefault<HashIdentity>>,
+    /// Hashes and sequence numbers of entries in the inserted order, which tells the oldest entry.
+    order: VecDeque<(u64, u64)>,
+    /// Sequence number of the entry to be inserted next.
+    next_seq: u64,
+    /// Sum of sizes of the entries.
+    bytes: usize,
+}
+
+#[derive(Clone)]
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    size: usize,
+    seq: u64,
+}
+
+/// Hasher of `buckets`, whose keys are already hashes.
+#[derive(Default)]
+struct HashIdentity(u64);
+
+impl Hasher for HashIdentity {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 << 8) | u64::from(*byte);
+        }
+    }
+
+    fn write_u64(&mut self, x: u64) {
+        self.0 = x;
+    }
+}
+
+impl<K, V, S, const N: usize> HashedMap<K, V, S, N>
+where
+    S: Default,
+{
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self {
+            hasher: S::default(),
+            inner: RefCell::new(Inner {
+                buckets: HashMap::default(),
+                order: VecDeque::new(),
+                next_seq: 0,
+                bytes: 0,
+            }),
+        }
+    }
+}
+
+impl<K, V, S, const N: usize> HashedMap<K, V, S, N> {
+    /// Returns the maximum number of entries, i.e. `N`.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.inner.borrow().order.len()
+    }
+
+    /// Returns `true` if the map has no entry.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the sum of sizes given to [`Self::insert_sized()`] of the entries.
+    pub fn bytes(&self) -> usize {
+        self.inner.borrow().bytes
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        let inner = self.inner.get_mut();
+        inner.buckets.clear();
+        inner.order.clear();
+        inner.bytes = 0;
+    }
+}
+
+impl<K, V, S, const N: usize> HashedMap<K, V, S, N>
+where
+    S: BuildHasher,
+{
+    fn hash<Q>(&self, key: &Q) -> u64
+    where
+        Q: Hash + ?Sized,
+    {
+        let mut hasher = self.hasher.build_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Returns a clone of the value for the key.
+    pub fn get(&self, key: &K) -> Option<V>
+    where
+        K: Hash + Eq,
+        V: Clone,
+    {
+        self.get_by(key, |x| x == key)
+    }
+
+    /// Returns a clone of the value for the key matching `eq`, without an owned key.
+    ///
+    /// `hashed` must hash the same as the key, as `Borrow` requires, e.g. `("a", &1)` for the key
+    /// `(String::from("a"), 1)`.
+    pub fn get_by<Q, F>(&self, hashed: &Q, eq: F) -> Option<V>
+    where
+        Q: Hash + ?Sized,
+        F: Fn(&K) -> bool,
+        V: Clone,
+    {
+        let hash = self.hash(hashed);
+        self.inner
+            .borrow()
+            .buckets
+            .get(&hash)?
+            .iter()
+            .find(|x| eq(&x.key))
+            .map(|x| x.value.clone())
+    }
+
+    /// Inserts the entry, evicting the oldest one if full. The value is kept if the key already
+    /// exists, like cells keeping the value stored first.
+    pub fn insert(&self, key: K, value: V)
+    where
+        K: Hash + Eq,
+    {
+        self.insert_sized(key, value, 0, usize::MAX);
+    }
+
+    /// Same as [`Self::insert()`], but also evicts the oldest entries until the sum of sizes of the
+    /// entries fits in `max_bytes`. The entry is not inserted if `size` exceeds `max_bytes` alone.
+    pub fn insert_sized(&self, key: K, value: V, size: usize, max_bytes: usize)
+    where
+        K: Hash + Eq,
+    {
+        if N == 0 || size > max_bytes {
+            return;
+        }
+        let hash = self.hash(&key);
+        let mut inner = self.inner.borrow_mut();
+        let inner = &mut *inner;
+        if inner
+            .buckets
+            .get(&hash)
+            .map_or(false, |bucket| bucket.iter().any(|x| x.key == key))
+        {
+            return;
+        }
+        while inner.order.len() >= N || inner.bytes + size > max_bytes {
+            let (hash, seq) = inner.order.pop_front().unwrap();
+            let bucket = inner.buckets.get_mut(&hash).unwrap();
+            let evicted = bucket.remove(bucket.iter().position(|x| x.seq == seq).unwrap());
+            if bucket.is_empty() {
+                inner.buckets.remove(&hash);
+            }
+            inner.bytes -= evicted.size;
+        }
+        let seq = inner.next_seq;
+        inner.buckets.entry(hash).or_default().push(Entry {
+            key,
+            value,
+            size,
+            seq,
+        });
+        inner.order.push_back((hash, seq));
+        inner.next_seq = seq + 1;
+        inner.bytes += size;
+    }
+}
+
+impl<K, V, S, const N: usize> Default for HashedMap<K, V, S, N>
+where
+    S: Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, S, const N: usize> Clone for HashedMap<K, V, S, N>
+where
+    K: Clone,
+    V: Clone,
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        let inner = self.inner.borrow();
+        Self {
+            hasher: self.hasher.clone(),
+            inner: RefCell::new(Inner {
+                buckets: inner.buckets.clone(),
+                order: inner.order.clone(),
+                next_seq: inner.next_seq,
+                bytes: inner.bytes,
+            }),
+        }
+    }
+}
+
+impl<K, V, S, const N: usize> fmt::Debug for HashedMap<K, V, S, N>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.borrow();
+        f.debug_map()
+            .entries(inner.order.iter().map(|(hash, seq)| {
+                let entry = inner.buckets[hash].iter().find(|x| x.seq == *seq).unwrap();
+                (&entry.key, &entry.value)
+            }))
+            .finish()
+    }
+}
+
+impl<K, V, S, const N: usize> HeapSize for HashedMap<K, V, S, N>
+where
+    K: HeapSize,
+    V: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        let inner = self.inner.borrow();
+        inner.buckets.capacity() * mem::size_of::<(u64, Vec<Entry<K, V>>)>()
+            + inner.order.capacity() * mem::size_of::<(u64, u64)>()
+            + inner
+                .buckets
+                .values()
+                .map(|bucket| {
+                    bucket.capacity() * mem::size_of::<Entry<K, V>>()
+                        + bucket
+                            .iter()
+                            .map(|x| x.key.heap_size() + x.value.heap_size())
+                            .sum::<usize>()
+                })
+                .sum::<usize>()
+    }
+}
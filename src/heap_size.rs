@@ -0,0 +1,121 @@
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, string::String, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::mem;
+use core::time::Duration;
+
+/// Size of heap memory owned by a value, in bytes.
+///
+/// Used by `#[add_cache_field(heap_size)]` to report memory consumed by caches. Implement it for
+/// types of cached values.
+pub trait HeapSize {
+    /// Returns the size of heap memory owned by the value, excluding `size_of_val(self)`.
+    fn heap_size(&self) -> usize;
+}
+
+macro_rules! impl_heap_size_zero {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl HeapSize for $ty {
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_heap_size_zero!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    Duration,
+);
+
+/// Borrowed data is not owned.
+impl<T> HeapSize for &T
+where
+    T: ?Sized,
+{
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> HeapSize for Vec<T>
+where
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> HeapSize for Box<T>
+where
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        mem::size_of::<T>() + T::heap_size(self)
+    }
+}
+
+impl<T> HeapSize for Option<T>
+where
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, T::heap_size)
+    }
+}
+
+impl<T, const N: usize> HeapSize for [T; N]
+where
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.iter().map(T::heap_size).sum()
+    }
+}
+
+macro_rules! impl_heap_size_tuple {
+    ($(($($name:ident),*)),* $(,)?) => {
+        $(
+            impl<$($name),*> HeapSize for ($($name,)*)
+            where
+                $($name: HeapSize,)*
+            {
+                #[allow(non_snake_case)]
+                fn heap_size(&self) -> usize {
+                    let ($($name,)*) = self;
+                    0 $(+ $name.heap_size())*
+                }
+            }
+        )*
+    };
+}
+
+impl_heap_size_tuple!((A), (A, B), (A, B, C), (A, B, C, D));
@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::needless_doctest_main)]
 
 //! # `struct_cache_field`
@@ -62,262 +63,733 @@
 //! ```
 //!
 //! Note that currently procedural macro in expression position is currently not supported.
-//! So, you need to initialize `__cache_fields__` with `Default::default()` by yourself.
+//! So, you need to initialize `__cache_fields__` with `Default::default()` by yourself, use
+//! [`construct!`], or generate a constructor with `#[add_cache_field(new)]`.
+//!
+//! Place `#[add_cache_field]` above `#[derive(...)]`. Then derives, e.g. `#[derive(Default)]`,
+//! see the injected field, whose type implements `Default`. Derives placed above it are expanded
+//! first and don't know the field, so `#[derive(Default)]` there fails with "missing field
+//! `__cache_fields__`". If the order can't be changed, use `#[add_cache_field(derive_default)]`
+//! instead.
+//!
+//! The injected field is always the last field, so the layout of the other fields is kept with
+//! `#[repr(C)]`. In functional record update, e.g. `Hoge { x: 2, ..old }`, the caches are moved
+//! from `old` with the other fields, and may hold values computed from its `x`. Call
+//! `reset_caches()` afterwards, or update from `Hoge::default()` or a constructor instead, whose
+//! caches are empty.
 //!
 //! You MUST use both `#[impl_cached_method]` and `#[add_cache_field]` together.
-//! If you use only `#[impl_cached_method]`, it can cause a compile error in other crates.
-//! Because this crate uses type-name-keyed compile time storage.
+//! If you use only `#[impl_cached_method]`, it can cause a compile error on other structs of the same
+//! name in the crate. Because this crate uses type-name-keyed compile time storage.
 //! In the above example, `#[impl_cached_method]` registeres data with key `"Hoge"`, and
-//! `#[add_cache_field]` consumes it.
-
-mod storage;
-
-use itertools::{multiunzip, Itertools};
-use proc_macro2::{Span, TokenStream};
-use quote::{quote, TokenStreamExt};
-use syn::parse::Parser;
-use syn::parse_macro_input;
-use syn::spanned::Spanned;
-
-#[proc_macro_attribute]
-pub fn impl_cached_method(
-    args: proc_macro::TokenStream,
-    input: proc_macro::TokenStream,
-) -> proc_macro::TokenStream {
-    let input = parse_macro_input!(input as syn::Item);
-
-    match impl_cached_method_aux(&args.into(), &input) {
-        Ok(x) => x.into(),
-        Err(e) => TokenStream::from_iter([e.into_compile_error(), (quote! { #input })]).into(),
+//! `#[add_cache_field]` consumes it. Registrations are per target of a package, and expanding the
+//! same impl again, as IDEs may do, replaces its own registration. An impl is identified by its
+//! location or its tokens, so either editing it or moving it by edits elsewhere is fine. For a
+//! generic struct, type and lifetime parameters of the impl must have the same names in the same
+//! order as the struct, while bounds may differ. If a cached method returns an associated type, e.g. `T::Output` or
+//! `<Self as Shape>::Area`, the bounds of the impl on `T` or `Self`, e.g. `T: Op` or
+//! `Self: Shape`, are added to the struct and the cache struct, which need them to name the type.
+//! `Self` there is replaced with the type of the impl. The cache struct takes only those bounds,
+//! bounds of allocators, e.g. `A: Allocator` for `Box<T, A>`, and `?Sized` of the struct, so that
+//! naming it, e.g. in a constructor of it, doesn't require bounds like `T: From<String>` its caches
+//! don't need. Return types like `Box<dyn Error + Send + Sync>` are cached as they are.
+//!
+//! A cached method computes its value before borrowing the cache, so that its body can call other
+//! cached methods, even itself, and can mutate `self` via `&mut self`. If the cache is filled
+//! during the computation, the value stored first wins.
+//!
+//! For the same reason, if the body panics, the cache is left empty and the next call computes
+//! again. It holds for thread-safe cells, e.g. `std::sync::OnceLock`, which are neither poisoned nor
+//! deadlocked, and for caches keyed by arguments or type parameters.
+//!
+//! A method returning a reference with an elided lifetime, e.g. `fn name(&self) -> &str`, caches
+//! the owned value given by `ToOwned`, e.g. `String`. Its body returns the owned value and the
+//! method returns a reference to the cache.
+//!
+//! Methods with `self: Rc<Self>` or `self: Arc<Self>` can't return a reference to the cache since
+//! they own the pointer. They return a clone of the cached value instead, which must be `Clone`.
+//! `self: Arc<Self>` also requires a thread-safe cell, e.g. `cell_path = "std::sync::OnceLock"` or
+//! `cell_path = "struct_cache_field::SyncCell"`.
+//!
+//! Methods with `self: Pin<&mut Self>` are supported as well. The cache field is not pinned.
+//!
+//! An `unsafe fn` is cached as well and stays `unsafe`, so callers still need an `unsafe` block.
+//! It is left out of `warm_caches` and `assert_caches_fresh!`, which would call it. An
+//! `extern "C" fn` is not cached but passed through untouched, as it can't return a reference to
+//! the cache across the ABI.
+//!
+//! A method with type parameters, e.g. `fn render<T: Theme>(&self) -> Style`, caches a value per
+//! instantiation in a [`TypeMap`] keyed by `TypeId`. The parameters get a `'static` bound, and the
+//! return type can't mention them. It requires the `alloc` feature.
+//!
+//! `#[cfg(...)]` on a cached method also applies to its cache and the generated code for it.
+//! Alternatives of a method under exclusive conditions, e.g. `#[cfg(feature = "x")]` and
+//! `#[cfg(not(feature = "x"))]`, share one cache if they return the same type.
+//!
+//! A cached method whose body doesn't mention `self` gets a warning, as every instance would cache
+//! the same value. Use `#[cached(const)]` or a `static` instead. The warning is a `deprecated`
+//! lint, so `#[deny(deprecated)]` on the method, the impl or the crate turns it into an error, and
+//! `#[allow(deprecated)]` silences it. Methods with `const`, `shared_by` or `run_once`, and ones
+//! taking arguments or type parameters, which cache a value per key, don't get it.
+//!
+//! `#[repr(align(N))]` on the struct is kept, and the injected field is aligned as usual.
+//! `#[repr(packed)]` is rejected, as cached methods take references into the injected field, which
+//! may be unaligned. Wrap such a struct with [`Cached`] and use `#[cached_ext]` instead.
+//!
+//! The injected field works with `#[pin_project]` of `pin-project` in either order. It has no
+//! `#[pin]`, i.e. it is structurally not pinned, so caches don't affect `Unpin` of the struct.
+//!
+//! `#[add_cache_field]` also implements [`HasCacheFields`] for the struct, which allows to reset
+//! caches and to inspect them generically. `Hoge::CACHED_METHODS` lists names and return types of
+//! cached methods as [`CachedMethod`]. The cache struct has `occupancy()` iterating over pairs of a
+//! method name and whether its cache is filled, which works without `alloc`.
+//!
+//! ## Foreign types
+//!
+//! Types of other crates can't be annotated. Wrap values with [`Cached`] and define cached methods
+//! with `#[cached_ext]` instead, which generates an extension trait and the cache struct. They are
+//! named `name` and `{name}Caches`, and default to `{Type}CachedExt`. `vis = "pub"` gives their
+//! visibility, and `verify`, `stats` and `test_util` work as for `#[impl_cached_method]`. Methods
+//! accept `#[cached(...)]` as in `#[impl_cached_method]`.
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use struct_cache_field::Cached;
+//!
+//! #[struct_cache_field::cached_ext(name = "DurationExt")]
+//! impl Cached<Duration> {
+//!     fn millis_squared(&self) -> u128 {
+//!         self.as_millis() * self.as_millis()
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let d: Cached<Duration, DurationExtCaches> = Cached::new(Duration::from_millis(3));
+//! assert_eq!(d.millis_squared(), &9);
+//! # }
+//! ```
+//!
+//! With `side_table`, `#[cached_ext]` defines methods for `Rc<T>` instead, without wrapping the
+//! value. Values are stored in a thread-local [`SideTable`] per method keyed by the instance, and
+//! methods return clones of them, which must be `Clone`. `#[cached(...)]` is not supported.
+//!
+//! ```rust
+//! use std::rc::Rc;
+//! use std::time::Duration;
+//!
+//! #[struct_cache_field::cached_ext(name = "DurationExt", side_table)]
+//! impl Rc<Duration> {
+//!     fn millis_squared(&self) -> u128 {
+//!         self.as_millis() * self.as_millis()
+//!     }
+//! }
+//!
+//! # fn main() {
+//! let d = Rc::new(Duration::from_millis(3));
+//! assert_eq!(d.millis_squared(), 9);
+//! # }
+//! ```
+//!
+//! ## Features
+//!
+//! - `stats`: Enable `#[impl_cached_method(stats)]` counting hits and misses of caches, and
+//!   `HasCacheFields::cache_stats()` and `set_observer()`. It doesn't change caches of impls not
+//!   opting in, even if enabled by another crate.
+//! - `tracing`: Enable `#[cached(trace)]`.
+//! - `std` (default): Enable `OnceLock` and `SyncCell` as a cell, `#[cached(timed)]`, and
+//!   `#[cached_ext(side_table)]`. Without it, the crate is `no_std`. `stats` and `tracing` require
+//!   it.
+//! - `alloc`: Enable `HasCacheFields::cache_occupancy()`, `#[cached(box_dyn)]`, and methods
+//!   returning references with elided lifetimes. Implied by `std`.
+//! - `once_cell`: Hold caches in `once_cell::unsync::OnceCell` of the `once_cell` crate by default,
+//!   and implement `CacheCell` for it and `once_cell::sync::OnceCell`. The crate requires Rust 1.70
+//!   for `core::cell::OnceCell`, but with this feature it builds on Rust 1.66 with
+//!   `--ignore-rust-version`. `stats` and `switch` still require Rust 1.70.
+//! - `rayon`: Enable `#[impl_cached_method(warm_caches)]` and `warm_caches_par()` computing caches
+//!   in parallel with `rayon`.
+//! - `switch`: Enable `#[impl_cached_method(switch)]` and `HasCacheFields::set_caching_enabled()`.
+//!   It doesn't change caches of impls not opting in, even if enabled by another crate.
+//! - `test-util`: Enable `#[impl_cached_method(test_util)]` generating utilities for tests. Enable
+//!   it in `[dev-dependencies]`, as the counts make the cache struct larger. It doesn't change
+//!   caches of impls not opting in, even if enabled by another crate.
+//! - `persist`: Enable `#[cached(persist = "...")]` persisting values on disk across processes, and
+//!   `set_persist_dir()`. Implies `std`.
+//!
+//! ## Options
+//!
+//! `#[impl_cached_method(...)]` accepts the following arguments.
+//!
+//! - `target = "Hoge"`: Name of the struct. Use it if the impl is for a type alias, e.g.
+//!   `impl HogeAlias { ... }` with `type HogeAlias = Hoge<u64>;`.
+//! - `field = "caches"`: Name of the injected field. Defaults to `__cache_fields__`, or the one
+//!   given by the crate as described in [Defaults of a crate](#defaults-of-a-crate).
+//!   `#[add_cache_field]` follows it.
+//! - `for = "crate::model::Hoge"`: Pair with `#[add_cache_field(from = "crate::model::Hoge")]`
+//!   by the given path instead of the name of the struct. Use it if structs of the same name are
+//!   defined in an interleaved order, e.g. in nested modules, or if the struct is generated under
+//!   another name. The paths are compared as written and need not resolve.
+//! - `cell_path = "my_crate::MyCell"`: Cell type holding caches, e.g. an instrumented one.
+//!   `MyCell<T>` must implement [`CacheCell<T>`]. Defaults to `core::cell::OnceCell`, or
+//!   `once_cell::unsync::OnceCell` with the `once_cell` feature. Use `std::sync::OnceLock` or
+//!   [`SyncCell`] to share the struct between threads.
+//! - `single_thread`: Hold caches in the default cell above, i.e. `OnceCell`, even if the crate
+//!   gives a thread-safe one as described in [Defaults of a crate](#defaults-of-a-crate). The
+//!   struct is then not `Sync`. Use it on single-threaded targets like `wasm32-unknown-unknown`,
+//!   which don't need to pay for synchronization, e.g.
+//!   `#[cfg_attr(target_arch = "wasm32", impl_cached_method(single_thread))]` and
+//!   `#[cfg_attr(not(target_arch = "wasm32"), impl_cached_method)]`. It can't be given with
+//!   `cell_path`.
+//! - `inline`: Mark all generated methods with `#[inline]` for hot paths. Methods already having
+//!   `#[inline(...)]` are left as is.
+//! - `warm_caches`: Implement `WarmCaches` for the struct, whose `warm_caches_par()` computes
+//!   caches of methods taking only `&self` in parallel. Requires the `rayon` feature and a
+//!   thread-safe cell.
+//! - `doc_note`: Append a "Caching" section to docs of each cached method, describing when the
+//!   value is computed and what invalidates it, e.g. `HasCacheFields::reset_caches()`. Methods
+//!   without docs get only the section.
+//! - `dyn_trait` or `dyn_trait = "HogeAccessors"`: Generate a dyn-safe trait `CachedHoge` with the
+//!   cached methods taking `&self` and having no type parameters, and implement it for the struct.
+//!   It lets code holding e.g. `Box<dyn CachedHoge>` reach cached values. The trait has the widest
+//!   visibility of the methods.
+//! - `assert_send_sync`: Assert at compile time that the cache of each cached method is
+//!   `Send + Sync`, so that a struct shared between threads doesn't silently lose it, e.g. by a
+//!   method returning `Rc<T>` or a non-thread-safe cell. The error points at the method and names
+//!   the type of its cache.
+//! - `warn_mut_self`: Warn on cached methods taking `&mut self`, e.g. `x_plus_1` above, as the
+//!   body runs only until the value is cached and later calls silently skip its mutations. The
+//!   warning is a `deprecated` lint like the one on methods not using `self`. Methods with
+//!   `run_once` are left as is.
+//! - `strict`: Reject the methods `warn_mut_self` warns on with a compile error.
+//! - `stats`: Count hits and misses of each cache, which are available via
+//!   `HasCacheFields::cache_stats()`. Each cache is wrapped by `Counted`. Also, hits, misses, and
+//!   resets are reported to the observer registered with `set_observer()`. Requires the `stats`
+//!   feature. Without it, `cache_stats()` returns no entries.
+//! - `switch`: Allow `HasCacheFields::set_caching_enabled()` to disable caches of a value at
+//!   runtime. Each cache is wrapped by `Switchable`. While disabled, values computed on every call
+//!   are kept until caches are enabled again or reset, so that the methods can still return
+//!   references. Requires the `switch` feature. Without it, disabling caches panics.
+//! - `test_util`: For tests, generate `fn two_times_x_init_count(&self) -> usize` returning how
+//!   many times the computation of the method `two_times_x` ran, and
+//!   `fn inject_two_times_x(&self, value: u64)` filling its cache, e.g. with a fake value, which
+//!   panics if already filled. The latter is not generated for `run_once` and methods with type
+//!   parameters. Without the `test-util` feature, e.g. in builds other than tests with it in
+//!   `[dev-dependencies]`, it does nothing.
+//! - `verify`: In debug builds, compute the value again on every cache hit of a method taking
+//!   `&self`, and panic if it differs from the cached one, which catches caches not reset after
+//!   mutation, e.g. in tests of the impl. Only values implementing `PartialEq + Debug` are
+//!   compared. Methods whose values may legitimately change, e.g. ones reading the clock, can opt
+//!   out with `#[cached(no_verify)]`. [`assert_caches_fresh!`] checks all caches of a struct at
+//!   once without it.
+//!
+//! `#[add_cache_field(...)]` accepts the following arguments.
+//!
+//! - `vis = "pub(crate)"`: Visibility of the cache struct and the injected field `__cache_fields__`.
+//!   Use it to construct the struct from other modules. Defaults to the visibility of the struct if
+//!   all fields have the same visibility as the struct, and private otherwise. For a
+//!   `#[non_exhaustive]` struct, `pub` is lowered to `pub(crate)`.
+//! - `name = "HogeCaches"`: Name of the cache struct.
+//! - `expose`: The cache struct and the injected field are `#[doc(hidden)]` as implementation
+//!   details. With this, they are documented instead, e.g. for those constructing or inspecting
+//!   the cache struct directly. Combine with `vis` to control where they are accessible.
+//! - `field = "caches"`: Name of the injected field. Since `#[impl_cached_method]` is expanded first,
+//!   the same name must be given to it.
+//! - `bound = "T: ToOwned"`: Add bounds to the cache struct, for types of cached values which
+//!   need them to be named, e.g. `Cow<'a, T>`. Other bounds of the struct are not copied.
+//! - `field_first`: Inject the field before the other fields rather than after them, e.g. to keep
+//!   hot data fields together after the caches with `#[repr(C)]`.
+//! - `tolerant`: If no cached methods are registered, inject an empty cache struct with a warning
+//!   instead of an error. It happens when the struct is expanded without the impl, e.g. by
+//!   `cargo expand` of a single item. Not for regular builds, as a missing `#[impl_cached_method]`
+//!   is then noticed only by the warning.
+//! - `from = "crate::model::Hoge"`: Pair with `#[impl_cached_method(for = "crate::model::Hoge")]`.
+//! - `new` or `new = "from_fields"`: Generate a constructor taking all fields but the cache field,
+//!   e.g. `Hoge::new(x: u64) -> Hoge`. Enabled automatically for `#[non_exhaustive]` structs,
+//!   which can't be constructed literally in other crates.
+//! - `builder`: Mark the injected field with `#[builder(default, setter(skip))]` so that builders
+//!   derived by `typed-builder` or `derive_builder` don't require it. Enabled automatically if the
+//!   struct derives `TypedBuilder` or `Builder` and `#[add_cache_field]` is placed above it.
+//! - `serde_skip`: Mark the injected field with `#[serde(skip)]`, so that caches are not serialized
+//!   and are empty after deserialization. Enabled automatically if the struct derives `Serialize` or
+//!   `Deserialize` of `serde` and `#[add_cache_field]` is placed above it.
+//! - `serde_persist`: Instead of skipping, serialize computed values with the struct so that they
+//!   can be restored after deserialization. The cache struct implements `Serialize` and
+//!   `Deserialize` and each cache is serialized as `Option<T>`. Requires `serde` as a dependency.
+//! - `rkyv_skip`: Mark the injected field with `#[with(rkyv::with::Skip)]`, so that archives
+//!   omit caches and they are empty after deserialization. Enabled automatically if the struct
+//!   derives `Archive` and `#[add_cache_field]` is placed above it.
+//! - `derive(Debug, Clone)`: Derive traits for the cache struct. Use it if the struct derives them.
+//!   `Default` is always implemented. `PartialEq`, `Eq`, and `Hash` are implemented ignoring
+//!   caches unless given here, so that derived impls of the struct compare only its data.
+//! - `clone = "preserve"` or `clone = "reset"`: Implement `Clone` for the cache struct, so that the
+//!   struct can derive `Clone`. With `"preserve"`, clones carry computed values. With `"reset"`,
+//!   clones start with empty caches and cached types need not be `Clone`.
+//! - `debug_values`: `Debug` is implemented for the cache struct unless given in `derive(...)`,
+//!   showing whether each cache is `<filled>` or `<empty>`. With this, values of caches are shown
+//!   as `Some(value)` or `None` instead.
+//! - `graph`: Generate `const CACHE_GRAPH_DOT: &str` describing which fields and other caches each
+//!   cached method depends on, in Graphviz DOT format. Dependencies are `self.field` and
+//!   `self.method(...)` appearing in the method body.
+//! - `heap_size`: Generate `fn cache_heap_size(&self) -> usize` returning the sum of
+//!   [`HeapSize::heap_size()`] of cached values, so that memory consumed by caches can be reported.
+//!   Types of cached values must implement [`HeapSize`].
+//! - `derive_default`: Implement `Default` for the struct as `#[derive(Default)]` does, so that
+//!   `Hoge { x: 2, ..Default::default() }` works. It is a no-op if the struct derives `Default`
+//!   below `#[add_cache_field]`.
+//! - `snapshot` or `snapshot = "HogeState"`: Generate a struct `HogeCacheSnapshot` holding clones
+//!   of cached values as `Option<T>`, `fn cache_snapshot(&self) -> HogeCacheSnapshot`, and
+//!   `fn restore_cache(&mut self, snapshot: HogeCacheSnapshot)`, so that caches can be rolled back
+//!   with the data, e.g. for undo. Values must be `Clone`.
+//! - `init` or `init = "HogeData"`: Generate a struct `HogeInit` with the same fields and
+//!   attributes (e.g. `#[derive(...)]`) but without the cache field, and `impl From<HogeInit> for Hoge`.
+//!   It is useful to accept plain data, e.g. deserialized one. `impl From<Hoge> for HogeInit`
+//!   dropping the caches is also generated, e.g. to send or persist plain data.
+//!
+//! Each method in `#[impl_cached_method]` can be annotated with `#[cached(...)]`, which accepts the
+//! following arguments. Other attributes, e.g. `#[inline]`, `#[must_use]`, and docs, are kept on
+//! the method.
+//!
+//! - `box_dyn = "Trait"`: Store the value as `Box<dyn Trait>` and return `&dyn Trait`.
+//!   Methods returning `impl Trait` are treated like this automatically.
+//! - `timed`: Record how long the computation took, and generate
+//!   `fn two_times_x_compute_time(&self) -> Option<Duration>` for the method `two_times_x`.
+//!   For a raw identifier like `r#type`, the prefix is dropped, i.e. `type_compute_time`.
+//! - `trace`: On a cache miss, enter a `tracing` span `cache_miss` with the type and the method
+//!   name, and emit an event with the elapsed time. Requires the `tracing` feature.
+//! - `run_once`: Run a method returning `()` only once, e.g. for lazy setup with side effects. The
+//!   method returns `()` rather than `&()`.
+//! - `field_attr(...)`: Attach attributes to the cache field of the method, e.g.
+//!   `field_attr(cfg_attr(feature = "serde", serde(skip)))`.
+//! - `capacity = 16`: Memoize a method taking arguments, keeping values for at most 16 distinct
+//!   arguments in a [`FixedMap`]. The oldest one is evicted if full. It needs no heap, so it also
+//!   works without `alloc`. Arguments must be `Clone + PartialEq`, and the method returns a clone of
+//!   the value rather than a reference. An argument by shared reference like `&str` or `&[u8]` is
+//!   looked up via `Borrow` and stored as `ToOwned::Owned`, e.g. `String`, which is allocated only
+//!   on a miss and requires the `alloc` feature. `stats` doesn't count lookups in it.
+//! - `max_bytes = 1_048_576`: With `capacity`, also evict the oldest entries so that sizes of the
+//!   values sum up to at most the given bytes. The size is `size_of_val()` plus [`HeapSize`] of the
+//!   value, or given by `size_fn = "path::to::fn"` taking `&T` and returning `usize`.
+//! - `hasher = "fxhash::FxBuildHasher"`: With `capacity`, keep values in a [`HashedMap`] looking
+//!   up arguments by hash with the given `BuildHasher` instead of linearly, which suits large
+//!   capacities. Arguments must also be `Hash + Eq`. Requires the `std` feature.
+//! - `backend = "path::to::Map"`: With `capacity`, keep values in the given map implementing
+//!   [`CacheMap`] instead, e.g. a wrapper of `moka::sync::Cache` for its eviction and
+//!   concurrency. The path is given the key and value types as `Map<K, V>`, and the map is created
+//!   by `CacheMap::with_capacity()` with the capacity on the first call. Keys are made owned
+//!   before the lookup. It can't be used with `hasher` or `max_bytes`.
+//! - `key = "(self.config_version, query.id)", key_type = "(u64, u64)"`: With `capacity`, memoize
+//!   by the expression over `self` and arguments rather than by the arguments, which are then not
+//!   stored and need no bounds. The expression is evaluated on every call, so values get stale when
+//!   a field in it changes, e.g. `capacity = 1, key = "self.version"`. The key must be
+//!   `Clone + PartialEq` of the given type.
+//! - `shared_by = "self.path.clone()", key_type = "PathBuf"`: Share values among instances with
+//!   the same key, e.g. ones representing the same file, in a process-wide [`SharedMap`] of the
+//!   method instead of caching per instance. The expression over `self` is evaluated on every
+//!   call, and the method returns a clone of the value. The key must be `Hash + Eq` and both must
+//!   be `Send`. Entries are kept for the whole process, and `reset_caches()` doesn't affect them.
+//!   The map is a `static` in the method, shared by all instantiations, so the impl and the
+//!   method can't have type parameters, and `key_type` and the return type can't name `Self`.
+//!   Requires the `std` feature.
+//! - `weak`: Cache a returned `Rc<T>` or `Arc<T>` as a weak reference in a [`WeakCache`], so that
+//!   the value is dropped when no one else holds it, and computed again on the next access.
+//!   Requires the `std` feature.
+//! - `resettable`: Store the value in a [`Resettable`], i.e. `RefCell<Option<T>>`, rather than
+//!   `OnceCell<T>`, and generate `fn invalidate_two_times_x(&self)` resetting it for the method
+//!   `two_times_x`, e.g. from callbacks holding only a shared reference. The method returns a
+//!   clone of the value, which must be `Clone`. The struct is not `Sync` even with a thread-safe
+//!   cell.
+//! - `scope`: Store the value in the innermost [`CacheScope`] entered on the current thread rather
+//!   than the instance, so that values memoized while serving a request are dropped when the
+//!   request ends, e.g. `let _scope = CacheScope::enter();` at the top of a handler. Without a
+//!   scope, the method computes the value on every call. The method returns a clone of the value,
+//!   which must be `Clone + 'static`. Requires the `std` feature.
+//! - `recompute_if_changed(self.x, self.y)`: Store clones of the fields with the value in a
+//!   [`Tracked`], and compute it again on access if any of them differs, so that the cache follows
+//!   changes of the fields without manual invalidation. The fields must be `Clone + PartialEq`, and
+//!   are compared on every call. The method returns a clone of the value, which must be `Clone`.
+//!   The struct is not `Sync` even with a thread-safe cell. It can't be used in `#[cached_ext]`.
+//! - `unchecked_cell`: Hold the cache in an [`UncheckedCell`], i.e. `UnsafeCell<Option<T>>`,
+//!   rather than the cell of the impl. A hit checks once whether the cell is filled and reads the
+//!   value without checking again, which `OnceCell::get_or_init()` does, for single-threaded hot
+//!   loops where profiling shows the check matters. With `stats` or `switch`, the wrapping cell
+//!   accesses it as any other cell. The cache field is documented with why it is sound. The
+//!   struct is not `Sync`. It can't be used with type parameters.
+//! - `fresh`: Also generate `fn two_times_x_fresh(&self) -> u64` for the method `two_times_x`,
+//!   which runs the body and returns its value, neither reading nor filling the cache, for callers
+//!   which occasionally need a fresh value. It returns what the body returns, e.g. `String` for a
+//!   method returning `&str`.
+//! - `refresh`: Also generate `fn two_times_x_refresh(&mut self) -> &u64` for the method
+//!   `two_times_x`, which resets its cache and returns the value computed again, e.g. after known
+//!   mutations. For a generic method, values for the other type parameters are also dropped.
+//!   Requires the method to take `&self` or `&mut self`, and can't be used with `capacity`.
+//! - `unless = "result.is_empty()"`: Return values for which the expression over `result: &T` is
+//!   `true` without storing them, e.g. an empty search result expected to fill in shortly. Requires
+//!   `capacity`, `shared_by`, `resettable`, `scope`, `recompute_if_changed` or `weak`, which return
+//!   a value rather than a reference to the stored one.
+//! - `produces(min, max)`: Compute several values in one pass. The method returns a tuple of them,
+//!   e.g. `(u64, u64)`, and methods `min` and `max` returning a reference to each element are
+//!   generated. They compute the tuple on first access as the method does.
+//! - `arena = "self.arena"`: Allocate the value in an arena, e.g. `&'a bumpalo::Bump` or
+//!   `&'a typed_arena::Arena<T>`, and cache only the reference. The expression must have a method
+//!   `alloc(value: T) -> &'a T` (or `&'a mut T`), and the method returns `&'a T` while the body
+//!   returns `T`. The arena must outlive the struct, so it is typically a borrowed field.
+//! - `const`: Evaluate the body at compile time into a hidden associated constant, which the method
+//!   returns by value, instead of caching it per instance. The body must be a constant expression,
+//!   so it can use const generics of the impl but not `self` or `return`. The method can't have
+//!   type parameters, and the struct gets no cache for it.
+//! - `persist = "serde_json"`: On a miss, look up the value in the directory given by
+//!   `set_persist_dir()` or the environment variable `STRUCT_CACHE_FIELD_PERSIST_DIR` before
+//!   computing it, and write the computed value there, e.g. for parsing large assets. Files are
+//!   keyed by a stable hash of the type, the method, `self` and arguments, so the struct must
+//!   implement `Hash`, e.g. by `derive(Hash)` ignoring caches. The hash also covers the version of
+//!   the crate, the return type and the body of the method, so that values persisted before
+//!   changing any of them are not loaded. The path is a serde format crate providing `to_vec()` and
+//!   `from_slice()`, e.g. `serde_json` or `rmp_serde`, and the value must implement `Serialize` and
+//!   `DeserializeOwned`. Without the directory, or on I/O errors, the value is just computed. It
+//!   can't be used with `box_dyn`, `arena`, `weak`, `run_once` and `key`. Requires the `persist`
+//!   feature.
+//!
+//! ```rust
+//! #[struct_cache_field::impl_cached_method]
+//! impl Hoge {
+//!     fn adder(&self) -> impl Fn(u64) -> u64 {
+//!         let x = self.x;
+//!         move |y| x + y
+//!     }
+//!
+//!     #[cached(box_dyn = "std::fmt::Display")]
+//!     fn label(&self) -> String {
+//!         format!("x = {}", self.x)
+//!     }
+//! }
+//!
+//! #[struct_cache_field::add_cache_field]
+//! struct Hoge {
+//!     x: u64,
+//! }
+//!
+//! # fn main() {
+//! let hoge = Hoge {
+//!     x: 1,
+//!     __cache_fields__: Default::default(),
+//! };
+//! assert_eq!(hoge.adder()(2), 3);
+//! assert_eq!(hoge.label().to_string(), "x = 1");
+//! # }
+//! ```
+//!
+//! ## Defaults of a crate
+//!
+//! A large crate can give defaults once instead of repeating arguments on every impl block. The
+//! macros read the following environment variables at compile time, which are meant to be set by
+//! the build script of the crate, so that Cargo compiles it again when they change. Arguments
+//! given to the macros take precedence.
+//!
+//! - `STRUCT_CACHE_FIELD_CELL_PATH`: Default of `cell_path`, e.g. `std::sync::OnceLock` to make
+//!   all structs of the crate thread-safe.
+//! - `STRUCT_CACHE_FIELD_FIELD`: Default of `field`, also followed by `construct!`.
+//! - `STRUCT_CACHE_FIELD_STATS`: `true` to count hits and misses in all impls of the crate as with
+//!   `stats`. Requires the `stats` feature.
+//! - `STRUCT_CACHE_FIELD_TEST_UTIL`: `true` to generate utilities for tests in all impls of the
+//!   crate as with `test_util`.
+//! - `STRUCT_CACHE_FIELD_TOLERANT`: `true` to give structs of the crate without cached methods
+//!   empty caches as with `tolerant`.
+//!
+//! ```no_run
+//! // build.rs
+//! fn main() {
+//!     println!("cargo:rustc-env=STRUCT_CACHE_FIELD_CELL_PATH=std::sync::OnceLock");
+//!     println!("cargo:rustc-env=STRUCT_CACHE_FIELD_FIELD=caches");
+//! }
+//! ```
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod cache_map;
+mod cached;
+mod fixed_map;
+#[cfg(feature = "std")]
+mod hashed_map;
+mod heap_size;
+#[cfg(feature = "persist")]
+mod persist;
+mod resettable;
+#[cfg(feature = "std")]
+mod scope;
+#[cfg(feature = "std")]
+mod shared_map;
+#[cfg(feature = "std")]
+mod side_table;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(feature = "switch")]
+mod switch;
+#[cfg(feature = "test-util")]
+mod test_util;
+mod tracked;
+#[cfg(feature = "alloc")]
+mod type_map;
+mod unchecked_cell;
+mod verify;
+#[cfg(feature = "rayon")]
+mod warm;
+#[cfg(feature = "std")]
+mod weak_cache;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+pub use cache_map::CacheMap;
+pub use cached::Cached;
+pub use fixed_map::FixedMap;
+#[cfg(feature = "std")]
+pub use hashed_map::HashedMap;
+pub use heap_size::HeapSize;
+#[cfg(feature = "persist")]
+pub use persist::{persist_dir, set_persist_dir, PERSIST_DIR_ENV};
+pub use resettable::Resettable;
+#[cfg(feature = "std")]
+pub use scope::{CacheScope, ScopedCache};
+#[cfg(feature = "std")]
+pub use shared_map::SharedMap;
+#[cfg(feature = "std")]
+pub use side_table::SideTable;
+#[cfg(feature = "stats")]
+pub use stats::{
+    set_observer, CacheKey, CacheObserver, CacheStat, CacheStats, Counted, SetObserverError,
+};
+pub use struct_cache_field_macros::{add_cache_field, cached_ext, construct, impl_cached_method};
+#[cfg(feature = "switch")]
+pub use switch::Switchable;
+#[cfg(feature = "test-util")]
+pub use test_util::InitCounts;
+pub use tracked::Tracked;
+#[cfg(feature = "alloc")]
+pub use type_map::TypeMap;
+pub use unchecked_cell::UncheckedCell;
+#[cfg(feature = "rayon")]
+pub use warm::{warm_caches_par, WarmCaches};
+#[cfg(feature = "std")]
+pub use weak_cache::{Downgrade, WeakCache};
+
+/// Used by generated code. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::verify::{Verify, VerifyEq, VerifySkip};
+    #[cfg(feature = "alloc")]
+    pub use alloc::{borrow::ToOwned, boxed::Box, vec::Vec};
+    #[cfg(feature = "once_cell")]
+    pub use once_cell;
+    #[cfg(feature = "persist")]
+    pub mod persist {
+        pub use crate::persist::{content_hash, load, store};
     }
+    #[cfg(feature = "rayon")]
+    pub use rayon;
+    #[cfg(feature = "tracing")]
+    pub use tracing;
 }
 
-fn impl_cached_method_aux(args: &TokenStream, input: &syn::Item) -> syn::Result<TokenStream> {
-    if !args.is_empty() {
-        return Err(syn::Error::new_spanned(
-            args,
-            "arguments must be empty `struct_cache_field::impl_cached_method`",
-        ));
+/// Cell holding a cache, `OnceCell<T>` by default. `OnceLock<T>` is also available for structs
+/// shared between threads.
+///
+/// Methods of `#[impl_cached_method]` and the other generated code access caches only through this
+/// trait.
+pub trait CacheCell<T>: Default {
+    /// Returns the value if computed.
+    fn get(&self) -> Option<&T>;
+
+    /// Returns the value, computing it with `f` if not yet.
+    fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T;
+
+    /// Clears the value.
+    fn reset(&mut self);
+}
+
+// `OnceCell` and `OnceLock` are stabilized in Rust 1.70. Skip them on older toolchains, which are
+// supported with the `once_cell` feature.
+#[cfg_attr(feature = "once_cell", rustversion::since(1.70))]
+impl<T> CacheCell<T> for core::cell::OnceCell<T> {
+    fn get(&self) -> Option<&T> {
+        core::cell::OnceCell::get(self)
     }
 
-    let syn::Item::Impl(impl_) = input else {
-        return Err(syn::Error::new(input.span(), "expected `impl ...`"));
-    };
-    if let Some((_, path, for_)) = &impl_.trait_ {
-        let mut spans = TokenStream::new();
-        spans.append_all([path]);
-        spans.append_all([for_]);
-        return Err(syn::Error::new_spanned(
-            spans,
-            "expected `impl ...` without trait",
-        ));
+    fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        core::cell::OnceCell::get_or_init(self, f)
     }
 
-    let (items, fields): (Vec<syn::ImplItem>, Vec<Option<TokenStream>>) = multiunzip(
-        impl_
-            .items
-            .iter()
-            .map(rewrite_cached_method)
-            .collect::<syn::Result<Vec<_>>>()?,
-    );
-    let mut impl_ = impl_.clone();
-    impl_.items = items;
-    let fields = fields.into_iter().flatten().collect_vec();
-    storage::register_cache_fields(&impl_.self_ty, &impl_.generics, fields)?;
-
-    Ok(quote! {
-        #impl_
-    })
+    fn reset(&mut self) {
+        self.take();
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "once_cell", rustversion::since(1.70))]
+impl<T> CacheCell<T> for std::sync::OnceLock<T> {
+    fn get(&self) -> Option<&T> {
+        std::sync::OnceLock::get(self)
+    }
+
+    fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        std::sync::OnceLock::get_or_init(self, f)
+    }
+
+    fn reset(&mut self) {
+        self.take();
+    }
 }
 
-fn rewrite_cached_method(
-    item: &syn::ImplItem,
-) -> syn::Result<(syn::ImplItem, Option<TokenStream>)> {
-    let syn::ImplItem::Fn(fn_) = item else {
-        return Ok((item.clone(), None));
-    };
-    let ident = &fn_.sig.ident;
-    let block = &fn_.block;
-    let syn::ReturnType::Type(_, return_ty) = &fn_.sig.output else {
-        return Err(syn::Error::new_spanned(
-            fn_.sig.clone(),
-            "cache-generator method must have return type",
-        ));
-    };
-    let mut new_fn = fn_.clone();
-    new_fn.block = syn::parse2(quote! {{
-        self.__cache_fields__.#ident.get_or_init(|| {
-            #block
-        })
-    }})
-    .unwrap();
-    new_fn.sig.output = syn::parse2(quote! { -> &#return_ty }).unwrap();
-    let field = quote! {
-        #ident: ::core::cell::OnceCell<#return_ty>
-    };
-    Ok((new_fn.into(), Some(field)))
+#[cfg(feature = "once_cell")]
+impl<T> CacheCell<T> for once_cell::unsync::OnceCell<T> {
+    fn get(&self) -> Option<&T> {
+        once_cell::unsync::OnceCell::get(self)
+    }
+
+    fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        once_cell::unsync::OnceCell::get_or_init(self, f)
+    }
+
+    fn reset(&mut self) {
+        self.take();
+    }
 }
 
-#[proc_macro_attribute]
-pub fn add_cache_field(
-    args: proc_macro::TokenStream,
-    input: proc_macro::TokenStream,
-) -> proc_macro::TokenStream {
-    let input = parse_macro_input!(input as syn::Item);
+#[cfg(all(feature = "once_cell", feature = "std"))]
+impl<T> CacheCell<T> for once_cell::sync::OnceCell<T> {
+    fn get(&self) -> Option<&T> {
+        once_cell::sync::OnceCell::get(self)
+    }
+
+    fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        once_cell::sync::OnceCell::get_or_init(self, f)
+    }
 
-    match add_cache_field_aux(&args.into(), &input) {
-        Ok(x) => x.into(),
-        Err(e) => TokenStream::from_iter([e.into_compile_error(), (quote! { #input })]).into(),
+    fn reset(&mut self) {
+        self.take();
     }
 }
 
-fn add_cache_field_aux(args: &TokenStream, input: &syn::Item) -> syn::Result<TokenStream> {
-    if !args.is_empty() {
-        return Err(syn::Error::new_spanned(
-            args,
-            "arguments must be empty `struct_cache_field::add_cache_field`",
-        ));
+/// Thread-safe cell, i.e. `OnceLock<T>`, for structs shared between threads.
+///
+/// It is `Sync` on every target, including `wasm32` without threads. Use
+/// `#[impl_cached_method(single_thread)]` instead for impls which needn't be `Sync` there, e.g.
+/// under `cfg_attr` of the target.
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "once_cell", rustversion::since(1.70))]
+pub type SyncCell<T> = std::sync::OnceLock<T>;
+
+/// Implemented for structs annotated with `#[add_cache_field]`.
+///
+/// It allows generic code, e.g. test harnesses, to manipulate caches of many types uniformly.
+pub trait HasCacheFields {
+    /// Clears all caches.
+    fn reset_caches(&mut self);
+
+    /// Returns cached methods of the struct, i.e. `CACHED_METHODS` generated for it.
+    fn cached_methods(&self) -> &'static [CachedMethod];
+
+    /// Returns which caches are filled.
+    #[cfg(feature = "alloc")]
+    fn cache_occupancy(&self) -> CacheOccupancy;
+
+    /// Returns hit/miss counts of caches.
+    #[cfg(feature = "stats")]
+    fn cache_stats(&self) -> CacheStats;
+
+    /// Enables or disables all caches. While disabled, cached methods compute the value on every
+    /// call, e.g. to compare timings or to debug a live value. Caches are cleared either way.
+    ///
+    /// Panics on disabling caches of a struct whose impl lacks `#[impl_cached_method(switch)]`.
+    #[cfg(feature = "switch")]
+    fn set_caching_enabled(&mut self, enabled: bool);
+}
+
+/// Metadata of a cached method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CachedMethod {
+    /// Name of the method, e.g. `"two_times_x"`.
+    pub name: &'static str,
+    /// Return type of the method as written, e.g. `"u64"` or `"&str"`.
+    pub return_type: &'static str,
+}
+
+/// Which caches of a struct are filled.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheOccupancy {
+    entries: Vec<(&'static str, bool)>,
+}
+
+#[cfg(feature = "alloc")]
+impl CacheOccupancy {
+    /// Creates from pairs of a method name and whether its cache is filled.
+    pub fn new(entries: Vec<(&'static str, bool)>) -> Self {
+        Self { entries }
+    }
+
+    /// Returns pairs of a method name and whether its cache is filled.
+    pub fn entries(&self) -> &[(&'static str, bool)] {
+        &self.entries
+    }
+
+    /// Iterates over pairs of a method name and whether its cache is filled.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, bool)> + '_ {
+        self.entries.iter().copied()
+    }
+
+    /// Returns names of methods whose caches are filled.
+    pub fn filled(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.entries
+            .iter()
+            .filter(|(_, filled)| *filled)
+            .map(|(name, _)| *name)
+    }
+
+    /// Returns the number of filled caches.
+    pub fn num_filled(&self) -> usize {
+        self.filled().count()
+    }
+
+    /// Returns the number of caches.
+    pub fn len(&self) -> usize {
+        self.entries.len()
     }
 
-    let syn::Item::Struct(struct_) = input else {
-        return Err(syn::Error::new(input.span(), "expected `struct ...`"));
-    };
-    let syn::Fields::Named(fields) = &struct_.fields else {
-        return Err(syn::Error::new(
-            struct_.fields.span(),
-            "expected named fields",
-        ));
-    };
-
-    // Define a new struct holding caches. This makes initialization easy.
-    let cache_fields_struct_name = syn::Ident::new(
-        &format!(
-            "__struct_cache_field__{}CacheFields",
-            &struct_.ident.to_string()
-        ),
-        Span::call_site(),
-    );
-    let cache_fields = storage::withdraw_cache_fields(&struct_.ident, &struct_.generics)?;
-    // Extract type parameter and and make phantom fields for the struct.
-    //
-    // It is easier to use phantom fields rather than checking each type parameter is actually used.
-    // We use them only for `syn::GenericParam::Type`.
-    let mut generics = struct_.generics.clone();
-    generics.params = generics
-        .params
-        .into_iter()
-        .filter(|param| match param {
-            syn::GenericParam::Lifetime(_) | syn::GenericParam::Const(_) => false,
-            syn::GenericParam::Type(_) => true,
-        })
-        .collect();
-    let phantom_fields = generics
-        .params
-        .iter()
-        .enumerate()
-        .map(|(i, param)| {
-            let ident = syn::Ident::new(&format!("_phantom{i}"), Span::call_site());
-            quote! {
-                #ident: ::core::marker::PhantomData<#param>
-            }
-        })
-        .collect_vec();
-    let (_, ty_generics, where_clause) = generics.split_for_impl();
-    let cache_fields_struct = quote! {
-        #[derive(Default)]
-        struct #cache_fields_struct_name #ty_generics #where_clause {
-            #(#cache_fields,)*
-            #(#phantom_fields,)*
-        }
-    };
-
-    // Add the above struct to original struct.
-    let embedding = syn::Field::parse_named
-        .parse2(quote! { __cache_fields__: #cache_fields_struct_name #ty_generics })
-        .unwrap();
-    let mut fields = fields.clone();
-    fields.named.push(embedding);
-    let mut struct_ = struct_.clone();
-    struct_.fields = syn::Fields::Named(fields);
-
-    Ok(quote! {
-        #struct_
-
-        #cache_fields_struct
-    })
+    /// Returns `true` if the struct has no cache.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_rewrite_cached_method_1() -> syn::Result<()> {
-        use quote::ToTokens;
-
-        let item = syn::parse2(quote! {
-            pub fn two_times_x() -> u64 {
-                2 * self.x
-            }
-        })?;
-
-        let expected_item: syn::ImplItem = syn::parse2(quote! {
-            pub fn two_times_x() -> &u64 {
-                self.__cache_fields__.two_times_x.get_or_init(|| {{
-                    2 * self.x
-                }})
-            }
-        })?;
-        let expected_struct_cache_field = quote! {
-            two_times_x: ::core::cell::OnceCell<u64>
-        };
-
-        let Ok((got_item, Some(got_cache_field))) = rewrite_cached_method(&item) else {
-            panic!();
-        };
-        dbg!(got_item.clone().into_token_stream().to_string());
-        dbg!(expected_item.clone().into_token_stream().to_string());
-        assert_eq!(
-            (got_item, Some(got_cache_field.to_string())),
-            (expected_item, Some(expected_struct_cache_field.to_string()))
-        );
-
-        Ok(())
+#[cfg(feature = "alloc")]
+impl IntoIterator for CacheOccupancy {
+    type Item = (&'static str, bool);
+    type IntoIter = alloc::vec::IntoIter<(&'static str, bool)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
     }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> IntoIterator for &'a CacheOccupancy {
+    type Item = (&'static str, bool);
+    type IntoIter = core::iter::Copied<core::slice::Iter<'a, (&'static str, bool)>>;
 
-    #[test]
-    fn test_rewrite_cached_method_2() -> syn::Result<()> {
-        use quote::ToTokens;
-
-        let item = syn::parse2(quote! {
-            fn x_plus_1(&mut self) -> u64 {
-                self.x = self.x + 1;
-                self.x
-            }
-        })?;
-
-        let expected_item: syn::ImplItem = syn::parse2(quote! {
-            fn x_plus_1(&mut self) -> &u64 {
-                self.__cache_fields__.x_plus_1.get_or_init(|| {{
-                    self.x = self.x + 1;
-                    self.x
-                }})
-            }
-        })?;
-        let expected_struct_cache_field = quote! {
-            x_plus_1: ::core::cell::OnceCell<u64>
-        };
-
-        let Ok((got_item, Some(got_cache_field))) = rewrite_cached_method(&item) else {
-            panic!();
-        };
-        dbg!(got_item.clone().into_token_stream().to_string());
-        dbg!(expected_item.clone().into_token_stream().to_string());
-        assert_eq!(
-            (got_item, Some(got_cache_field.to_string())),
-            (expected_item, Some(expected_struct_cache_field.to_string()))
-        );
-
-        Ok(())
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().copied()
     }
 }
@@ -0,0 +1,75 @@
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+/// Environment variable giving the directory of persisted caches, unless [`set_persist_dir()`] is
+/// called.
+pub const PERSIST_DIR_ENV: &str = "STRUCT_CACHE_FIELD_PERSIST_DIR";
+
+static DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Sets the directory of values persisted by `#[cached(persist = "...")]`, overriding
+/// [`PERSIST_DIR_ENV`]. It is created on the first write.
+pub fn set_persist_dir(dir: impl Into<PathBuf>) {
+    *DIR.lock().unwrap_or_else(PoisonError::into_inner) = Some(dir.into());
+}
+
+/// Returns the directory of persisted values, given by [`set_persist_dir()`] or
+/// [`PERSIST_DIR_ENV`]. Values are not persisted if neither is given.
+pub fn persist_dir() -> Option<PathBuf> {
+    DIR.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .or_else(|| std::env::var_os(PERSIST_DIR_ENV).map(PathBuf::from))
+}
+
+/// FNV-1a, which is stable across processes unlike `DefaultHasher`, so that a persisted value is
+/// found again by the next run.
+struct StableHasher(u64);
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+/// Returns the hash of the contents the value is computed from, e.g. the type, the method, `self`
+/// and arguments.
+pub fn content_hash<T>(contents: &T) -> u64
+where
+    T: Hash + ?Sized,
+{
+    let mut hasher = StableHasher(0xcbf29ce484222325);
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn path(method: &str, hash: u64) -> Option<PathBuf> {
+    Some(persist_dir()?.join(format!("{method}-{hash:016x}")))
+}
+
+/// Returns the persisted bytes for the hash, if any.
+pub fn load(method: &str, hash: u64) -> Option<Vec<u8>> {
+    fs::read(path(method, hash)?).ok()
+}
+
+/// Persists the bytes for the hash. Errors are ignored, as the value is computed again on a miss.
+pub fn store(method: &str, hash: u64, bytes: &[u8]) {
+    let Some(path) = path(method, hash) else {
+        return;
+    };
+    // Write to a temporary file first, so that a concurrent reader never sees a partial value.
+    let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+    let _ = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(&tmp, bytes))
+        .and_then(|()| fs::rename(&tmp, &path));
+}
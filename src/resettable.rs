@@ -0,0 +1,92 @@
+use crate::HeapSize;
+use core::cell::RefCell;
+use core::fmt;
+
+/// Value which can be reset through a shared reference, used by `#[cached(resettable)]`.
+///
+/// It is a `RefCell<Option<T>>`, so the struct holding it is not `Sync`. The value is cloned on
+/// lookup, as it can be reset while a reference to it is alive.
+pub struct Resettable<T> {
+    value: RefCell<Option<T>>,
+}
+
+impl<T> Resettable<T> {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self {
+            value: RefCell::new(None),
+        }
+    }
+
+    /// Returns `true` if the value is stored.
+    pub fn is_filled(&self) -> bool {
+        self.value.borrow().is_some()
+    }
+
+    /// Drops the value, so that it is computed again on the next access.
+    pub fn reset(&self) {
+        // Drop outside the borrow, as `Drop` of the value may access the cache.
+        let value = self.value.borrow_mut().take();
+        drop(value);
+    }
+}
+
+impl<T> Resettable<T>
+where
+    T: Clone,
+{
+    /// Returns a clone of the value if stored.
+    pub fn get(&self) -> Option<T> {
+        self.value.borrow().clone()
+    }
+
+    /// Returns a clone of the value if stored, e.g. during the computation of `value`, and stores
+    /// `value` otherwise.
+    pub fn get_or_insert(&self, value: T) -> T {
+        let mut stored = self.value.borrow_mut();
+        match &*stored {
+            Some(stored) => stored.clone(),
+            None => {
+                *stored = Some(value.clone());
+                value
+            }
+        }
+    }
+}
+
+impl<T> Default for Resettable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for Resettable<T>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Resettable<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Resettable")
+            .field(&*self.value.borrow())
+            .finish()
+    }
+}
+
+impl<T> HeapSize for Resettable<T>
+where
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.value.borrow().heap_size()
+    }
+}
@@ -0,0 +1,178 @@
+use crate::HeapSize;
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Values stored in a scope, by indices held by `ScopedCache`.
+type Values = Vec<Box<dyn Any>>;
+
+thread_local! {
+    /// Entered scopes, innermost last, with values stored in them.
+    static SCOPES: RefCell<Vec<(u64, Values)>> = RefCell::new(Vec::new());
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Scope holding values of methods with `#[cached(scope)]`, e.g. for a request of a web service.
+///
+/// Values are stored in the innermost scope entered on the current thread, and dropped together
+/// when the guard is dropped. Without a scope, the methods compute values on every call. The guard
+/// is not `Send`, as scopes are per thread: in async code, enter it where the task doesn't move
+/// between threads, e.g. around a synchronous part of a handler.
+pub struct CacheScope {
+    id: u64,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl CacheScope {
+    /// Enters a new scope, which lasts until the guard is dropped.
+    #[must_use = "the scope ends when the guard is dropped"]
+    pub fn enter() -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        SCOPES.with(|scopes| scopes.borrow_mut().push((id, Vec::new())));
+        Self {
+            id,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Returns `true` if a scope is entered on the current thread.
+    pub fn is_active() -> bool {
+        SCOPES.with(|scopes| !scopes.borrow().is_empty())
+    }
+
+    /// Returns the number of values stored in the scope.
+    pub fn len(&self) -> usize {
+        SCOPES.with(|scopes| {
+            scopes
+                .borrow()
+                .iter()
+                .find(|(id, _)| *id == self.id)
+                .map_or(0, |(_, values)| values.len())
+        })
+    }
+
+    /// Returns `true` if the scope has no value.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for CacheScope {
+    fn drop(&mut self) {
+        // Guards can be dropped out of order, so remove this scope rather than the innermost one.
+        // The thread-local is gone if the guard is dropped on thread exit, with the values.
+        let values = SCOPES.try_with(|scopes| {
+            let mut scopes = scopes.borrow_mut();
+            let index = scopes.iter().position(|(id, _)| *id == self.id)?;
+            Some(scopes.remove(index).1)
+        });
+        // Drop outside the borrow, as `Drop` of the values may access caches.
+        drop(values);
+    }
+}
+
+impl fmt::Debug for CacheScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CacheScope")
+            .field("id", &self.id)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Reference to a value stored in a [`CacheScope`], used by `#[cached(scope)]`.
+///
+/// It holds the scope and the index of the value, so an instance outliving the scope computes the
+/// value again in the next one. The value is cloned on lookup, as the scope can end while a
+/// reference to it is alive.
+pub struct ScopedCache<T> {
+    slot: Mutex<Option<(u64, usize)>>,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> ScopedCache<T> {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self {
+            slot: Mutex::new(None),
+            _value: PhantomData,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<(u64, usize)>> {
+        // The slot is replaced at once, so it is consistent even if poisoned.
+        self.slot.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> ScopedCache<T>
+where
+    T: Clone + 'static,
+{
+    /// Returns a clone of the value if it is stored in a scope entered on the current thread.
+    pub fn get(&self) -> Option<T> {
+        let (id, index) = (*self.lock())?;
+        SCOPES.with(|scopes| {
+            scopes
+                .borrow()
+                .iter()
+                .find(|(x, _)| *x == id)?
+                .1
+                .get(index)?
+                .downcast_ref::<T>()
+                .cloned()
+        })
+    }
+
+    /// Returns a clone of the value if stored, e.g. during the computation of `value`, and stores
+    /// `value` in the innermost scope otherwise. Without a scope, `value` is returned as is.
+    pub fn get_or_insert(&self, value: T) -> T {
+        if let Some(stored) = self.get() {
+            return stored;
+        }
+        if CacheScope::is_active() {
+            // Clone outside the borrow, as `Clone` of the value may access caches.
+            let stored: Box<dyn Any> = Box::new(value.clone());
+            SCOPES.with(|scopes| {
+                if let Some((id, values)) = scopes.borrow_mut().last_mut() {
+                    values.push(stored);
+                    *self.lock() = Some((*id, values.len() - 1));
+                }
+            });
+        }
+        value
+    }
+}
+
+impl<T> Default for ScopedCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The clone refers to the same value.
+impl<T> Clone for ScopedCache<T> {
+    fn clone(&self) -> Self {
+        Self {
+            slot: Mutex::new(*self.lock()),
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for ScopedCache<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ScopedCache").field(&*self.lock()).finish()
+    }
+}
+
+/// The value is owned by the scope, so it is not counted.
+impl<T> HeapSize for ScopedCache<T> {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Process-wide values keyed by `K`, used by `#[cached(shared_by = "...")]`.
+///
+/// Each cached method has its own `static` map, so instances with the same key share one value.
+/// Entries are kept until [`Self::clear()`].
+pub struct SharedMap<K, V> {
+    entries: Mutex<Option<HashMap<K, V>>>,
+}
+
+impl<K, V> SharedMap<K, V> {
+    /// Creates an empty map, usable as the initializer of a `static`.
+    pub const fn new() -> Self {
+        Self {
+            entries: Mutex::new(None),
+        }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.lock().as_ref().map_or(0, HashMap::len)
+    }
+
+    /// Returns `true` if the map has no entry.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all entries.
+    pub fn clear(&self) {
+        *self.lock() = None;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<HashMap<K, V>>> {
+        // Entries are inserted at once, so they are consistent even if poisoned.
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<K, V> SharedMap<K, V>
+where
+    K: Hash + Eq,
+    V: Clone,
+{
+    /// Returns a clone of the value for the key.
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.lock().as_ref()?.get(key).cloned()
+    }
+
+    /// Returns a clone of the value for the key, inserting `value` if absent. The value stored
+    /// first wins, like cells.
+    pub fn get_or_insert(&self, key: K, value: V) -> V {
+        self.lock()
+            .get_or_insert_with(HashMap::new)
+            .entry(key)
+            .or_insert(value)
+            .clone()
+    }
+}
+
+impl<K, V> Default for SharedMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
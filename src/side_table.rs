@@ -0,0 +1,81 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+/// Values associated with `Rc<K>` instances, used by `#[cached_ext(side_table)]`.
+///
+/// Each entry holds a `Weak<K>`, which keeps the allocation, i.e. the identity of the instance,
+/// until the entry is removed. Entries of dropped instances are removed as the table grows.
+pub struct SideTable<K, V> {
+    entries: RefCell<HashMap<*const K, (Weak<K>, V)>>,
+    next_purge: Cell<usize>,
+}
+
+const MIN_PURGE: usize = 16;
+
+impl<K, V> SideTable<K, V> {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self {
+            entries: RefCell::new(HashMap::new()),
+            next_purge: Cell::new(MIN_PURGE),
+        }
+    }
+
+    /// Returns the number of entries, including ones of dropped instances not yet removed.
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    /// Returns `true` if the table has no entry.
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    /// Removes the value of the instance.
+    pub fn remove(&self, key: &Rc<K>) -> Option<V> {
+        self.entries
+            .borrow_mut()
+            .remove(&Rc::as_ptr(key))
+            .map(|(_, value)| value)
+    }
+
+    /// Removes entries of dropped instances.
+    pub fn purge(&self) {
+        let mut entries = self.entries.borrow_mut();
+        entries.retain(|_, (weak, _)| weak.strong_count() > 0);
+        self.next_purge.set(MIN_PURGE.max(2 * entries.len()));
+    }
+}
+
+impl<K, V> SideTable<K, V>
+where
+    V: Clone,
+{
+    /// Returns a clone of the value of the instance.
+    pub fn get(&self, key: &Rc<K>) -> Option<V> {
+        self.entries
+            .borrow()
+            .get(&Rc::as_ptr(key))
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Returns a clone of the value of the instance, inserting `value` if absent.
+    pub fn get_or_insert(&self, key: &Rc<K>, value: V) -> V {
+        if self.len() >= self.next_purge.get() {
+            self.purge();
+        }
+        self.entries
+            .borrow_mut()
+            .entry(Rc::as_ptr(key))
+            .or_insert_with(|| (Rc::downgrade(key), value))
+            .1
+            .clone()
+    }
+}
+
+impl<K, V> Default for SideTable<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,248 @@
+use crate::CacheCell;
+use std::cell::OnceCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// Cell counting hits and misses of another cell `C`.
+///
+/// With the `stats` feature, each cache is wrapped by this.
+pub struct Counted<T, C = OnceCell<T>> {
+    cell: C,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T, C> Counted<T, C> {
+    /// Returns the counts so far. They are kept on reset.
+    pub fn stat(&self) -> CacheStat {
+        CacheStat {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the wrapped cell.
+    pub fn inner(&self) -> &C {
+        &self.cell
+    }
+
+    /// Returns the wrapped cell mutably. Counts are not updated through it.
+    pub fn inner_mut(&mut self) -> &mut C {
+        &mut self.cell
+    }
+}
+
+impl<T, C> Counted<T, C>
+where
+    C: CacheCell<T>,
+{
+    /// Same as [`CacheCell::get_or_init()`], but also notifies the observer.
+    pub fn get_or_init_observed<F>(&self, key: CacheKey, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        let (value, computed) = self.get_or_init_counted(f);
+        if let Some(observer) = OBSERVER.get() {
+            if computed {
+                observer.on_miss(key);
+            } else {
+                observer.on_hit(key);
+            }
+        }
+        value
+    }
+
+    /// Same as [`CacheCell::reset()`], but also notifies the observer.
+    pub fn reset_observed(&mut self, key: CacheKey) {
+        self.cell.reset();
+        if let Some(observer) = OBSERVER.get() {
+            observer.on_reset(key);
+        }
+    }
+
+    fn get_or_init_counted<F>(&self, f: F) -> (&T, bool)
+    where
+        F: FnOnce() -> T,
+    {
+        let mut computed = false;
+        let value = self.cell.get_or_init(|| {
+            computed = true;
+            f()
+        });
+        let counter = if computed { &self.misses } else { &self.hits };
+        counter.fetch_add(1, Ordering::Relaxed);
+        (value, computed)
+    }
+}
+
+impl<T, C> Default for Counted<T, C>
+where
+    C: Default,
+{
+    fn default() -> Self {
+        Self {
+            cell: C::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, C> Clone for Counted<T, C>
+where
+    C: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            cell: self.cell.clone(),
+            hits: AtomicU64::new(self.hits.load(Ordering::Relaxed)),
+            misses: AtomicU64::new(self.misses.load(Ordering::Relaxed)),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, C> fmt::Debug for Counted<T, C>
+where
+    C: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Counted")
+            .field("cell", &self.cell)
+            .field("stat", &self.stat())
+            .finish()
+    }
+}
+
+/// Compares only the cells.
+impl<T, C> PartialEq for Counted<T, C>
+where
+    C: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cell == other.cell
+    }
+}
+
+impl<T, C> Eq for Counted<T, C> where C: Eq {}
+
+impl<T, C> CacheCell<T> for Counted<T, C>
+where
+    C: CacheCell<T>,
+{
+    fn get(&self) -> Option<&T> {
+        self.cell.get()
+    }
+
+    fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        self.get_or_init_counted(f).0
+    }
+
+    fn reset(&mut self) {
+        self.cell.reset();
+    }
+}
+
+/// Hit/miss counts of a cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStat {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStat {
+    /// Returns the ratio of hits, or `None` if the cache is never accessed.
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        (total != 0).then(|| self.hits as f64 / total as f64)
+    }
+}
+
+/// Hit/miss counts of caches of a struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheStats {
+    entries: Vec<(&'static str, CacheStat)>,
+}
+
+impl CacheStats {
+    /// Creates from pairs of a method name and counts of its cache.
+    pub fn new(entries: Vec<(&'static str, CacheStat)>) -> Self {
+        Self { entries }
+    }
+
+    /// Returns pairs of a method name and counts of its cache.
+    pub fn entries(&self) -> &[(&'static str, CacheStat)] {
+        &self.entries
+    }
+
+    /// Returns counts of the cache of the method.
+    pub fn get(&self, name: &str) -> Option<CacheStat> {
+        self.entries
+            .iter()
+            .find(|(x, _)| *x == name)
+            .map(|(_, stat)| *stat)
+    }
+
+    /// Returns the sum of counts of all caches.
+    pub fn total(&self) -> CacheStat {
+        self.entries
+            .iter()
+            .fold(CacheStat::default(), |acc, (_, stat)| CacheStat {
+                hits: acc.hits + stat.hits,
+                misses: acc.misses + stat.misses,
+            })
+    }
+}
+
+/// Identifies a cache for [`CacheObserver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    /// Name of the struct given by `std::any::type_name()`.
+    pub ty: &'static str,
+    /// Name of the cached method.
+    pub method: &'static str,
+}
+
+/// Receives accesses to caches, e.g. to export them as metrics.
+///
+/// Register it with [`set_observer()`].
+pub trait CacheObserver: Send + Sync + 'static {
+    /// Called when a cached value is returned.
+    fn on_hit(&self, _key: CacheKey) {}
+
+    /// Called when a value is computed.
+    fn on_miss(&self, _key: CacheKey) {}
+
+    /// Called when a cache is cleared by [`HasCacheFields::reset_caches()`].
+    ///
+    /// [`HasCacheFields::reset_caches()`]: crate::HasCacheFields::reset_caches
+    fn on_reset(&self, _key: CacheKey) {}
+}
+
+static OBSERVER: OnceLock<Box<dyn CacheObserver>> = OnceLock::new();
+
+/// Registers the global observer. It can be set only once.
+pub fn set_observer(observer: impl CacheObserver) -> Result<(), SetObserverError> {
+    OBSERVER
+        .set(Box::new(observer))
+        .map_err(|_| SetObserverError(()))
+}
+
+/// Returned by [`set_observer()`] if an observer is already registered.
+#[derive(Debug)]
+pub struct SetObserverError(());
+
+impl fmt::Display for SetObserverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("observer is already set")
+    }
+}
+
+impl std::error::Error for SetObserverError {}
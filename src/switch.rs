@@ -0,0 +1,144 @@
+use crate::CacheCell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+/// Cell which can be disabled, wrapping another cell `C`.
+///
+/// With the `switch` feature, each cache is wrapped by this. While disabled, cached methods compute
+/// the value on every call and store it in a scratch, which keeps values until the switch is
+/// flipped or the cache is reset, so that references to them stay valid.
+pub struct Switchable<T, C> {
+    cell: C,
+    enabled: bool,
+    scratch: Scratch<T>,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T, C> Switchable<T, C>
+where
+    C: CacheCell<T>,
+{
+    /// Returns `true` unless disabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables or disables the cache. Values cached so far and ones in the scratch are dropped.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.reset();
+    }
+
+    /// Stores a value computed while disabled and returns a reference to it.
+    pub fn push_scratch(&self, value: T) -> &T {
+        self.scratch.push(value)
+    }
+}
+
+impl<T, C> Default for Switchable<T, C>
+where
+    C: Default,
+{
+    fn default() -> Self {
+        Self {
+            cell: C::default(),
+            enabled: true,
+            scratch: Scratch::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// The scratch is not cloned.
+impl<T, C> Clone for Switchable<T, C>
+where
+    C: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            cell: self.cell.clone(),
+            enabled: self.enabled,
+            scratch: Scratch::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, C> fmt::Debug for Switchable<T, C>
+where
+    C: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Switchable")
+            .field("cell", &self.cell)
+            .field("enabled", &self.enabled)
+            .finish()
+    }
+}
+
+/// Compares only the cells.
+impl<T, C> PartialEq for Switchable<T, C>
+where
+    C: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cell == other.cell
+    }
+}
+
+impl<T, C> Eq for Switchable<T, C> where C: Eq {}
+
+impl<T, C> CacheCell<T> for Switchable<T, C>
+where
+    C: CacheCell<T>,
+{
+    fn get(&self) -> Option<&T> {
+        self.cell.get()
+    }
+
+    fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        self.cell.get_or_init(f)
+    }
+
+    fn reset(&mut self) {
+        self.cell.reset();
+        self.scratch = Scratch::new();
+    }
+}
+
+type Chunk<T> = OnceLock<Box<[OnceLock<T>]>>;
+
+/// Append-only storage. The `k`-th chunk holds `2^k` slots, so that values are never moved.
+struct Scratch<T> {
+    chunks: OnceLock<Box<[Chunk<T>]>>,
+    len: AtomicUsize,
+}
+
+impl<T> Scratch<T> {
+    fn new() -> Self {
+        Self {
+            chunks: OnceLock::new(),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    fn push(&self, value: T) -> &T {
+        // 1-based, so that the chunk is given by the highest bit.
+        let index = self.len.fetch_add(1, Ordering::Relaxed) + 1;
+        let chunk = (usize::BITS - 1 - index.leading_zeros()) as usize;
+        let chunks = self
+            .chunks
+            .get_or_init(|| (0..usize::BITS).map(|_| OnceLock::new()).collect());
+        let slots =
+            chunks[chunk].get_or_init(|| (0..1usize << chunk).map(|_| OnceLock::new()).collect());
+        let slot = &slots[index - (1 << chunk)];
+        // Each slot is given only once.
+        let _ = slot.set(value);
+        slot.get().unwrap()
+    }
+}
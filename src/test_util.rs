@@ -0,0 +1,71 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Numbers of times computations of cached methods ran, held by the cache struct with
+/// `#[impl_cached_method(test_util)]`.
+///
+/// They are read by generated `fn {method}_init_count(&self) -> usize`.
+///
+/// They don't affect comparison of the cache struct, i.e. any two are equal.
+#[derive(Default)]
+pub struct InitCounts {
+    counts: Mutex<Vec<(&'static str, usize)>>,
+}
+
+impl InitCounts {
+    /// Creates counts of zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a run of the computation of the method.
+    pub fn record(&self, method: &'static str) {
+        let mut counts = self.lock();
+        match counts.iter_mut().find(|(x, _)| *x == method) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((method, 1)),
+        }
+    }
+
+    /// Returns how many times the computation of the method ran.
+    pub fn get(&self, method: &str) -> usize {
+        self.lock()
+            .iter()
+            .find(|(x, _)| *x == method)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<(&'static str, usize)>> {
+        // Counts are updated at once, so they are consistent even if poisoned.
+        self.counts.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Clone for InitCounts {
+    fn clone(&self) -> Self {
+        Self {
+            counts: Mutex::new(self.lock().clone()),
+        }
+    }
+}
+
+impl PartialEq for InitCounts {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for InitCounts {}
+
+impl Hash for InitCounts {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+impl fmt::Debug for InitCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.lock().iter().map(|(method, count)| (method, count)))
+            .finish()
+    }
+}
@@ -0,0 +1,102 @@
+use crate::HeapSize;
+use core::cell::RefCell;
+use core::fmt;
+
+/// Value stored with clones of the fields it was computed from, used by
+/// `#[cached(recompute_if_changed(...))]`.
+///
+/// It is a `RefCell<Option<(K, V)>>`, so the struct holding it is not `Sync`. The value is cloned
+/// on lookup, as it can be replaced while a reference to it is alive.
+pub struct Tracked<K, V> {
+    entry: RefCell<Option<(K, V)>>,
+}
+
+impl<K, V> Tracked<K, V> {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self {
+            entry: RefCell::new(None),
+        }
+    }
+
+    /// Returns `true` if the value is stored.
+    pub fn is_filled(&self) -> bool {
+        self.entry.borrow().is_some()
+    }
+
+    /// Drops the value, so that it is computed again on the next access.
+    pub fn reset(&self) {
+        // Drop outside the borrow, as `Drop` of the value may access the cache.
+        let entry = self.entry.borrow_mut().take();
+        drop(entry);
+    }
+
+    /// Stores `value` computed from `key`, replacing the stored one, and returns `value`.
+    pub fn insert(&self, key: K, value: V) -> V
+    where
+        V: Clone,
+    {
+        let old = self.entry.borrow_mut().replace((key, value.clone()));
+        // Drop outside the borrow, as `Drop` of the value may access the cache.
+        drop(old);
+        value
+    }
+}
+
+impl<K, V> Tracked<K, V>
+where
+    V: Clone,
+{
+    /// Returns a clone of the value if stored and `unchanged` returns `true` for its key.
+    pub fn get<F>(&self, unchanged: F) -> Option<V>
+    where
+        F: FnOnce(&K) -> bool,
+    {
+        let entry = self.entry.borrow();
+        let (key, value) = entry.as_ref()?;
+        unchanged(key).then(|| value.clone())
+    }
+}
+
+impl<K, V> Default for Tracked<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Clone for Tracked<K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            entry: self.entry.clone(),
+        }
+    }
+}
+
+impl<K, V> fmt::Debug for Tracked<K, V>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Tracked")
+            .field(&*self.entry.borrow())
+            .finish()
+    }
+}
+
+/// Only the value is counted, as the key is a clone of fields.
+impl<K, V> HeapSize for Tracked<K, V>
+where
+    V: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.entry
+            .borrow()
+            .as_ref()
+            .map_or(0, |(_, value)| value.heap_size())
+    }
+}
@@ -0,0 +1,124 @@
+use crate::HeapSize;
+use alloc::boxed::Box;
+use core::any::TypeId;
+#[cfg(not(feature = "once_cell"))]
+use core::cell::OnceCell;
+use core::{fmt, mem};
+#[cfg(feature = "once_cell")]
+use once_cell::unsync::OnceCell;
+
+/// Map from `TypeId` to a value, used for cached methods with type parameters.
+///
+/// Entries are appended to a linked list and never moved, so references to values stay valid while
+/// the map is borrowed. It is looked up linearly, which is fast enough for a few instantiations.
+pub struct TypeMap<V> {
+    head: OnceCell<Box<Entry<V>>>,
+}
+
+struct Entry<V> {
+    key: TypeId,
+    value: V,
+    next: TypeMap<V>,
+}
+
+impl<V> TypeMap<V> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self {
+            head: OnceCell::new(),
+        }
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if the map has no entry.
+    pub fn is_empty(&self) -> bool {
+        self.head.get().is_none()
+    }
+
+    /// Returns the value for the key.
+    pub fn get(&self, key: TypeId) -> Option<&V> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    /// Returns the value for the key, computing it with `f` if absent. If the key is inserted during
+    /// `f`, the value inserted first wins.
+    pub fn get_or_init<F>(&self, key: TypeId, f: F) -> &V
+    where
+        F: FnOnce() -> V,
+    {
+        if let Some(value) = self.get(key) {
+            return value;
+        }
+        let mut entry = Some(Box::new(Entry {
+            key,
+            value: f(),
+            next: TypeMap::new(),
+        }));
+        let mut map = self;
+        loop {
+            let head = map.head.get_or_init(|| entry.take().unwrap());
+            if head.key == key {
+                return &head.value;
+            }
+            map = &head.next;
+        }
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.head.take();
+    }
+
+    /// Returns pairs of a key and a value in the inserted order.
+    pub fn iter(&self) -> impl Iterator<Item = (TypeId, &V)> {
+        let mut map = self;
+        core::iter::from_fn(move || {
+            let entry = map.head.get()?;
+            map = &entry.next;
+            Some((entry.key, &entry.value))
+        })
+    }
+}
+
+impl<V> Default for TypeMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> Clone for TypeMap<V>
+where
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        let ret = Self::new();
+        for (key, value) in self.iter() {
+            ret.get_or_init(key, || value.clone());
+        }
+        ret
+    }
+}
+
+impl<V> fmt::Debug for TypeMap<V>
+where
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<V> HeapSize for TypeMap<V>
+where
+    V: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.iter()
+            .map(|(_, value)| mem::size_of::<Entry<V>>() + value.heap_size())
+            .sum()
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{CacheCell, HeapSize};
+use core::cell::UnsafeCell;
+use core::fmt;
+
+/// Cell holding a cache as `UnsafeCell<Option<T>>`, used by `#[cached(unchecked_cell)]`.
+///
+/// Cached methods check once whether it is filled and then read the value with
+/// [`get_unchecked()`](Self::get_unchecked), rather than checking again in
+/// `CacheCell::get_or_init()`. If a value is stored during the computation, e.g. by calling the
+/// method again, the value stored first is kept. It is not `Sync`, so the struct holding it isn't
+/// either.
+pub struct UncheckedCell<T> {
+    value: UnsafeCell<Option<T>>,
+}
+
+impl<T> UncheckedCell<T> {
+    /// Creates an empty cell.
+    pub const fn new() -> Self {
+        Self {
+            value: UnsafeCell::new(None),
+        }
+    }
+
+    /// Returns the value without checking whether it is stored.
+    ///
+    /// # Safety
+    ///
+    /// The value must be stored, i.e. [`CacheCell::get()`] returns `Some`.
+    pub unsafe fn get_unchecked(&self) -> &T {
+        // SAFETY: The caller guarantees that the value is stored, and it is not overwritten while
+        // `self` is borrowed, as only `CacheCell::reset()` taking `&mut self` clears it.
+        unsafe { (*self.value.get()).as_ref().unwrap_unchecked() }
+    }
+
+    /// Stores the value unless one is already stored, and returns the stored one.
+    pub fn insert(&self, value: T) -> &T {
+        // A value may have been stored during the computation of this one, e.g. by calling the
+        // method again. Keep it, as references to it may be alive.
+        if self.get().is_none() {
+            // SAFETY: The value is `None`, so no reference to it is alive, and the cell is not
+            // `Sync`, so no other thread accesses it.
+            unsafe { *self.value.get() = Some(value) };
+        }
+        // SAFETY: The value is stored above unless it already was.
+        unsafe { self.get_unchecked() }
+    }
+}
+
+impl<T> CacheCell<T> for UncheckedCell<T> {
+    fn get(&self) -> Option<&T> {
+        // SAFETY: The cell is not `Sync`, and the value is written only while it is `None`, so no
+        // mutable reference to it is alive.
+        unsafe { &*self.value.get() }.as_ref()
+    }
+
+    fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        match self.get() {
+            Some(value) => value,
+            None => self.insert(f()),
+        }
+    }
+
+    fn reset(&mut self) {
+        *self.value.get_mut() = None;
+    }
+}
+
+impl<T> Default for UncheckedCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for UncheckedCell<T>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            value: UnsafeCell::new(self.get().cloned()),
+        }
+    }
+}
+
+impl<T> fmt::Debug for UncheckedCell<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("UncheckedCell").field(&self.get()).finish()
+    }
+}
+
+impl<T> HeapSize for UncheckedCell<T>
+where
+    T: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        self.get().heap_size()
+    }
+}
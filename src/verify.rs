@@ -0,0 +1,74 @@
+//! Comparison of a cached value with a fresh one for `#[impl_cached_method(verify)]` and
+//! `assert_caches_fresh!`.
+//!
+//! Values are compared only if they are `PartialEq + Debug`. It is chosen by method resolution, i.e.
+//! `VerifyEq` for `Verify<T>` is preferred to `VerifySkip` for `&Verify<T>` if it applies, so that
+//! enabling `verify` doesn't require the bounds for all values.
+
+use core::fmt::Debug;
+
+/// A cached value, a fresh one, and the name of the method.
+pub struct Verify<'a, T>(pub &'a T, pub &'a T, pub &'static str);
+
+pub trait VerifyEq {
+    fn verify(&self);
+}
+
+impl<T> VerifyEq for Verify<'_, T>
+where
+    T: PartialEq + Debug,
+{
+    fn verify(&self) {
+        let Verify(cached, fresh, method) = self;
+        assert!(
+            cached == fresh,
+            "cached value of `{method}` differs from a fresh computation: {cached:?} != {fresh:?}. maybe forgot to reset caches after mutation?",
+        );
+    }
+}
+
+pub trait VerifySkip {
+    fn verify(&self);
+}
+
+impl<T> VerifySkip for &Verify<'_, T> {
+    fn verify(&self) {}
+}
+
+/// Panics if any cached value of a struct with `#[impl_cached_method]` differs from a fresh
+/// computation, which means a cache not reset after mutation, e.g. in property tests.
+///
+/// Only methods taking `&self` alone and returning `PartialEq + Debug` values are checked, except
+/// ones with `#[cached(no_verify)]`.
+///
+/// ```rust
+/// #[struct_cache_field::impl_cached_method]
+/// impl Hoge {
+///     fn two_times_x(&self) -> u64 {
+///         2 * self.x
+///     }
+/// }
+///
+/// #[struct_cache_field::add_cache_field]
+/// struct Hoge {
+///     x: u64,
+/// }
+///
+/// # fn main() {
+/// let mut hoge = Hoge {
+///     x: 1,
+///     __cache_fields__: Default::default(),
+/// };
+/// hoge.two_times_x();
+/// hoge.x = 2;
+/// struct_cache_field::HasCacheFields::reset_caches(&mut hoge);
+/// hoge.two_times_x();
+/// struct_cache_field::assert_caches_fresh!(hoge);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_caches_fresh {
+    ($value:expr $(,)?) => {
+        $value.__struct_cache_field_assert_caches_fresh()
+    };
+}
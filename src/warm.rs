@@ -0,0 +1,23 @@
+use rayon::prelude::*;
+
+/// Computes caches eagerly, e.g. to prepare many instances in a batch.
+///
+/// Implemented by `#[impl_cached_method(warm_caches)]` with the `rayon` feature. Methods taking
+/// only `&self` are computed, and the others are skipped. The struct must be `Sync`, i.e. use a
+/// thread-safe cell like `cell_path = "std::sync::OnceLock"`.
+pub trait WarmCaches: Sync {
+    /// Computes the caches one by one.
+    fn warm_caches(&self);
+
+    /// Computes the caches of methods not calling other cached methods in parallel, and then the
+    /// rest.
+    fn warm_caches_par(&self);
+}
+
+/// Computes the caches of the instances in parallel.
+pub fn warm_caches_par<T>(items: &[T])
+where
+    T: WarmCaches,
+{
+    items.par_iter().for_each(T::warm_caches);
+}
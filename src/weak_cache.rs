@@ -0,0 +1,129 @@
+use crate::HeapSize;
+use std::fmt;
+use std::rc::{self, Rc};
+use std::sync::{self, Arc, Mutex, MutexGuard, PoisonError};
+
+/// Shared pointer which can be downgraded to a weak one, i.e. `Rc<T>` and `Arc<T>`.
+pub trait Downgrade: Clone {
+    /// Weak pointer, e.g. `rc::Weak<T>`.
+    type Weak: Clone;
+
+    /// Creates a weak pointer to the value.
+    fn downgrade(&self) -> Self::Weak;
+
+    /// Returns a strong pointer if the value is alive.
+    fn upgrade(weak: &Self::Weak) -> Option<Self>;
+}
+
+impl<T> Downgrade for Rc<T>
+where
+    T: ?Sized,
+{
+    type Weak = rc::Weak<T>;
+
+    fn downgrade(&self) -> Self::Weak {
+        Rc::downgrade(self)
+    }
+
+    fn upgrade(weak: &Self::Weak) -> Option<Self> {
+        weak.upgrade()
+    }
+}
+
+impl<T> Downgrade for Arc<T>
+where
+    T: ?Sized,
+{
+    type Weak = sync::Weak<T>;
+
+    fn downgrade(&self) -> Self::Weak {
+        Arc::downgrade(self)
+    }
+
+    fn upgrade(weak: &Self::Weak) -> Option<Self> {
+        weak.upgrade()
+    }
+}
+
+/// Weak reference to a cached `Rc<T>` or `Arc<T>`, used by `#[cached(weak)]`.
+///
+/// The value is dropped when no strong reference remains outside, and computed again on the next
+/// access.
+pub struct WeakCache<P>
+where
+    P: Downgrade,
+{
+    weak: Mutex<Option<P::Weak>>,
+}
+
+impl<P> WeakCache<P>
+where
+    P: Downgrade,
+{
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self {
+            weak: Mutex::new(None),
+        }
+    }
+
+    /// Returns the value if it is alive.
+    pub fn get(&self) -> Option<P> {
+        self.lock().as_ref().and_then(P::upgrade)
+    }
+
+    /// Returns the alive value if any, e.g. stored during the computation of `value`, and stores
+    /// `value` otherwise.
+    pub fn get_or_insert(&self, value: P) -> P {
+        let mut weak = self.lock();
+        if let Some(alive) = weak.as_ref().and_then(P::upgrade) {
+            return alive;
+        }
+        *weak = Some(value.downgrade());
+        value
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<P::Weak>> {
+        // The value is replaced at once, so it is consistent even if poisoned.
+        self.weak.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<P> Default for WeakCache<P>
+where
+    P: Downgrade,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> Clone for WeakCache<P>
+where
+    P: Downgrade,
+{
+    fn clone(&self) -> Self {
+        Self {
+            weak: Mutex::new(self.lock().clone()),
+        }
+    }
+}
+
+impl<P> fmt::Debug for WeakCache<P>
+where
+    P: Downgrade + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WeakCache").field(&self.get()).finish()
+    }
+}
+
+/// The value is shared, so it is not counted.
+impl<P> HeapSize for WeakCache<P>
+where
+    P: Downgrade,
+{
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
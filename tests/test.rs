@@ -3,4 +3,27 @@ fn ui_test() {
     let t = trybuild::TestCases::new();
     t.pass("tests/ui/pass_*.rs");
     t.compile_fail("tests/ui/fail*.rs");
+    if cfg!(feature = "stats") {
+        t.pass("tests/ui/stats/pass_*.rs");
+    }
+    if cfg!(feature = "once_cell") {
+        t.pass("tests/ui/once_cell/pass_*.rs");
+    }
+    if cfg!(feature = "persist") {
+        t.pass("tests/ui/persist/pass_*.rs");
+    }
+    if cfg!(feature = "rayon") {
+        t.pass("tests/ui/rayon/pass_*.rs");
+    }
+    if cfg!(feature = "switch") {
+        t.pass("tests/ui/switch/pass_*.rs");
+    }
+    if cfg!(feature = "test-util") {
+        t.pass("tests/ui/test_util/pass_*.rs");
+    }
+    if cfg!(feature = "tracing") {
+        t.pass("tests/ui/tracing/pass_*.rs");
+    } else {
+        t.compile_fail("tests/ui/no_tracing/fail_*.rs");
+    }
 }
@@ -0,0 +1,15 @@
+use std::sync::Arc;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(self: Arc<Self>) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {}
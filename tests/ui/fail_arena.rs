@@ -0,0 +1,17 @@
+use typed_arena::Arena;
+
+#[struct_cache_field::impl_cached_method]
+impl<'a> Source<'a> {
+    #[cached(arena = "self.arena")]
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Source<'a> {
+    arena: &'a Arena<usize>,
+    text: String,
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+#[struct_cache_field::impl_cached_method(assert_send_sync, cell_path = "OnceLock")]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    pub fn shared(&self) -> Rc<u64> {
+        Rc::new(self.x)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(unknown)]
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(capacity = 2)]
+    pub fn name(&self, name: &mut String) -> String {
+        format!("{name}{}", self.x)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {}
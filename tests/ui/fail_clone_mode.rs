@@ -0,0 +1,25 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field(clone = "deep")]
+struct Hoge {
+    x: u64,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field(derive(Clone), clone = "reset")]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+// Derives above `#[add_cache_field]` don't see the injected field.
+#[derive(Default)]
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    #[cached(capacity = 2)]
+    pub extern "C" fn x_plus(&self, n: u64) -> u64 {
+        self.x + n
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn x_times(&self, n: u64) -> u64 {
+        n * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+#[struct_cache_field::impl_cached_method]
+impl<'a, T> Hoge<'a, T> {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<'a, T, U> {
+    x: u64,
+    t: &'a T,
+    u: U,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl<'a, T, const N: usize> Fuga<'a, T, N> {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga<'b, T, const N: usize> {
+    x: u64,
+    t: &'b [T; N],
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(capacity = 1, key = "self.version")]
+    pub fn doubled(&self) -> u64 {
+        self.x * 2
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    version: u64,
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+#![deny(unused_must_use)]
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[must_use]
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    hoge.two_times_x();
+}
@@ -0,0 +1,14 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(produces(min, max))]
+    pub fn stats(&self) -> (u64, u64, u64) {
+        (self.x, self.x, self.x)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {}
@@ -0,0 +1,40 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(recompute_if_changed(x))]
+    pub fn doubled(&self) -> u64 {
+        self.x * 2
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    #[cached(recompute_if_changed(self.x))]
+    pub fn name(&self) -> &str {
+        "fuga"
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    x: u64,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Piyo {
+    #[cached(recompute_if_changed(self.x), resettable)]
+    pub fn doubled(&self) -> u64 {
+        self.x * 2
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Piyo {
+    x: u64,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(shared_by = "self.id")]
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    id: u64,
+    x: u64,
+}
+
+fn main() {}
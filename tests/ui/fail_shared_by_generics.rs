@@ -0,0 +1,30 @@
+use std::marker::PhantomData;
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T> {
+    #[cached(shared_by = "self.id", key_type = "u64")]
+    pub fn size(&self) -> usize {
+        std::mem::size_of::<T>()
+    }
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    #[cached(shared_by = "self.id", key_type = "u64")]
+    pub fn this(&self) -> Option<Box<Self>> {
+        None
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<T> {
+    id: u64,
+    _phantom: PhantomData<T>,
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    id: u64,
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+#[struct_cache_field::impl_cached_method(strict)]
+impl Hoge {
+    pub fn x_plus_1(&mut self) -> u64 {
+        self.x += 1;
+        self.x
+    }
+
+    #[cached(run_once)]
+    pub fn setup(&mut self) {
+        self.x = 0;
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+#![deny(deprecated)]
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn doubled(&self) -> u64 {
+        self.x * 2
+    }
+
+    pub fn table(&self) -> Vec<u64> {
+        (0..16).map(|x| x * x).collect()
+    }
+
+    #[allow(deprecated)]
+    pub fn allowed(&self) -> Vec<u64> {
+        (0..16).collect()
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+mod module1 {
+    #[struct_cache_field::impl_cached_method]
+    impl Hoge {
+        pub fn two_times_x(&self) -> u64 {
+            2 * self.x
+        }
+    }
+
+    #[struct_cache_field::add_cache_field]
+    pub struct Hoge {
+        pub x: u64,
+        y: u64,
+    }
+}
+
+fn main() {
+    let _ = module1::Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+}
@@ -0,0 +1,20 @@
+#![deny(deprecated)]
+
+#[struct_cache_field::impl_cached_method(warn_mut_self)]
+impl Hoge {
+    pub fn x_plus_1(&mut self) -> u64 {
+        self.x += 1;
+        self.x
+    }
+
+    pub fn doubled(&self) -> u64 {
+        self.x * 2
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+use struct_cache_field::HasCacheFields;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+#[struct_cache_field::impl_cached_method(cell_path = "once_cell::sync::OnceCell")]
+impl Fuga {
+    pub fn two_times_x(self: Arc<Self>) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+    hoge.x = 2;
+    hoge.reset_caches();
+    assert_eq!(hoge.two_times_x(), &4);
+
+    let fuga = Arc::new(Fuga {
+        x: 1,
+        __cache_fields__: Default::default(),
+    });
+    let handle = {
+        let fuga = fuga.clone();
+        std::thread::spawn(move || fuga.two_times_x())
+    };
+    assert_eq!(handle.join().unwrap(), 2);
+    assert_eq!(fuga.two_times_x(), 2);
+}
@@ -0,0 +1,50 @@
+use std::cell::Cell;
+use typed_arena::Arena;
+
+#[derive(Debug, PartialEq)]
+struct Ast {
+    tokens: Vec<String>,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl<'a> Source<'a> {
+    #[cached(arena = "self.arena")]
+    pub fn ast(&self) -> &'a Ast {
+        self.count.set(self.count.get() + 1);
+        if self.text.is_empty() {
+            return Ast { tokens: vec![] };
+        }
+        Ast {
+            tokens: self.text.split(' ').map(ToOwned::to_owned).collect(),
+        }
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Source<'a> {
+    arena: &'a Arena<Ast>,
+    text: String,
+    count: Cell<usize>,
+}
+
+fn parse<'a>(arena: &'a Arena<Ast>, text: &str) -> &'a Ast {
+    let source = Source {
+        arena,
+        text: text.to_owned(),
+        count: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    let ast = source.ast();
+    assert!(std::ptr::eq(ast, source.ast()));
+    assert_eq!(source.count.get(), 1);
+    // The reference outlives `source`.
+    ast
+}
+
+fn main() {
+    let arena = Arena::new();
+    let ast = parse(&arena, "a b");
+    assert_eq!(ast.tokens, ["a", "b"]);
+    assert!(parse(&arena, "").tokens.is_empty());
+    assert_eq!(arena.len(), 2);
+}
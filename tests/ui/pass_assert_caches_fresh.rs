@@ -0,0 +1,69 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use struct_cache_field::{assert_caches_fresh, HasCacheFields};
+
+struct Opaque(u64);
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    pub fn label(&self) -> &str {
+        format!("x = {}", self.x)
+    }
+
+    #[cached(timed)]
+    pub fn xs(&self) -> Vec<u64> {
+        vec![self.x]
+    }
+
+    pub fn opaque(&self) -> Opaque {
+        Opaque(self.x)
+    }
+
+    #[cached(no_verify)]
+    pub fn unchecked(&self) -> u64 {
+        self.x
+    }
+
+    #[cached(capacity = 4)]
+    pub fn x_times(&self, n: u64) -> u64 {
+        self.x * n
+    }
+
+    #[cfg(any())]
+    pub fn never(&self) -> u64 {
+        0
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_caches_fresh!(hoge);
+    hoge.two_times_x();
+    hoge.label();
+    hoge.xs();
+    hoge.opaque();
+    hoge.unchecked();
+    hoge.x_times(2);
+    assert_caches_fresh!(hoge);
+
+    // Forgot to reset caches.
+    hoge.x = 2;
+    let result = catch_unwind(AssertUnwindSafe(|| assert_caches_fresh!(hoge)));
+    assert!(result.is_err());
+
+    hoge.reset_caches();
+    hoge.two_times_x();
+    hoge.label();
+    assert_caches_fresh!(hoge);
+}
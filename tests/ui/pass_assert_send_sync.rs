@@ -0,0 +1,29 @@
+use std::sync::OnceLock;
+
+#[struct_cache_field::impl_cached_method(assert_send_sync, cell_path = "OnceLock")]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    pub fn name(&self) -> &str {
+        format!("hoge{}", self.x)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_send_sync(&hoge);
+    assert_eq!(*hoge.two_times_x(), 2);
+    assert_eq!(hoge.name(), "hoge1");
+}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+/// Stands for a wrapper of an external cache, e.g. `moka::sync::Cache`.
+struct MutexMap<K, V> {
+    capacity: usize,
+    entries: Mutex<HashMap<K, V>>,
+}
+
+impl<K, V> struct_cache_field::CacheMap<K, V> for MutexMap<K, V>
+where
+    K: Hash + Eq,
+    V: Clone,
+{
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        self.entries.lock().unwrap().get(key).cloned()
+    }
+
+    fn insert(&self, key: K, value: V) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity {
+            entries.clear();
+        }
+        entries.insert(key, value);
+    }
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(capacity = 2, backend = "MutexMap")]
+    pub fn greet(&mut self, name: &str, n: usize) -> String {
+        self.count += 1;
+        format!("{}{}", name, "!".repeat(n))
+    }
+
+    #[cached(capacity = 4, key = "self.version", key_type = "u64", backend = "MutexMap")]
+    pub fn doubled(&mut self) -> u64 {
+        self.count += 1;
+        self.x * 2
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    version: u64,
+    count: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        version: 0,
+        count: 0,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.greet("a", 1), "a!");
+    assert_eq!(hoge.greet("a", 1), "a!");
+    assert_eq!(hoge.count, 1);
+    assert_eq!(hoge.greet("b", 2), "b!!");
+    assert_eq!(hoge.count, 2);
+    // `MutexMap` clears itself when full.
+    assert_eq!(hoge.greet("c", 0), "c");
+    assert_eq!(hoge.greet("a", 1), "a!");
+    assert_eq!(hoge.count, 4);
+
+    assert_eq!(hoge.doubled(), 2);
+    assert_eq!(hoge.doubled(), 2);
+    assert_eq!(hoge.count, 5);
+    hoge.x = 2;
+    hoge.version += 1;
+    assert_eq!(hoge.doubled(), 4);
+    assert_eq!(hoge.count, 6);
+}
@@ -0,0 +1,42 @@
+use std::collections::hash_map::RandomState;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(capacity = 2)]
+    pub fn greet(&mut self, name: &str, n: u64) -> String {
+        self.count += 1;
+        format!("{name}{}", n * self.x)
+    }
+
+    #[cached(capacity = 2, hasher = "RandomState")]
+    pub fn checksum(&mut self, bytes: &[u8]) -> u64 {
+        self.count += 1;
+        bytes.iter().map(|&b| u64::from(b)).sum::<u64>() * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    count: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 2,
+        count: 0,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.greet("a", 1), "a2");
+    assert_eq!(hoge.greet(&String::from("a"), 1), "a2");
+    assert_eq!(hoge.count, 1);
+    assert_eq!(hoge.greet("b", 1), "b2");
+    assert_eq!(hoge.greet("a", 2), "a4");
+    assert_eq!(hoge.count, 3);
+
+    assert_eq!(hoge.checksum(b"ab"), 390);
+    assert_eq!(hoge.checksum(&vec![b'a', b'b']), 390);
+    assert_eq!(hoge.count, 4);
+    assert_eq!(hoge.checksum(&[]), 0);
+    assert_eq!(hoge.count, 5);
+}
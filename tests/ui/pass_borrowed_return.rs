@@ -0,0 +1,37 @@
+use std::path::Path;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn normalized(&self) -> &str {
+        self.name.trim().to_lowercase()
+    }
+
+    #[cached(timed)]
+    pub fn doubled(&self) -> &[u64] {
+        self.xs.iter().map(|x| 2 * x).collect::<Vec<_>>()
+    }
+
+    pub fn path(&self) -> &'_ Path {
+        Path::new("/tmp").join(self.normalized())
+    }
+}
+
+#[struct_cache_field::add_cache_field(debug_values)]
+#[derive(Debug)]
+struct Hoge {
+    name: String,
+    xs: Vec<u64>,
+}
+
+fn main() {
+    let hoge = Hoge {
+        name: " Hoge ".to_string(),
+        xs: vec![1, 2],
+        __cache_fields__: Default::default(),
+    };
+    let normalized: &str = hoge.normalized();
+    assert_eq!(normalized, "hoge");
+    assert_eq!(hoge.doubled(), &[2, 4]);
+    assert_eq!(hoge.path(), Path::new("/tmp/hoge"));
+    assert!(format!("{hoge:?}").contains("normalized: Some(\"hoge\")"));
+}
@@ -0,0 +1,46 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::error::Error;
+use std::hash::BuildHasher;
+
+#[struct_cache_field::impl_cached_method]
+impl<S> Hoge<S>
+where
+    S: BuildHasher + Default,
+{
+    pub fn parsed(&self) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        Ok(self.text.parse()?)
+    }
+
+    pub fn counts(&self) -> HashMap<char, usize, S> {
+        let mut counts = HashMap::with_hasher(S::default());
+        for c in self.text.chars() {
+            *counts.entry(c).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+// The hasher parameter is propagated to the cache struct like an allocator parameter.
+#[struct_cache_field::add_cache_field]
+struct Hoge<S = RandomState> {
+    text: String,
+    _hasher: std::marker::PhantomData<S>,
+}
+
+fn main() {
+    let hoge: Hoge = Hoge {
+        text: "112".to_owned(),
+        _hasher: Default::default(),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(*hoge.parsed().as_ref().unwrap(), 112);
+    assert_eq!(hoge.counts()[&'1'], 2);
+
+    let hoge: Hoge = Hoge {
+        text: "x".to_owned(),
+        _hasher: Default::default(),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.parsed().as_ref().unwrap_err().to_string(), "invalid digit found in string");
+}
@@ -0,0 +1,34 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+#[derive(typed_builder::TypedBuilder)]
+struct Hoge {
+    x: u64,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field(vis = "pub")]
+#[derive(derive_builder::Builder)]
+#[builder(pattern = "owned")]
+pub struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    let hoge = Hoge::builder().x(1).build();
+    assert_eq!(hoge.two_times_x(), &2);
+
+    let fuga = FugaBuilder::default().x(1).build().unwrap();
+    assert_eq!(fuga.two_times_x(), &2);
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+use struct_cache_field::Cached;
+
+#[struct_cache_field::cached_ext(name = "DurationExt")]
+impl Cached<Duration> {
+    /// Returns the square of milliseconds.
+    pub fn millis_squared(&self) -> u128 {
+        self.as_millis() * self.as_millis()
+    }
+
+    #[cached(timed)]
+    fn label(&self) -> &str {
+        format!("{:?}", **self)
+    }
+}
+
+mod ext {
+    #[struct_cache_field::cached_ext(vis = "pub")]
+    impl struct_cache_field::Cached<Vec<u64>> {
+        fn sum(&self) -> u64 {
+            self.iter().sum()
+        }
+    }
+}
+
+use ext::VecCachedExt;
+
+fn main() {
+    let mut d: Cached<Duration, DurationExtCaches> = Cached::new(Duration::from_millis(3));
+    assert_eq!(d.millis_squared(), &9);
+    assert_eq!(d.label(), "3ms");
+    assert!(d.label_compute_time().is_some());
+
+    *d = Duration::from_millis(4);
+    assert_eq!(d.millis_squared(), &9);
+    d.reset_caches();
+    assert_eq!(d.millis_squared(), &16);
+    assert_eq!(d.clone().into_inner(), Duration::from_millis(4));
+
+    let xs: Cached<_, ext::VecCachedExtCaches> = vec![1, 2, 3].into();
+    assert_eq!(xs.sum(), &6);
+    assert_eq!(format!("{xs:?}"), "Cached([1, 2, 3])");
+}
@@ -0,0 +1,76 @@
+use std::fmt::Display;
+use struct_cache_field::{CachedMethod, HasCacheFields};
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T>
+where
+    T: Display,
+{
+    pub fn label(&self) -> &str {
+        format!("{}: {}", self.t, self.x)
+    }
+
+    pub fn pair(&self) -> (u64, Vec<u64>) {
+        (self.x, vec![self.x])
+    }
+
+    pub fn adder(&self) -> impl Fn(u64) -> u64 {
+        let x = self.x;
+        move |y| x + y
+    }
+
+    #[cfg(any())]
+    pub fn disabled(&self) -> u64 {
+        self.x
+    }
+
+    #[cached(run_once)]
+    pub fn setup(&self) {}
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<T>
+where
+    T: Display,
+{
+    x: u64,
+    t: T,
+}
+
+fn names(xs: &[&dyn HasCacheFields]) -> Vec<&'static str> {
+    xs.iter()
+        .flat_map(|x| x.cached_methods())
+        .map(|method| method.name)
+        .collect()
+}
+
+fn main() {
+    assert_eq!(
+        Hoge::<u64>::CACHED_METHODS,
+        &[
+            CachedMethod {
+                name: "label",
+                return_type: "&str",
+            },
+            CachedMethod {
+                name: "pair",
+                return_type: "(u64, Vec<u64>)",
+            },
+            CachedMethod {
+                name: "adder",
+                return_type: "impl Fn(u64) -> u64",
+            },
+            CachedMethod {
+                name: "setup",
+                return_type: "()",
+            },
+        ]
+    );
+
+    let hoge = Hoge {
+        x: 1,
+        t: "t",
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(names(&[&hoge]), vec!["label", "pair", "adder", "setup"]);
+}
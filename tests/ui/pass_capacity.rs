@@ -0,0 +1,49 @@
+use struct_cache_field::{CacheCell, HasCacheFields};
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(capacity = 2)]
+    pub fn x_times(&mut self, n: u64) -> u64 {
+        self.count += 1;
+        n * self.x
+    }
+
+    #[cached(capacity = 4)]
+    pub fn label(&self, prefix: String, mut n: u64) -> String {
+        n += self.x;
+        format!("{prefix}{n}")
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    count: usize,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 2,
+        count: 0,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.x_times(1), 2);
+    assert_eq!(hoge.x_times(2), 4);
+    assert_eq!(hoge.x_times(1), 2);
+    assert_eq!(hoge.count, 2);
+    // Evicts the oldest entry for `1`.
+    assert_eq!(hoge.x_times(3), 6);
+    assert_eq!(hoge.x_times(2), 4);
+    assert_eq!(hoge.count, 3);
+    assert_eq!(hoge.x_times(1), 2);
+    assert_eq!(hoge.count, 4);
+
+    assert_eq!(hoge.label("x = ".to_owned(), 1), "x = 3");
+    let labels = CacheCell::get(&hoge.__cache_fields__.label).unwrap();
+    assert_eq!(labels.len(), 1);
+
+    hoge.x = 3;
+    hoge.reset_caches();
+    assert_eq!(hoge.x_times(1), 3);
+    assert_eq!(hoge.label("x = ".to_owned(), 1), "x = 4");
+}
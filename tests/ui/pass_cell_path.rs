@@ -0,0 +1,76 @@
+use std::cell::{Cell, OnceCell};
+use struct_cache_field::{CacheCell, HasCacheFields};
+
+thread_local! {
+    static COUNT: Cell<usize> = const { Cell::new(0) };
+}
+
+mod my_cell {
+    use super::*;
+
+    /// Counts how many times values are computed.
+    pub struct CountingCell<T> {
+        pub inner: OnceCell<T>,
+    }
+
+    impl<T> Default for CountingCell<T> {
+        fn default() -> Self {
+            Self {
+                inner: OnceCell::new(),
+            }
+        }
+    }
+
+    impl<T> CacheCell<T> for CountingCell<T> {
+        fn get(&self) -> Option<&T> {
+            self.inner.get()
+        }
+
+        fn get_or_init<F>(&self, f: F) -> &T
+        where
+            F: FnOnce() -> T,
+        {
+            self.inner.get_or_init(|| {
+                COUNT.set(COUNT.get() + 1);
+                f()
+            })
+        }
+
+        fn reset(&mut self) {
+            self.inner.take();
+        }
+    }
+}
+
+#[struct_cache_field::impl_cached_method(cell_path = "my_cell::CountingCell")]
+impl<T> Hoge<T>
+where
+    T: ToString,
+{
+    pub fn two_times_t(&self) -> String {
+        self.t.to_string().repeat(2)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<T>
+where
+    T: ToString,
+{
+    t: T,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        t: "t",
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_t(), "tt");
+    assert_eq!(hoge.two_times_t(), "tt");
+    assert_eq!(COUNT.get(), 1);
+
+    hoge.t = "s";
+    hoge.reset_caches();
+    assert_eq!(hoge.two_times_t(), "ss");
+    assert_eq!(COUNT.get(), 2);
+}
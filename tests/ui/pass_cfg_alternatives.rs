@@ -0,0 +1,46 @@
+use struct_cache_field::HasCacheFields;
+
+// Alternatives of a method under exclusive `#[cfg(...)]` share the cache.
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cfg(debug_assertions)]
+    pub fn label(&self) -> String {
+        format!("debug {}", self.x)
+    }
+
+    #[cfg(not(debug_assertions))]
+    pub fn label(&self) -> String {
+        format!("release {}", self.x)
+    }
+
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field(clone = "preserve", debug_values, heap_size, graph)]
+#[derive(Debug, Clone)]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    let expected = if cfg!(debug_assertions) {
+        "debug 1"
+    } else {
+        "release 1"
+    };
+    assert_eq!(hoge.label(), expected);
+    assert_eq!(
+        hoge.cache_occupancy().entries(),
+        &[("label", true), ("two_times_x", false)]
+    );
+    assert_eq!(hoge.cached_methods().len(), 2);
+    assert!(format!("{hoge:?}").contains("label: Some("));
+    hoge.reset_caches();
+    assert_eq!(hoge.cache_occupancy().num_filled(), 0);
+}
@@ -0,0 +1,45 @@
+use struct_cache_field::HasCacheFields;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    // `Missing` doesn't exist. Neither does the cache.
+    #[cfg(any())]
+    #[cached(timed)]
+    pub fn missing(&self) -> Missing {
+        Missing::new(self.x)
+    }
+
+    #[cfg(all())]
+    #[cached(timed)]
+    pub fn x_plus_1(&self) -> u64 {
+        self.x + 1
+    }
+}
+
+#[struct_cache_field::add_cache_field(clone = "preserve", debug_values, heap_size, graph)]
+#[derive(Debug, Clone)]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.x_plus_1(), &2);
+    assert!(hoge.x_plus_1_compute_time().is_some());
+    assert_eq!(
+        hoge.cache_occupancy().entries(),
+        &[("two_times_x", false), ("x_plus_1", true)]
+    );
+    assert_eq!(hoge.clone().cache_occupancy().num_filled(), 1);
+    assert_eq!(hoge.cache_heap_size(), 0);
+    assert!(format!("{hoge:?}").contains("x_plus_1: Some("));
+    hoge.reset_caches();
+    assert_eq!(hoge.cache_occupancy().num_filled(), 0);
+}
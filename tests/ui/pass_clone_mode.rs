@@ -0,0 +1,61 @@
+use std::cell::Cell;
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T>
+where
+    T: ToString,
+{
+    pub fn two_times_t(&self) -> String {
+        self.count.set(self.count.get() + 1);
+        self.t.to_string().repeat(2)
+    }
+}
+
+#[struct_cache_field::add_cache_field(clone = "preserve")]
+#[derive(Clone)]
+struct Hoge<T>
+where
+    T: ToString,
+{
+    t: T,
+    count: Cell<u64>,
+}
+
+struct NotClone(u64);
+
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    pub fn not_clone(&self) -> NotClone {
+        self.count.set(self.count.get() + 1);
+        NotClone(self.x)
+    }
+}
+
+#[struct_cache_field::add_cache_field(clone = "reset")]
+#[derive(Clone)]
+struct Fuga {
+    x: u64,
+    count: Cell<u64>,
+}
+
+fn main() {
+    let hoge = Hoge {
+        t: "t".to_string(),
+        count: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_t(), "tt");
+    let cloned = hoge.clone();
+    assert_eq!(cloned.two_times_t(), "tt");
+    assert_eq!(cloned.count.get(), 1);
+
+    let fuga = Fuga {
+        x: 1,
+        count: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(fuga.not_clone().0, 1);
+    let cloned = fuga.clone();
+    assert_eq!(cloned.not_clone().0, 1);
+    assert_eq!(cloned.count.get(), 2);
+}
@@ -0,0 +1,39 @@
+#[struct_cache_field::impl_cached_method(doc_note)]
+impl<const N: usize> Table<N> {
+    /// Squares of `0..N`.
+    #[cached(const)]
+    pub fn squares(&self) -> [u64; N] {
+        let mut ret = [0; N];
+        let mut i = 0;
+        while i < N {
+            ret[i] = (i * i) as u64;
+            i += 1;
+        }
+        ret
+    }
+
+    #[cached(const)]
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    pub fn sum(&self) -> u64 {
+        self.squares().iter().sum::<u64>() + self.offset
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Table<const N: usize> {
+    offset: u64,
+}
+
+fn main() {
+    let table = Table::<4> {
+        offset: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(table.squares(), [0, 1, 4, 9]);
+    assert_eq!(table.len(), 4);
+    assert_eq!(*table.sum(), 15);
+    assert_eq!(Table::<4>::CACHED_METHODS.len(), 1);
+}
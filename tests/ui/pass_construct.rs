@@ -0,0 +1,46 @@
+use struct_cache_field::construct;
+
+#[struct_cache_field::impl_cached_method]
+impl<'a> Hoge<'a> {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<'a> {
+    x: u64,
+    t: &'a str,
+}
+
+#[struct_cache_field::impl_cached_method(field = "caches")]
+impl Fuga {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    let t = "t".to_string();
+    let hoge = construct!(Hoge { x: 1, t: &t });
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.t, "t");
+
+    // Field init shorthand.
+    let x = 2;
+    let t = "t";
+    let hoge2 = construct!(Hoge { x, t });
+    assert_eq!(hoge2.two_times_x(), &4);
+
+    // Caches are not inherited by struct update syntax.
+    let hoge3 = construct!(Hoge { x: 3, ..hoge });
+    assert_eq!(hoge3.two_times_x(), &6);
+
+    let fuga = construct!(Fuga { x: 1 }, field = caches);
+    assert_eq!(fuga.two_times_x(), &2);
+}
@@ -0,0 +1,48 @@
+mod module1 {
+    #[struct_cache_field::impl_cached_method]
+    impl<'a, T> Hoge<'a, T>
+    where
+        T: ToString,
+    {
+        pub fn two_times_x(&self) -> u64 {
+            2 * self.x
+        }
+
+        pub fn t_and_s(&self) -> String {
+            format!("{}{}", self.t.to_string(), self.s)
+        }
+    }
+
+    #[struct_cache_field::add_cache_field(new)]
+    pub struct Hoge<'a, T>
+    where
+        T: ToString,
+    {
+        pub x: u64,
+        t: T,
+        s: &'a str,
+    }
+
+    #[struct_cache_field::impl_cached_method]
+    impl Fuga {
+        pub fn two_times_x(&self) -> u64 {
+            2 * self.x
+        }
+    }
+
+    #[struct_cache_field::add_cache_field(new = "from_x")]
+    pub struct Fuga {
+        x: u64,
+    }
+}
+
+fn main() {
+    let mut hoge = module1::Hoge::new(1, 't', "s");
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.t_and_s(), "ts");
+    hoge.x = 2;
+    assert_eq!(hoge.two_times_x(), &2);
+
+    let fuga = module1::Fuga::from_x(1);
+    assert_eq!(fuga.two_times_x(), &2);
+}
@@ -0,0 +1,66 @@
+struct NotDebug;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    #[allow(deprecated)]
+    pub fn not_debug(&self) -> NotDebug {
+        NotDebug
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+#[derive(Debug)]
+struct Hoge {
+    x: u64,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Fuga<T>
+where
+    T: Clone,
+{
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    pub fn t(&self) -> T {
+        self.t.clone()
+    }
+}
+
+#[struct_cache_field::add_cache_field(name = "FugaCaches", debug_values)]
+#[derive(Debug)]
+struct Fuga<T>
+where
+    T: Clone,
+{
+    x: u64,
+    t: T,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(
+        format!("{hoge:?}"),
+        "Hoge { x: 1, __cache_fields__: __struct_cache_field__HogeCacheFields { two_times_x: <filled>, not_debug: <empty> } }"
+    );
+
+    let fuga = Fuga {
+        x: 1,
+        t: "t",
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(fuga.two_times_x(), &2);
+    assert_eq!(
+        format!("{fuga:?}"),
+        r#"Fuga { x: 1, t: "t", __cache_fields__: FugaCaches { two_times_x: Some(2), t: None } }"#
+    );
+}
@@ -0,0 +1,35 @@
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T>
+where
+    T: ToString,
+{
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    pub fn t(&self) -> String {
+        self.t.to_string()
+    }
+}
+
+#[struct_cache_field::add_cache_field(derive_default)]
+struct Hoge<T>
+where
+    T: ToString,
+{
+    x: u64,
+    t: T,
+}
+
+fn main() {
+    let hoge = Hoge::<String>::default();
+    assert_eq!(hoge.two_times_x(), &0);
+    assert_eq!(hoge.t(), "");
+
+    let hoge = Hoge {
+        x: 2,
+        ..Hoge::<u64>::default()
+    };
+    assert_eq!(hoge.two_times_x(), &4);
+    assert_eq!(hoge.t(), "0");
+}
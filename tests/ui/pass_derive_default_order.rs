@@ -0,0 +1,37 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+#[derive(Default)]
+struct Hoge {
+    x: u64,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+// `derive_default` is redundant but harmless.
+#[struct_cache_field::add_cache_field(derive_default)]
+#[derive(Default)]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 2,
+        ..Default::default()
+    };
+    assert_eq!(hoge.two_times_x(), &4);
+
+    let fuga = Fuga::default();
+    assert_eq!(fuga.two_times_x(), &0);
+}
@@ -0,0 +1,31 @@
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T>
+where
+    T: ToString,
+{
+    pub fn two_times_t(&self) -> String {
+        self.t.to_string().repeat(2)
+    }
+}
+
+#[struct_cache_field::add_cache_field(derive(Debug, Clone, PartialEq))]
+#[derive(Debug, Clone, PartialEq)]
+struct Hoge<T>
+where
+    T: ToString,
+{
+    x: u64,
+    t: T,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        t: "t".to_string(),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_t(), "tt");
+    let cloned = hoge.clone();
+    assert_eq!(cloned, hoge);
+    assert!(format!("{cloned:?}").contains("two_times_t"));
+}
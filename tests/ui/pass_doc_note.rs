@@ -0,0 +1,31 @@
+#![deny(missing_docs)]
+//! Docs are kept and the note is appended.
+
+/// Hoge.
+pub mod hoge {
+    #[struct_cache_field::impl_cached_method(doc_note)]
+    impl Hoge {
+        /// Returns `x` twice.
+        #[must_use]
+        pub fn two_times_x(&self) -> u64 {
+            2 * self.x
+        }
+
+        #[cached(capacity = 4)]
+        pub fn x_times(&self, n: u64) -> u64 {
+            n * self.x
+        }
+    }
+
+    /// Hoge.
+    #[struct_cache_field::add_cache_field(new)]
+    pub struct Hoge {
+        x: u64,
+    }
+}
+
+fn main() {
+    let hoge = hoge::Hoge::new(1);
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.x_times(3), 3);
+}
@@ -0,0 +1,66 @@
+mod hoge {
+    #[struct_cache_field::impl_cached_method(dyn_trait)]
+    impl Hoge {
+        pub fn two_times_x(&self) -> u64 {
+            2 * self.x
+        }
+
+        #[cached(capacity = 4)]
+        pub(crate) fn add(&self, y: u64, z: u64) -> u64 {
+            self.x + y + z
+        }
+
+        #[cached(box_dyn = "std::fmt::Display")]
+        fn display(&self) -> String {
+            format!("x = {}", self.x)
+        }
+
+        // Not dyn-safe, so left out.
+        pub fn name<T: 'static>(&self) -> String {
+            std::any::type_name::<T>().to_string()
+        }
+    }
+
+    #[struct_cache_field::add_cache_field(new)]
+    pub struct Hoge {
+        x: u64,
+    }
+}
+
+#[struct_cache_field::impl_cached_method(dyn_trait = "FugaAccessors")]
+impl<'a, T> Fuga<'a, T>
+where
+    T: Clone,
+{
+    pub fn t(&self) -> T {
+        self.t.clone()
+    }
+
+    pub fn s(&self) -> &'a str {
+        self.s
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga<'a, T> {
+    t: T,
+    s: &'a str,
+}
+
+use hoge::CachedHoge;
+
+fn main() {
+    let hoge: Box<dyn CachedHoge> = Box::new(hoge::Hoge::new(1));
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.add(2, 3), 6);
+    assert_eq!(hoge.display().to_string(), "x = 1");
+
+    let fuga = Fuga {
+        t: 1u8,
+        s: "s",
+        __cache_fields__: Default::default(),
+    };
+    let fuga: &dyn FugaAccessors<u8> = &fuga;
+    assert_eq!(fuga.t(), &1);
+    assert_eq!(fuga.s(), &"s");
+}
@@ -0,0 +1,40 @@
+use std::collections::HashSet;
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T>
+where
+    T: ToString,
+{
+    pub fn two_times_t(&self) -> String {
+        self.t.to_string().repeat(2)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+#[derive(PartialEq, Eq, Hash)]
+struct Hoge<T>
+where
+    T: ToString,
+{
+    x: u64,
+    t: T,
+}
+
+fn main() {
+    let hoge1 = Hoge {
+        x: 1,
+        t: "t".to_string(),
+        __cache_fields__: Default::default(),
+    };
+    let hoge2 = Hoge {
+        x: 1,
+        t: "t".to_string(),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge1.two_times_t(), "tt");
+    assert!(hoge1 == hoge2);
+
+    let mut set = HashSet::new();
+    set.insert(hoge1);
+    assert!(set.contains(&hoge2));
+}
@@ -0,0 +1,35 @@
+// Without explicit keys, both impls would be registered as `Hoge` before either struct consumes it.
+mod module1 {
+    #[struct_cache_field::impl_cached_method(for = "module1::Hoge")]
+    impl Hoge {
+        pub fn two_times_x(&self) -> u64 {
+            2 * self.x
+        }
+    }
+
+    pub mod module2 {
+        #[struct_cache_field::impl_cached_method(for = "module1::module2::Hoge")]
+        impl Hoge {
+            pub fn three_times_y(&self) -> u64 {
+                3 * self.y
+            }
+        }
+
+        #[struct_cache_field::add_cache_field(from = "module1::module2::Hoge", new)]
+        pub struct Hoge {
+            pub y: u64,
+        }
+    }
+
+    #[struct_cache_field::add_cache_field(from = "module1::Hoge", new)]
+    pub struct Hoge {
+        pub x: u64,
+    }
+}
+
+fn main() {
+    let hoge = module1::Hoge::new(1);
+    assert_eq!(hoge.two_times_x(), &2);
+    let hoge = module1::module2::Hoge::new(1);
+    assert_eq!(hoge.three_times_y(), &3);
+}
@@ -0,0 +1,53 @@
+#![deny(missing_docs)]
+//! The cache struct and the injected field need no docs.
+
+/// Hoge.
+pub mod hoge {
+    #[struct_cache_field::impl_cached_method]
+    impl Hoge {
+        /// Returns `x` twice.
+        pub fn two_times_x(&self) -> u64 {
+            2 * self.x
+        }
+    }
+
+    /// Hoge.
+    #[struct_cache_field::add_cache_field]
+    pub struct Hoge {
+        /// x.
+        pub x: u64,
+    }
+
+    #[struct_cache_field::impl_cached_method]
+    impl Fuga {
+        /// Returns `x` twice.
+        pub fn two_times_x(&self) -> u64 {
+            2 * self.x
+        }
+    }
+
+    /// Fuga.
+    #[struct_cache_field::add_cache_field(expose, name = "FugaCaches")]
+    pub struct Fuga {
+        /// x.
+        pub x: u64,
+    }
+}
+
+fn main() {
+    let hoge = hoge::Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+
+    let fuga = hoge::Fuga {
+        x: 1,
+        __cache_fields__: hoge::FugaCaches::default(),
+    };
+    assert_eq!(fuga.two_times_x(), &2);
+    assert_eq!(
+        fuga.__cache_fields__.occupancy().collect::<Vec<_>>(),
+        [("two_times_x", true)]
+    );
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    #[cached(field_attr(cfg_attr(all(), serde(skip)), doc = "Cache of `x_plus_1`."))]
+    pub fn x_plus_1(&self) -> u64 {
+        self.x + 1
+    }
+
+    #[cached(field_attr(serde(rename = "label")))]
+    pub fn to_label(&self) -> String {
+        format!("x = {}", self.x)
+    }
+}
+
+#[struct_cache_field::add_cache_field(serde_persist)]
+#[derive(Serialize, Deserialize)]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.x_plus_1(), &2);
+    assert_eq!(hoge.to_label(), "x = 1");
+
+    let json = serde_json::to_string(&hoge).unwrap();
+    assert_eq!(
+        json,
+        r#"{"x":1,"__cache_fields__":{"two_times_x":2,"label":"x = 1"}}"#
+    );
+    let hoge: Hoge = serde_json::from_str(&json).unwrap();
+    assert_eq!(hoge.x_plus_1(), &2);
+}
@@ -0,0 +1,44 @@
+#[struct_cache_field::impl_cached_method(field = "caches")]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field(field = "caches")]
+struct Hoge {
+    x: u64,
+}
+
+#[struct_cache_field::impl_cached_method(field = "caches")]
+impl Fuga {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    x: u64,
+    __cache_fields__: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        caches: Default::default(),
+    };
+
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.two_times_x(), &2);
+    hoge.x = 2;
+    assert_eq!(hoge.two_times_x(), &2);
+
+    let fuga = Fuga {
+        x: 1,
+        __cache_fields__: 3,
+        caches: Default::default(),
+    };
+    assert_eq!(fuga.two_times_x(), &2);
+    assert_eq!(fuga.__cache_fields__, 3);
+}
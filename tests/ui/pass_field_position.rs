@@ -0,0 +1,61 @@
+use struct_cache_field::HasCacheFields;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field(derive_default)]
+#[derive(Debug)]
+struct Hoge {
+    x: u64,
+    y: u64,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field(field_first)]
+#[derive(Debug, Default)]
+#[repr(C)]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    // The injected field is last.
+    let hoge = Hoge {
+        x: 1,
+        y: 2,
+        __cache_fields__: Default::default(),
+    };
+    assert!(format!("{hoge:?}").starts_with("Hoge { x: 1, y: 2, __cache_fields__: "));
+
+    // The caches are moved with the other fields, so they are stale until reset.
+    assert_eq!(hoge.two_times_x(), &2);
+    let mut hoge = Hoge { x: 2, ..hoge };
+    assert_eq!(hoge.two_times_x(), &2);
+    hoge.reset_caches();
+    assert_eq!(hoge.two_times_x(), &4);
+    assert_eq!(hoge.y, 2);
+
+    // Updating from `Default` starts with empty caches.
+    let hoge = Hoge {
+        x: 3,
+        ..Default::default()
+    };
+    assert_eq!(hoge.two_times_x(), &6);
+
+    let fuga = Fuga {
+        x: 1,
+        ..Default::default()
+    };
+    assert!(format!("{fuga:?}").starts_with("Fuga { __cache_fields__: "));
+    assert_eq!(fuga.two_times_x(), &2);
+}
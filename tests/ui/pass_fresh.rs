@@ -0,0 +1,51 @@
+use std::cell::Cell;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(fresh)]
+    pub fn two_times_x(&self) -> u64 {
+        self.count.set(self.count.get() + 1);
+        2 * self.x.get()
+    }
+
+    #[cached(fresh)]
+    pub fn name(&self) -> &str {
+        format!("hoge{}", self.x.get())
+    }
+
+    #[cached(fresh, capacity = 2)]
+    pub fn x_plus(&self, n: u64) -> u64 {
+        self.x.get() + n
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: Cell<u64>,
+    count: Cell<usize>,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: Cell::new(1),
+        count: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(*hoge.two_times_x(), 2);
+    hoge.x.set(2);
+    // The fresh variant neither reads nor fills the cache.
+    assert_eq!(hoge.two_times_x_fresh(), 4);
+    assert_eq!(*hoge.two_times_x(), 2);
+    assert_eq!(hoge.count.get(), 2);
+
+    assert_eq!(hoge.name(), "hoge2");
+    hoge.x.set(3);
+    let name: String = hoge.name_fresh();
+    assert_eq!(name, "hoge3");
+    assert_eq!(hoge.name(), "hoge2");
+
+    assert_eq!(hoge.x_plus(1), 4);
+    hoge.x.set(4);
+    assert_eq!(hoge.x_plus_fresh(1), 5);
+    assert_eq!(hoge.x_plus(1), 4);
+}
@@ -0,0 +1,58 @@
+use struct_cache_field::HasCacheFields;
+
+trait Theme {
+    const PREFIX: &'static str;
+}
+
+struct Dark;
+
+impl Theme for Dark {
+    const PREFIX: &'static str = "dark";
+}
+
+struct Light;
+
+impl Theme for Light {
+    const PREFIX: &'static str = "light";
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn render<T: Theme>(&self) -> String {
+        format!("{}: {}", T::PREFIX, self.x)
+    }
+
+    pub fn name<T>(&mut self) -> &str
+    where
+        T: Theme,
+    {
+        self.count += 1;
+        String::from(T::PREFIX)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    count: usize,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        count: 0,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.render::<Dark>(), "dark: 1");
+    assert_eq!(hoge.render::<Light>(), "light: 1");
+    hoge.x = 2;
+    assert_eq!(hoge.render::<Dark>(), "dark: 1");
+
+    assert_eq!(hoge.name::<Dark>(), "dark");
+    assert_eq!(hoge.name::<Light>(), "light");
+    assert_eq!(hoge.name::<Dark>(), "dark");
+    assert_eq!(hoge.count, 2);
+
+    hoge.reset_caches();
+    assert_eq!(hoge.render::<Dark>(), "dark: 2");
+}
@@ -0,0 +1,55 @@
+// Bounds may differ as long as parameters have the same names.
+#[struct_cache_field::impl_cached_method]
+impl<T: ToString + From<String>> Hoge<T>
+where
+    T: Clone,
+{
+    pub fn two_times_x(&self) -> T {
+        let s = self.x.to_string();
+        format!("{s}{s}").into()
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<T>
+where
+    T: ToString,
+{
+    x: u64,
+    t: T,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl<'a, T> Fuga<'a, T>
+where
+    T: AsRef<str> + Copy,
+{
+    pub fn name(&self) -> &'a str {
+        self.name.as_ref()
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga<'a, T: AsRef<str>> {
+    name: &'a T,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        t: "t".to_string(),
+        __cache_fields__: Default::default(),
+    };
+
+    assert_eq!(hoge.two_times_x(), &"11");
+    assert_eq!(hoge.two_times_x(), &"11");
+    hoge.x = 2;
+    assert_eq!(hoge.two_times_x(), &"11");
+    assert_eq!(hoge.t, "t");
+
+    let fuga = Fuga {
+        name: &"fuga",
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(*fuga.name(), "fuga");
+}
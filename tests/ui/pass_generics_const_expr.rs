@@ -0,0 +1,47 @@
+#[struct_cache_field::impl_cached_method]
+impl crate::Hoge<{ 1 + 2 }> {
+    pub fn three_times_x(&self) -> u64 {
+        3 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<const N: u64> {
+    x: u64,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Fuga<T, 2>
+where
+    T: Copy + Into<u64>,
+{
+    pub fn two_times_t(&self) -> u64 {
+        2 * self.t.into()
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga<T, const N: usize>
+where
+    T: Copy + Into<u64>,
+{
+    t: T,
+}
+
+fn main() {
+    let mut hoge = Hoge::<3> {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+
+    assert_eq!(hoge.three_times_x(), &3);
+    assert_eq!(hoge.three_times_x(), &3);
+    hoge.x = 2;
+    assert_eq!(hoge.three_times_x(), &3);
+
+    let fuga = Fuga::<u32, 2> {
+        t: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(fuga.two_times_t(), &2);
+}
@@ -0,0 +1,40 @@
+// `'b` is not used by caches, so it is dropped from the cache struct with bounds naming it.
+#[struct_cache_field::impl_cached_method]
+impl<'a: 'b, 'b, 'c> Hoge<'a, 'b, 'c>
+where
+    'c: 'a + 'b,
+{
+    pub fn first_name(&self) -> &'a str {
+        self.full_name.split(' ').next().unwrap()
+    }
+
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<'a: 'b, 'b, 'c>
+where
+    'c: 'a + 'b,
+{
+    full_name: &'a str,
+    x: &'b u64,
+    y: &'c u64,
+}
+
+fn main() {
+    let full_name = "John Doe".to_string();
+    let x = 1;
+    let y = 2;
+    let hoge = Hoge {
+        full_name: &full_name,
+        x: &x,
+        y: &y,
+        __cache_fields__: Default::default(),
+    };
+
+    assert_eq!(hoge.first_name(), &"John");
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(*hoge.y, 2);
+}
@@ -0,0 +1,34 @@
+#[struct_cache_field::impl_cached_method]
+impl<'a, 'b> Hoge<'a, 'b> {
+    pub fn first_name(&self) -> &'a str {
+        self.full_name.split(' ').next().unwrap()
+    }
+
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<'a, 'b> {
+    full_name: &'a str,
+    x: &'b u64,
+}
+
+fn main() {
+    let full_name = "John Doe".to_string();
+    let x = 1;
+    let mut hoge = Hoge {
+        full_name: &full_name,
+        x: &x,
+        __cache_fields__: Default::default(),
+    };
+
+    assert_eq!(hoge.first_name(), &"John");
+    assert_eq!(hoge.first_name(), &"John");
+    let full_name2 = "Jane Doe".to_string();
+    hoge.full_name = &full_name2;
+    assert_eq!(hoge.first_name(), &"John");
+
+    assert_eq!(hoge.two_times_x(), &2);
+}
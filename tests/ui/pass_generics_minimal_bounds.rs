@@ -0,0 +1,51 @@
+use std::borrow::Cow;
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T>
+where
+    T: ToString + From<String>,
+{
+    pub fn two_times_t(&self) -> T {
+        self.t.to_string().repeat(2).into()
+    }
+}
+
+// The cache struct doesn't inherit `T: ToString + From<String>`, which its caches don't need.
+#[struct_cache_field::add_cache_field(name = "HogeCaches")]
+struct Hoge<T: ToString + From<String>> {
+    t: T,
+}
+
+fn empty_caches<T>() -> HogeCaches<T> {
+    HogeCaches::default()
+}
+
+#[struct_cache_field::impl_cached_method]
+impl<'a, T> Fuga<'a, T>
+where
+    T: ToOwned + ?Sized,
+{
+    pub fn borrowed(&self) -> Cow<'a, T> {
+        Cow::Borrowed(self.t)
+    }
+}
+
+// `Cow<'a, T>` needs `T: ToOwned` to be named.
+#[struct_cache_field::add_cache_field(bound = "T: ToOwned")]
+struct Fuga<'a, T: ToOwned + ?Sized> {
+    t: &'a T,
+}
+
+fn main() {
+    let hoge = Hoge {
+        t: String::from("t"),
+        __cache_fields__: empty_caches(),
+    };
+    assert_eq!(hoge.two_times_t(), "tt");
+
+    let fuga = Fuga {
+        t: "fuga",
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(fuga.borrowed().as_ref(), "fuga");
+}
@@ -0,0 +1,39 @@
+pub trait Shape {
+    type Area;
+
+    fn area(&self) -> Self::Area;
+}
+
+impl<T> Shape for Square<T>
+where
+    T: Copy + std::ops::Mul<Output = T>,
+{
+    type Area = T;
+
+    fn area(&self) -> T {
+        self.side * self.side
+    }
+}
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Square<T>
+where
+    Self: Shape,
+{
+    pub fn cached_area(&self) -> <Self as Shape>::Area {
+        self.area()
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Square<T> {
+    side: T,
+}
+
+fn main() {
+    let square = Square {
+        side: 3u64,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(square.cached_area(), &9);
+}
@@ -0,0 +1,43 @@
+pub trait Op {
+    type Output;
+
+    fn run(&self) -> Self::Output;
+}
+
+impl Op for u64 {
+    type Output = String;
+
+    fn run(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T>
+where
+    T: Op,
+    T::Output: Clone,
+    Self: Sized,
+{
+    pub fn output(&self) -> T::Output {
+        self.t.run()
+    }
+
+    pub fn pair(&self) -> (T::Output, <T as Op>::Output) {
+        (self.output().clone(), self.t.run())
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<T> {
+    t: T,
+}
+
+fn main() {
+    let hoge = Hoge {
+        t: 1u64,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.output(), "1");
+    assert_eq!(hoge.pair(), &("1".to_owned(), "1".to_owned()));
+}
@@ -0,0 +1,46 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    pub fn sum(&self) -> u64 {
+        self.two_times_x() + self.y + self.helper()
+    }
+
+    fn y_plus_1(&self) -> u64 {
+        let y = self.y;
+        y + 1
+    }
+
+    #[allow(deprecated)]
+    fn helper(&self) -> u64 {
+        0
+    }
+}
+
+#[struct_cache_field::add_cache_field(graph)]
+struct Hoge {
+    x: u64,
+    y: u64,
+}
+
+fn main() {
+    assert_eq!(
+        Hoge::CACHE_GRAPH_DOT,
+        r#"digraph "Hoge" {
+    "x";
+    "y";
+    "two_times_x" [shape=box];
+    "sum" [shape=box];
+    "y_plus_1" [shape=box];
+    "helper" [shape=box];
+    "two_times_x" -> "x";
+    "sum" -> "two_times_x";
+    "sum" -> "y";
+    "sum" -> "helper";
+    "y_plus_1" -> "y";
+}
+"#
+    );
+}
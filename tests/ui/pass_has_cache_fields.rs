@@ -0,0 +1,59 @@
+use struct_cache_field::HasCacheFields;
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T>
+where
+    T: ToString,
+{
+    pub fn two_times_t(&self) -> String {
+        self.t.to_string().repeat(2)
+    }
+
+    pub fn x_plus_1(&self) -> u64 {
+        self.x + 1
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<T>
+where
+    T: ToString,
+{
+    x: u64,
+    t: T,
+}
+
+fn reset_all(xs: &mut [&mut dyn HasCacheFields]) {
+    for x in xs {
+        x.reset_caches();
+    }
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        t: "t".to_string(),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_t(), "tt");
+    let occupancy = hoge.cache_occupancy();
+    assert_eq!(
+        occupancy.entries(),
+        &[("two_times_t", true), ("x_plus_1", false)]
+    );
+    assert_eq!(occupancy.filled().collect::<Vec<_>>(), vec!["two_times_t"]);
+    assert_eq!(occupancy.num_filled(), 1);
+    assert_eq!(occupancy.len(), 2);
+    assert_eq!(
+        occupancy.iter().collect::<Vec<_>>(),
+        hoge.__cache_fields__.occupancy().collect::<Vec<_>>()
+    );
+    for (name, filled) in &occupancy {
+        assert_eq!(filled, name == "two_times_t");
+    }
+
+    hoge.t = "s".to_string();
+    reset_all(&mut [&mut hoge]);
+    assert_eq!(hoge.cache_occupancy().num_filled(), 0);
+    assert_eq!(hoge.two_times_t(), "ss");
+}
@@ -0,0 +1,85 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasherDefault, Hasher};
+use struct_cache_field::{CacheCell, HasCacheFields};
+
+/// FNV-1a, as a hasher faster than SipHash.
+#[derive(Default)]
+struct FnvHasher(u64);
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        if self.0 == 0 {
+            self.0 = 0xcbf29ce484222325;
+        }
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+type FnvBuildHasher = BuildHasherDefault<FnvHasher>;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(capacity = 2, hasher = "FnvBuildHasher")]
+    pub fn x_times(&mut self, n: u64) -> u64 {
+        self.count += 1;
+        n * self.x
+    }
+
+    #[cached(capacity = 1024, hasher = "RandomState", max_bytes = 12, size_fn = "str_len")]
+    pub fn label(&self, prefix: String, n: u64) -> String {
+        format!("{prefix}{}", n + self.x)
+    }
+}
+
+fn str_len(s: &String) -> usize {
+    s.len()
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    count: usize,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 2,
+        count: 0,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.x_times(1), 2);
+    assert_eq!(hoge.x_times(2), 4);
+    assert_eq!(hoge.x_times(1), 2);
+    assert_eq!(hoge.count, 2);
+    // Evicts the oldest entry for `1`.
+    assert_eq!(hoge.x_times(3), 6);
+    assert_eq!(hoge.x_times(2), 4);
+    assert_eq!(hoge.count, 3);
+    assert_eq!(hoge.x_times(1), 2);
+    assert_eq!(hoge.count, 4);
+
+    assert_eq!(hoge.label("x = ".to_owned(), 1), "x = 3");
+    assert_eq!(hoge.label("y = ".to_owned(), 1), "y = 3");
+    let labels = CacheCell::get(&hoge.__cache_fields__.label).unwrap();
+    assert_eq!(labels.len(), 2);
+    assert_eq!(labels.bytes(), 10);
+    // Evicts the oldest entry to fit in 12 bytes.
+    assert_eq!(hoge.label("z = ".to_owned(), 1), "z = 3");
+    let labels = CacheCell::get(&hoge.__cache_fields__.label).unwrap();
+    assert_eq!(labels.len(), 2);
+    assert_eq!(
+        format!("{labels:?}"),
+        r#"{("y = ", 1): "y = 3", ("z = ", 1): "z = 3"}"#
+    );
+
+    hoge.x = 3;
+    hoge.reset_caches();
+    assert_eq!(hoge.x_times(1), 3);
+    assert_eq!(hoge.label("x = ".to_owned(), 1), "x = 4");
+}
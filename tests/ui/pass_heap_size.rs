@@ -0,0 +1,62 @@
+use struct_cache_field::HeapSize;
+
+struct Blob(usize);
+
+impl HeapSize for Blob {
+    fn heap_size(&self) -> usize {
+        self.0
+    }
+}
+
+#[struct_cache_field::impl_cached_method]
+impl<'a> Hoge<'a> {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    pub fn repeated(&self) -> String {
+        let mut s = String::with_capacity(16);
+        s.push_str(self.s);
+        s
+    }
+
+    #[allow(deprecated)]
+    pub fn blob(&self) -> Blob {
+        Blob(100)
+    }
+
+    #[cached(timed)]
+    pub fn xs(&self) -> Vec<u64> {
+        let mut xs = Vec::with_capacity(4);
+        xs.push(self.x);
+        xs
+    }
+
+    pub fn s(&self) -> &'a str {
+        self.s
+    }
+}
+
+#[struct_cache_field::add_cache_field(heap_size)]
+struct Hoge<'a> {
+    x: u64,
+    s: &'a str,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        s: "s",
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.cache_heap_size(), 0);
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.s(), &"s");
+    assert_eq!(hoge.cache_heap_size(), 0);
+    assert_eq!(hoge.repeated(), "s");
+    assert_eq!(hoge.cache_heap_size(), 16);
+    let _ = hoge.blob();
+    assert_eq!(hoge.cache_heap_size(), 116);
+    assert_eq!(hoge.xs(), &[1]);
+    assert_eq!(hoge.cache_heap_size(), 116 + 4 * 8);
+}
@@ -0,0 +1,36 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn adder(&self) -> impl Fn(u64) -> u64 {
+        let x = self.x;
+        move |y| x + y
+    }
+
+    pub fn label(&self) -> impl std::fmt::Display + Send {
+        format!("x = {}", self.x)
+    }
+
+    #[cached(box_dyn = "std::fmt::Debug")]
+    pub fn debug(&self) -> Vec<u64> {
+        vec![self.x; 2]
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+
+    assert_eq!(hoge.adder()(2), 3);
+    assert_eq!(hoge.label().to_string(), "x = 1");
+    assert_eq!(format!("{:?}", hoge.debug()), "[1, 1]");
+    hoge.x = 2;
+    assert_eq!(hoge.adder()(2), 3);
+    assert_eq!(hoge.label().to_string(), "x = 1");
+    assert_eq!(format!("{:?}", hoge.debug()), "[1, 1]");
+}
@@ -0,0 +1,48 @@
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T>
+where
+    T: ToString,
+{
+    pub fn two_times_t(&self) -> String {
+        self.t.to_string().repeat(2)
+    }
+}
+
+/// Documented.
+#[struct_cache_field::add_cache_field(init)]
+#[allow(dead_code)]
+struct Hoge<T>
+where
+    T: ToString,
+{
+    x: u64,
+    t: T,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field(init = "FugaData")]
+#[repr(C)]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    let init = HogeInit { x: 1, t: 't' };
+    let hoge = Hoge::from(init);
+    assert_eq!(hoge.two_times_t(), "tt");
+    // And back, dropping the caches.
+    let init = HogeInit::from(hoge);
+    assert_eq!((init.x, init.t), (1, 't'));
+
+    let data = FugaData { x: 1 };
+    let fuga: Fuga = data.into();
+    assert_eq!(fuga.two_times_x(), &2);
+    let data: FugaData = fuga.into();
+    assert_eq!(data.x, 1);
+}
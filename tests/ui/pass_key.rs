@@ -0,0 +1,71 @@
+use std::collections::hash_map::RandomState;
+
+struct Query {
+    id: u64,
+    text: String,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(capacity = 1, key = "self.version", key_type = "u64")]
+    pub fn doubled(&mut self) -> u64 {
+        self.count += 1;
+        self.x * 2
+    }
+
+    #[cached(
+        capacity = 4,
+        key = "(self.version, query.id)",
+        key_type = "(u64, u64)",
+        hasher = "RandomState"
+    )]
+    pub fn answer(&mut self, query: &Query) -> String {
+        self.count += 1;
+        format!("{}{}", query.text, self.x)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    version: u64,
+    count: u64,
+}
+
+impl Hoge {
+    fn set_x(&mut self, x: u64) {
+        self.x = x;
+        self.version += 1;
+    }
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        version: 0,
+        count: 0,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.doubled(), 2);
+    assert_eq!(hoge.doubled(), 2);
+    assert_eq!(hoge.count, 1);
+    hoge.set_x(2);
+    assert_eq!(hoge.doubled(), 4);
+    assert_eq!(hoge.count, 2);
+
+    let query = Query {
+        id: 1,
+        text: "a".to_owned(),
+    };
+    assert_eq!(hoge.answer(&query), "a2");
+    // Only `id` is a part of the key.
+    let other = Query {
+        id: 1,
+        text: "b".to_owned(),
+    };
+    assert_eq!(hoge.answer(&other), "a2");
+    assert_eq!(hoge.count, 3);
+    hoge.set_x(3);
+    assert_eq!(hoge.answer(&other), "b3");
+    assert_eq!(hoge.count, 4);
+}
@@ -0,0 +1,47 @@
+struct Document {
+    text: String,
+}
+
+impl Document {
+    fn byte_len(this: &std::rc::Rc<Document>) -> usize {
+        this.text.len()
+    }
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(capacity = 8, max_bytes = 10)]
+    pub fn repeat(&self, n: usize) -> String {
+        "x".repeat(n)
+    }
+
+    #[cached(capacity = 8, max_bytes = 10, size_fn = "Document::byte_len")]
+    pub fn parse(&self, text: String) -> std::rc::Rc<Document> {
+        std::rc::Rc::new(Document { text })
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {}
+
+fn main() {
+    let hoge = Hoge {
+        __cache_fields__: Default::default(),
+    };
+    // `String` itself takes 24 bytes, which exceeds the budget.
+    assert_eq!(hoge.repeat(1), "x");
+    assert!(struct_cache_field::CacheCell::get(&hoge.__cache_fields__.repeat)
+        .unwrap()
+        .is_empty());
+
+    let a = hoge.parse("aaaa".to_owned());
+    let b = hoge.parse("bbbb".to_owned());
+    assert!(std::rc::Rc::ptr_eq(&a, &hoge.parse("aaaa".to_owned())));
+    // Evicts `aaaa`.
+    let _ = hoge.parse("cccc".to_owned());
+    assert!(!std::rc::Rc::ptr_eq(&a, &hoge.parse("aaaa".to_owned())));
+    assert!(!std::rc::Rc::ptr_eq(&b, &hoge.parse("bbbb".to_owned())));
+    let map = struct_cache_field::CacheCell::get(&hoge.__cache_fields__.parse).unwrap();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.bytes(), 8);
+}
@@ -0,0 +1,34 @@
+#![deny(missing_docs)]
+//! Attributes of cached methods are kept.
+
+#[struct_cache_field::impl_cached_method(inline)]
+impl Hoge {
+    /// Returns `2 * x`.
+    #[must_use]
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    /// Returns `x + 1`.
+    #[inline(always)]
+    #[allow(clippy::identity_op)]
+    #[cached(timed)]
+    pub fn x_plus_1(&self) -> u64 {
+        self.x + 1 * 1
+    }
+}
+
+/// Hoge.
+#[struct_cache_field::add_cache_field]
+pub struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.x_plus_1(), &2);
+}
@@ -0,0 +1,52 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn drain_sum(&mut self) -> u64 {
+        // Borrows the whole `self` mutably, which conflicts with a borrow of the cache field.
+        let xs = self.take_xs();
+        self.log.push(format!("sum of {xs:?}"));
+        xs.iter().sum()
+    }
+
+    #[cached(timed)]
+    pub fn bump(&mut self) -> u64 {
+        self.bump_count();
+        if self.count > 10 {
+            return 0;
+        }
+        self.count
+    }
+}
+
+impl Hoge {
+    fn take_xs(&mut self) -> Vec<u64> {
+        std::mem::take(&mut self.xs)
+    }
+
+    fn bump_count(&mut self) {
+        self.count += 1;
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    xs: Vec<u64>,
+    count: u64,
+    log: Vec<String>,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        xs: vec![1, 2, 3],
+        count: 0,
+        log: vec![],
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.drain_sum(), &6);
+    assert_eq!(hoge.drain_sum(), &6);
+    assert_eq!(hoge.log, vec!["sum of [1, 2, 3]"]);
+
+    assert_eq!(hoge.bump(), &1);
+    assert_eq!(hoge.bump(), &1);
+    assert_eq!(hoge.count, 1);
+    assert!(hoge.bump_compute_time().is_some());
+}
@@ -0,0 +1,59 @@
+// Generated code doesn't refer to `std`. `std` is linked only to run the test.
+#![no_std]
+
+mod rt {
+    extern crate std;
+}
+
+use struct_cache_field::HasCacheFields;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    #[allow(deprecated)]
+    pub fn name(&self) -> &str {
+        alloc::string::String::from("hoge")
+    }
+
+    #[cached(capacity = 4)]
+    pub fn x_times(&self, n: u64) -> u64 {
+        n * self.x
+    }
+}
+
+extern crate alloc;
+
+#[struct_cache_field::add_cache_field(new, clone = "preserve", heap_size)]
+#[derive(Debug, Clone)]
+struct Hoge {
+    x: u64,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    pub fn printable(&self) -> impl core::fmt::Display {
+        self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge::new(1);
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.name(), "hoge");
+    assert_eq!(hoge.x_times(3), 3);
+    assert_eq!(hoge.cache_occupancy().num_filled(), 3);
+    assert_eq!(hoge.clone().cache_heap_size(), 4);
+    hoge.reset_caches();
+    assert!(hoge.__cache_fields__.occupancy().all(|(_, filled)| !filled));
+
+    let fuga = struct_cache_field::construct!(Fuga { x: 1 });
+    let _ = fuga.printable();
+}
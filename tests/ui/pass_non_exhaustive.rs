@@ -0,0 +1,21 @@
+#![deny(private_interfaces)]
+
+mod hoge {
+    #[struct_cache_field::impl_cached_method]
+    impl Hoge {
+        pub fn two_times_x(&self) -> u64 {
+            2 * self.x
+        }
+    }
+
+    #[struct_cache_field::add_cache_field]
+    #[non_exhaustive]
+    pub struct Hoge {
+        pub x: u64,
+    }
+}
+
+fn main() {
+    let hoge = hoge::Hoge::new(1);
+    assert_eq!(hoge.two_times_x(), &2);
+}
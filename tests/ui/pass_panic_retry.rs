@@ -0,0 +1,110 @@
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        if self.fail.get() {
+            panic!("failed");
+        }
+        2 * self.x
+    }
+
+    #[cached(capacity = 4)]
+    pub fn add(&self, y: u64) -> u64 {
+        if self.fail.get() {
+            panic!("failed");
+        }
+        self.x + y
+    }
+
+    pub fn name<T: 'static>(&self) -> String {
+        if self.fail.get() {
+            panic!("failed");
+        }
+        format!("{}: {}", std::any::type_name::<T>(), self.x)
+    }
+
+    #[cached(weak)]
+    pub fn shared(&self) -> Rc<u64> {
+        if self.fail.get() {
+            panic!("failed");
+        }
+        Rc::new(self.x)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    fail: Cell<bool>,
+}
+
+#[struct_cache_field::impl_cached_method(cell_path = "std::sync::OnceLock")]
+impl Fuga {
+    pub fn two_times_x(&self) -> u64 {
+        if self.fail.load(Ordering::Relaxed) {
+            panic!("failed");
+        }
+        2 * self.x
+    }
+
+    #[cached(capacity = 4)]
+    pub fn add(&self, y: u64) -> u64 {
+        if self.fail.load(Ordering::Relaxed) {
+            panic!("failed");
+        }
+        self.x + y
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    x: u64,
+    fail: AtomicBool,
+}
+
+fn panics<R>(f: impl FnOnce() -> R) -> bool {
+    panic::catch_unwind(AssertUnwindSafe(f)).is_err()
+}
+
+fn main() {
+    panic::set_hook(Box::new(|_| {}));
+
+    let hoge = Hoge {
+        x: 1,
+        fail: Cell::new(true),
+        __cache_fields__: Default::default(),
+    };
+    assert!(panics(|| hoge.two_times_x()));
+    assert!(panics(|| hoge.add(2)));
+    assert!(panics(|| hoge.name::<u8>()));
+    assert!(panics(|| hoge.shared()));
+    // The caches are left empty, so the next calls compute again.
+    hoge.fail.set(false);
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.add(2), 3);
+    assert_eq!(hoge.name::<u8>(), "u8: 1");
+    assert_eq!(*hoge.shared(), 1);
+    // Values computed before are kept.
+    hoge.fail.set(true);
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.add(2), 3);
+    assert_eq!(hoge.name::<u8>(), "u8: 1");
+    assert!(panics(|| hoge.add(3)));
+    assert!(panics(|| hoge.name::<u16>()));
+
+    let fuga = Fuga {
+        x: 1,
+        fail: AtomicBool::new(true),
+        __cache_fields__: Default::default(),
+    };
+    assert!(panics(|| fuga.two_times_x()));
+    assert!(panics(|| fuga.add(2)));
+    // `OnceLock` is neither poisoned nor deadlocked.
+    fuga.fail.store(false, Ordering::Relaxed);
+    assert_eq!(fuga.two_times_x(), &2);
+    assert_eq!(fuga.add(2), 3);
+}
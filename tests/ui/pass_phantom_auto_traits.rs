@@ -0,0 +1,29 @@
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T> {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<T> {
+    x: u64,
+    _t: PhantomData<fn() -> T>,
+}
+
+fn assert_send<T: Send>() {}
+
+fn main() {
+    // `Rc<u64>` is not `Send`, but `Hoge` only holds it via `fn() -> T`.
+    assert_send::<Hoge<Rc<u64>>>();
+
+    let hoge = Hoge::<Rc<u64>> {
+        x: 1,
+        _t: PhantomData,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+}
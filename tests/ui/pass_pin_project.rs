@@ -0,0 +1,62 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+#[struct_cache_field::impl_cached_method]
+impl<F> Hoge<F> {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+#[pin_project::pin_project]
+struct Hoge<F> {
+    #[pin]
+    future: F,
+    x: u64,
+}
+
+impl<F: Future<Output = u64>> Future for Hoge<F> {
+    type Output = u64;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<u64> {
+        let this = self.project();
+        // The injected field is not pinned.
+        let _: &mut __struct_cache_field__HogeCacheFields<F> = this.__cache_fields__;
+        this.future.poll(cx).map(|y| y + *this.x)
+    }
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+// The order doesn't matter.
+#[pin_project::pin_project]
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    x: u64,
+}
+
+fn assert_unpin<T: Unpin>() {}
+
+fn main() {
+    let hoge = Hoge {
+        future: std::future::ready(1),
+        x: 2,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &4);
+    assert_unpin::<Hoge<std::future::Ready<u64>>>();
+
+    let mut fuga = Fuga {
+        x: 2,
+        __cache_fields__: Default::default(),
+    };
+    let _: &mut __struct_cache_field__FugaCacheFields = Pin::new(&mut fuga).project().__cache_fields__;
+    assert_eq!(fuga.two_times_x(), &4);
+}
@@ -0,0 +1,41 @@
+use std::marker::PhantomPinned;
+use std::pin::Pin;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(mut self: Pin<&mut Self>) -> u64 {
+        // Safety: `x` is not structurally pinned.
+        let x = unsafe { &mut self.as_mut().get_unchecked_mut().x };
+        *x += 1;
+        2 * self.x
+    }
+
+    #[cached(timed)]
+    pub fn x_plus_1(self: Pin<&Self>) -> u64 {
+        self.x + 1
+    }
+
+    pub fn x_plus_2(&self) -> u64 {
+        self.x + 2
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    _pinned: PhantomPinned,
+}
+
+fn main() {
+    let mut hoge = Box::pin(Hoge {
+        x: 1,
+        _pinned: PhantomPinned,
+        __cache_fields__: Default::default(),
+    });
+    assert_eq!(hoge.as_mut().two_times_x(), &4);
+    assert_eq!(hoge.as_mut().two_times_x(), &4);
+    assert_eq!(hoge.x, 2);
+    assert_eq!(hoge.as_ref().x_plus_1(), &3);
+    assert!(hoge.x_plus_1_compute_time().is_some());
+    assert_eq!(hoge.x_plus_2(), &4);
+}
@@ -0,0 +1,39 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    /// Traverses `xs` only once.
+    #[cached(produces(min, max, sum))]
+    pub fn stats(&mut self) -> (u64, u64, u64) {
+        self.count += 1;
+        let min = *self.xs.iter().min().unwrap();
+        let max = *self.xs.iter().max().unwrap();
+        (min, max, self.xs.iter().sum())
+    }
+
+    #[cached(timed, produces(first, rest))]
+    pub fn split(&self) -> (u64, Vec<u64>) {
+        (self.xs[0], self.xs[1..].to_vec())
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    xs: Vec<u64>,
+    count: usize,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        xs: vec![3, 1, 2],
+        count: 0,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.min(), &1);
+    assert_eq!(hoge.max(), &3);
+    assert_eq!(hoge.sum(), &6);
+    assert_eq!(hoge.stats(), &(1, 3, 6));
+    assert_eq!(hoge.count, 1);
+
+    assert_eq!(hoge.rest(), &[1, 2]);
+    assert_eq!(hoge.first(), &3);
+    assert!(hoge.split_compute_time().is_some());
+}
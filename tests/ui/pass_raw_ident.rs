@@ -0,0 +1,45 @@
+use struct_cache_field::HasCacheFields;
+
+#[derive(Debug, PartialEq)]
+enum Kind {
+    Small,
+    Large,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn r#type(&self) -> Kind {
+        if self.r#match < 10 {
+            Kind::Small
+        } else {
+            Kind::Large
+        }
+    }
+
+    #[cached(timed)]
+    pub fn r#match(&self) -> u64 {
+        self.r#match * 2
+    }
+}
+
+#[struct_cache_field::add_cache_field(graph, debug_values)]
+struct Hoge {
+    r#match: u64,
+}
+
+fn main() {
+    let hoge = Hoge {
+        r#match: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.r#type(), &Kind::Small);
+    assert_eq!(hoge.match_compute_time(), None);
+    assert_eq!(hoge.r#match(), &2);
+    assert!(hoge.match_compute_time().is_some());
+    assert_eq!(
+        hoge.cache_occupancy().entries(),
+        &[("type", true), ("match", true)]
+    );
+    assert!(format!("{:?}", hoge.__cache_fields__).contains("type: Some(Small)"));
+    assert!(Hoge::CACHE_GRAPH_DOT.contains("\"type\" -> \"match\";"));
+}
@@ -0,0 +1,52 @@
+use std::cell::Cell;
+use struct_cache_field::HasCacheFields;
+
+#[struct_cache_field::impl_cached_method]
+impl<T: Clone + PartialEq + std::fmt::Display> Hoge<T> {
+    #[cached(recompute_if_changed(self.x, self.names))]
+    pub fn label(&self) -> String {
+        self.count.set(self.count.get() + 1);
+        format!("{}: {}", self.x, self.names.join(", "))
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.get()
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<T> {
+    x: T,
+    names: Vec<String>,
+    count: Cell<usize>,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        names: vec![String::from("a")],
+        count: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.label(), "1: a");
+    assert_eq!(hoge.label(), "1: a");
+    assert_eq!(hoge.count.get(), 1);
+
+    // Recomputed once any of the fields changes.
+    hoge.x = 2;
+    assert_eq!(hoge.label(), "2: a");
+    assert_eq!(hoge.label(), "2: a");
+    assert_eq!(hoge.count.get(), 2);
+    hoge.names.push(String::from("b"));
+    assert_eq!(hoge.label(), "2: a, b");
+    assert_eq!(hoge.count.get(), 3);
+
+    // A change back is a change as well.
+    hoge.x = 1;
+    assert_eq!(hoge.label(), "1: a, b");
+    assert_eq!(hoge.count.get(), 4);
+
+    hoge.reset_caches();
+    assert_eq!(hoge.label(), "1: a, b");
+    assert_eq!(hoge.count.get(), 5);
+}
@@ -0,0 +1,40 @@
+use std::cell::Cell;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn nested(&self) -> u64 {
+        // Calls itself while the value is being computed.
+        if self.depth.get() < 3 {
+            self.depth.set(self.depth.get() + 1);
+            self.nested() + 1
+        } else {
+            self.two_times_x() + self.x_plus_1()
+        }
+    }
+
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    pub fn x_plus_1(&self) -> u64 {
+        *self.two_times_x() / 2 + 1
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    depth: Cell<u64>,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        depth: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    // The value stored by the innermost call wins.
+    assert_eq!(hoge.nested(), &4);
+    assert_eq!(hoge.nested(), &4);
+    assert_eq!(hoge.depth.get(), 3);
+}
@@ -0,0 +1,46 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(refresh)]
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    #[cached(refresh)]
+    pub fn name(&self) -> &str {
+        format!("hoge{}", self.x)
+    }
+
+    #[cached(refresh, resettable)]
+    pub fn x_squared(&self) -> u64 {
+        self.x * self.x
+    }
+
+    #[cached(refresh)]
+    pub fn size_of<T>(&self) -> usize {
+        std::mem::size_of::<T>() * self.x as usize
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(*hoge.two_times_x(), 2);
+    assert_eq!(hoge.name(), "hoge1");
+    assert_eq!(hoge.x_squared(), 1);
+    assert_eq!(*hoge.size_of::<u32>(), 4);
+    hoge.x = 2;
+    assert_eq!(*hoge.two_times_x(), 2);
+    assert_eq!(*hoge.two_times_x_refresh(), 4);
+    assert_eq!(*hoge.two_times_x(), 4);
+    assert_eq!(hoge.name_refresh(), "hoge2");
+    assert_eq!(hoge.x_squared_refresh(), 4);
+    assert_eq!(*hoge.size_of_refresh::<u32>(), 8);
+    assert_eq!(*hoge.size_of::<u32>(), 8);
+}
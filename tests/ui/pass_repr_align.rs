@@ -0,0 +1,21 @@
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+#[repr(C, align(64))]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(core::mem::align_of::<Hoge>(), 64);
+}
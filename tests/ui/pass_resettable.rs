@@ -0,0 +1,55 @@
+use std::cell::Cell;
+use struct_cache_field::HasCacheFields;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(resettable)]
+    pub fn label(&self) -> String {
+        self.count.set(self.count.get() + 1);
+        format!("x = {}", self.x.get())
+    }
+
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x.get()
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: Cell<u64>,
+    count: Cell<usize>,
+}
+
+impl Hoge {
+    fn set_x(&self, x: u64) {
+        self.x.set(x);
+        self.invalidate_label();
+    }
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: Cell::new(1),
+        count: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.label(), "x = 1");
+    assert_eq!(hoge.label(), "x = 1");
+    assert_eq!(hoge.count.get(), 1);
+
+    // Reset through `&self`.
+    hoge.set_x(2);
+    assert_eq!(hoge.label(), "x = 2");
+    assert_eq!(hoge.count.get(), 2);
+
+    hoge.reset_caches();
+    assert_eq!(hoge.label(), "x = 2");
+    assert_eq!(hoge.count.get(), 3);
+    assert_eq!(hoge.two_times_x(), &4);
+
+    hoge.invalidate_label();
+    // Invalidating an empty cache does nothing.
+    hoge.invalidate_label();
+    assert_eq!(hoge.label(), "x = 2");
+    assert_eq!(hoge.count.get(), 4);
+}
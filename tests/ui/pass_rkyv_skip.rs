@@ -0,0 +1,29 @@
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+#[derive(Archive, Serialize, Deserialize)]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+    hoge.x = 2;
+
+    let bytes = rkyv::to_bytes::<_, 256>(&hoge).unwrap();
+    let archived = unsafe { rkyv::archived_root::<Hoge>(&bytes) };
+    assert_eq!(archived.x, 2);
+    let hoge: Hoge = archived.deserialize(&mut rkyv::Infallible).unwrap();
+    assert_eq!(hoge.two_times_x(), &4);
+}
@@ -0,0 +1,33 @@
+use std::cell::Cell;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(run_once)]
+    pub fn register(&self) {
+        self.count.set(self.count.get() + 1);
+    }
+
+    #[cached(run_once)]
+    pub fn register_twice(&self) -> () {
+        self.register();
+        self.register();
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    count: Cell<u64>,
+}
+
+fn main() {
+    let hoge = Hoge {
+        count: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    let () = hoge.register();
+    hoge.register();
+    assert_eq!(hoge.count.get(), 1);
+    hoge.register_twice();
+    hoge.register_twice();
+    assert_eq!(hoge.count.get(), 1);
+}
@@ -0,0 +1,52 @@
+use std::cell::Cell;
+use struct_cache_field::CacheScope;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(scope)]
+    pub fn name(&self) -> String {
+        self.count.set(self.count.get() + 1);
+        format!("hoge{}", self.x)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    count: Cell<u64>,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        count: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    // Without a scope, the value is computed on every call.
+    assert_eq!(hoge.name(), "hoge1");
+    assert_eq!(hoge.name(), "hoge1");
+    assert_eq!(hoge.count.get(), 2);
+
+    {
+        let scope = CacheScope::enter();
+        assert_eq!(hoge.name(), "hoge1");
+        assert_eq!(hoge.name(), "hoge1");
+        assert_eq!(hoge.count.get(), 3);
+        assert_eq!(scope.len(), 1);
+        {
+            // The value stored in the outer scope is visible in the inner one.
+            let inner = CacheScope::enter();
+            assert_eq!(hoge.name(), "hoge1");
+            assert_eq!(hoge.count.get(), 3);
+            assert!(inner.is_empty());
+        }
+    }
+    assert!(!CacheScope::is_active());
+
+    // The value is dropped with the scope, and computed again in the next one.
+    let _scope = CacheScope::enter();
+    assert_eq!(hoge.name(), "hoge1");
+    assert_eq!(hoge.count.get(), 4);
+    assert_eq!(hoge.name(), "hoge1");
+    assert_eq!(hoge.count.get(), 4);
+}
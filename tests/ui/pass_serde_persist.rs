@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T>
+where
+    T: ToString,
+{
+    pub fn two_times_t(&self) -> String {
+        self.t.to_string().repeat(2)
+    }
+
+    pub fn x_plus_1(&self) -> u64 {
+        self.x + 1
+    }
+}
+
+#[struct_cache_field::add_cache_field(serde_persist)]
+#[derive(Serialize, Deserialize)]
+struct Hoge<T>
+where
+    T: ToString,
+{
+    x: u64,
+    t: T,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        t: "t".to_string(),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_t(), "tt");
+
+    let json = serde_json::to_string(&hoge).unwrap();
+    assert_eq!(
+        json,
+        r#"{"x":1,"t":"t","__cache_fields__":{"two_times_t":"tt","x_plus_1":null}}"#
+    );
+
+    // Restored values are returned without recomputation.
+    let json = r#"{"x":1,"t":"t","__cache_fields__":{"two_times_t":"restored","x_plus_1":null}}"#;
+    let hoge: Hoge<String> = serde_json::from_str(json).unwrap();
+    assert_eq!(hoge.two_times_t(), "restored");
+    assert_eq!(hoge.x_plus_1(), &2);
+}
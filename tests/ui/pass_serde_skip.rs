@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+#[struct_cache_field::impl_cached_method]
+impl<T> Hoge<T>
+where
+    T: ToString,
+{
+    pub fn two_times_t(&self) -> String {
+        self.t.to_string().repeat(2)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+#[derive(Serialize, Deserialize)]
+struct Hoge<T>
+where
+    T: ToString,
+{
+    x: u64,
+    t: T,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field(serde_skip)]
+#[derive(serde::Serialize)]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        t: "t".to_string(),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_t(), "tt");
+    hoge.t = "s".to_string();
+
+    let json = serde_json::to_string(&hoge).unwrap();
+    assert_eq!(json, r#"{"x":1,"t":"s"}"#);
+    let hoge: Hoge<String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(hoge.two_times_t(), "ss");
+
+    let fuga = Fuga {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(fuga.two_times_x(), &2);
+    assert_eq!(serde_json::to_string(&fuga).unwrap(), r#"{"x":1}"#);
+}
@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[struct_cache_field::impl_cached_method]
+impl Texture {
+    #[cached(shared_by = "self.path.clone()", key_type = "PathBuf")]
+    pub fn pixels(&self) -> Vec<u8> {
+        COUNT.fetch_add(1, Ordering::Relaxed);
+        self.path.to_string_lossy().bytes().collect()
+    }
+
+    #[cached(shared_by = "self.id", key_type = "u64", unless = "result.is_empty()")]
+    pub fn label(&self) -> String {
+        COUNT.fetch_add(1, Ordering::Relaxed);
+        self.name.clone()
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Texture {
+    path: PathBuf,
+    id: u64,
+    name: String,
+}
+
+fn texture(path: &str, id: u64, name: &str) -> Texture {
+    Texture {
+        path: PathBuf::from(path),
+        id,
+        name: name.to_owned(),
+        __cache_fields__: Default::default(),
+    }
+}
+
+fn main() {
+    let a = texture("a", 1, "");
+    let b = texture("a", 1, "b");
+    let c = texture("c", 2, "c");
+    assert_eq!(a.pixels(), b"a");
+    assert_eq!(b.pixels(), b"a");
+    assert_eq!(c.pixels(), b"c");
+    assert_eq!(COUNT.load(Ordering::Relaxed), 2);
+
+    // Empty labels are not shared.
+    assert_eq!(a.label(), "");
+    assert_eq!(b.label(), "b");
+    assert_eq!(a.label(), "b");
+    assert_eq!(COUNT.load(Ordering::Relaxed), 4);
+}
@@ -0,0 +1,37 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+thread_local! {
+    static COUNT: Cell<u64> = const { Cell::new(0) };
+}
+
+#[struct_cache_field::cached_ext(name = "DurationExt", side_table)]
+impl Rc<Duration> {
+    /// Returns the square of milliseconds.
+    fn millis_squared(&self) -> u128 {
+        COUNT.with(|count| count.set(count.get() + 1));
+        self.as_millis() * self.as_millis()
+    }
+
+    fn label(&self) -> String {
+        format!("{} ms^2", self.millis_squared())
+    }
+}
+
+fn main() {
+    let d = Rc::new(Duration::from_millis(3));
+    assert_eq!(d.millis_squared(), 9);
+    assert_eq!(d.clone().millis_squared(), 9);
+    assert_eq!(d.label(), "9 ms^2");
+    assert_eq!(COUNT.with(Cell::get), 1);
+
+    // Another instance has its own cache, even if it is allocated at the same address.
+    drop(d);
+    let e = Rc::new(Duration::from_millis(4));
+    assert_eq!(e.millis_squared(), 16);
+    assert_eq!(COUNT.with(Cell::get), 2);
+    for i in 0..100 {
+        assert_eq!(Rc::new(Duration::from_millis(i)).millis_squared(), (i * i) as u128);
+    }
+}
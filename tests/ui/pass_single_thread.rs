@@ -0,0 +1,44 @@
+#[cfg_attr(
+    target_arch = "wasm32",
+    struct_cache_field::impl_cached_method(single_thread)
+)]
+#[cfg_attr(
+    not(target_arch = "wasm32"),
+    struct_cache_field::impl_cached_method(cell_path = "struct_cache_field::SyncCell")
+)]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::impl_cached_method(single_thread)]
+impl Fuga {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+    let fuga = Fuga {
+        x: 2,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(fuga.two_times_x(), &4);
+    assert_eq!(fuga.two_times_x(), &4);
+}
@@ -0,0 +1,57 @@
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn label(self: Rc<Self>) -> String {
+        format!("x = {}", self.x)
+    }
+
+    #[cached(timed)]
+    pub fn two_times_x(self: Rc<Self>) -> u64 {
+        2 * self.x
+    }
+
+    pub fn x_plus_1(&self) -> u64 {
+        self.x + 1
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+#[struct_cache_field::impl_cached_method(cell_path = "std::sync::OnceLock")]
+impl Fuga {
+    pub fn two_times_x(self: Arc<Self>) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    let hoge = Rc::new(Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    });
+    assert_eq!(hoge.clone().label(), "x = 1");
+    assert_eq!(hoge.clone().two_times_x(), 2);
+    assert!(hoge.two_times_x_compute_time().is_some());
+    assert_eq!(hoge.x_plus_1(), &2);
+
+    let fuga = Arc::new(Fuga {
+        x: 1,
+        __cache_fields__: Default::default(),
+    });
+    let handle = {
+        let fuga = fuga.clone();
+        std::thread::spawn(move || fuga.two_times_x())
+    };
+    assert_eq!(handle.join().unwrap(), 2);
+    assert_eq!(fuga.two_times_x(), 2);
+}
@@ -0,0 +1,70 @@
+#[struct_cache_field::impl_cached_method]
+impl<T> Document<T>
+where
+    T: Clone + ToString,
+{
+    pub fn text(&self) -> String {
+        self.items.iter().map(ToString::to_string).collect()
+    }
+
+    pub fn first(&self) -> Option<T> {
+        self.items.first().cloned()
+    }
+
+    #[cfg(any())]
+    pub fn never(&self) -> u64 {
+        0
+    }
+}
+
+#[struct_cache_field::add_cache_field(snapshot)]
+struct Document<T>
+where
+    T: Clone + ToString,
+{
+    items: Vec<T>,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field(snapshot = "HogeState")]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let mut doc = Document {
+        items: vec![1, 2],
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(doc.text(), "12");
+    let items = doc.items.clone();
+    let snapshot: DocumentCacheSnapshot<u32> = doc.cache_snapshot();
+
+    // Edit, then undo.
+    doc.items.push(3);
+    doc.reset_caches();
+    assert_eq!(doc.text(), "123");
+    assert_eq!(*doc.first(), Some(1));
+    doc.items = items;
+    doc.restore_cache(snapshot.clone());
+    assert_eq!(doc.text(), "12");
+    // Empty in the snapshot.
+    assert!(struct_cache_field::CacheCell::get(&doc.__cache_fields__.first).is_none());
+
+    let mut hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    let empty: HogeState = hoge.cache_snapshot();
+    assert_eq!(*hoge.two_times_x(), 2);
+    hoge.restore_cache(empty);
+    assert!(struct_cache_field::CacheCell::get(&hoge.__cache_fields__.two_times_x).is_none());
+}
+
+use struct_cache_field::HasCacheFields;
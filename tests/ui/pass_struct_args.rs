@@ -0,0 +1,25 @@
+mod module1 {
+    #[struct_cache_field::impl_cached_method]
+    impl Hoge {
+        pub fn two_times_x(&self) -> u64 {
+            2 * self.x
+        }
+    }
+
+    #[struct_cache_field::add_cache_field(vis = "pub(crate)", name = "HogeCaches")]
+    pub struct Hoge {
+        pub x: u64,
+    }
+}
+
+fn main() {
+    let mut hoge = module1::Hoge {
+        x: 1,
+        __cache_fields__: module1::HogeCaches::default(),
+    };
+
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.two_times_x(), &2);
+    hoge.x = 2;
+    assert_eq!(hoge.two_times_x(), &2);
+}
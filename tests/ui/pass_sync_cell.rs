@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+#[struct_cache_field::impl_cached_method(cell_path = "struct_cache_field::SyncCell")]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    pub fn x_plus_1(self: Arc<Self>) -> u64 {
+        self.x + 1
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+fn main() {
+    assert_send_sync::<Hoge>();
+
+    let hoge = Arc::new(Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    });
+    let handle = {
+        let hoge = hoge.clone();
+        std::thread::spawn(move || (*hoge.two_times_x(), hoge.x_plus_1()))
+    };
+    assert_eq!(handle.join().unwrap(), (2, 2));
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.x_plus_1(), 2);
+}
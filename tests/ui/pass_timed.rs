@@ -0,0 +1,34 @@
+use std::time::Duration;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(timed)]
+    pub fn slow_two_times_x(&self) -> u64 {
+        std::thread::sleep(Duration::from_millis(10));
+        2 * self.x
+    }
+
+    #[cached(timed)]
+    fn adder(&self) -> impl Fn(u64) -> u64 {
+        let x = self.x;
+        move |y| x + y
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.slow_two_times_x_compute_time(), None);
+    assert_eq!(hoge.slow_two_times_x(), &2);
+    assert!(hoge.slow_two_times_x_compute_time().unwrap() >= Duration::from_millis(10));
+
+    assert_eq!(hoge.adder()(2), 3);
+    assert!(hoge.adder_compute_time().is_some());
+}
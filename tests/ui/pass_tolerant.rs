@@ -0,0 +1,21 @@
+// `tolerant` warns that cached methods are not defined.
+#![allow(deprecated)]
+
+use struct_cache_field::HasCacheFields;
+
+// No `#[impl_cached_method]`, e.g. only the struct is expanded.
+#[struct_cache_field::add_cache_field(new, tolerant)]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge::new(1);
+    assert_eq!(hoge.x, 1);
+    assert!(hoge.cached_methods().is_empty());
+    hoge.reset_caches();
+    let _ = Hoge {
+        x: 2,
+        __cache_fields__: Default::default(),
+    };
+}
@@ -0,0 +1,25 @@
+type HogeU64 = Hoge<u64>;
+
+#[struct_cache_field::impl_cached_method(target = "Hoge")]
+impl HogeU64 {
+    pub fn two_times_t(&self) -> u64 {
+        2 * self.t
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<T> {
+    t: T,
+}
+
+fn main() {
+    let mut hoge: HogeU64 = Hoge {
+        t: 1,
+        __cache_fields__: Default::default(),
+    };
+
+    assert_eq!(hoge.two_times_t(), &2);
+    assert_eq!(hoge.two_times_t(), &2);
+    hoge.t = 2;
+    assert_eq!(hoge.two_times_t(), &2);
+}
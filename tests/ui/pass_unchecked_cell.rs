@@ -0,0 +1,47 @@
+use std::cell::Cell;
+use struct_cache_field::HasCacheFields;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(unchecked_cell)]
+    pub fn two_times_x(&self) -> u64 {
+        self.count.set(self.count.get() + 1);
+        self.x * 2
+    }
+
+    #[cached(unchecked_cell)]
+    pub fn name(&self) -> &str {
+        // Calling itself again during the computation keeps the value stored first.
+        if self.depth.replace(self.depth.get() + 1) == 0 {
+            let _ = self.name();
+        }
+        format!("hoge{}", self.x)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    count: Cell<u64>,
+    depth: Cell<u64>,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        count: Cell::new(0),
+        depth: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(*hoge.two_times_x(), 2);
+    assert_eq!(*hoge.two_times_x(), 2);
+    assert_eq!(hoge.count.get(), 1);
+    assert_eq!(hoge.name(), "hoge1");
+    assert_eq!(hoge.name(), "hoge1");
+    assert_eq!(hoge.depth.get(), 2);
+
+    hoge.reset_caches();
+    hoge.x = 2;
+    assert_eq!(*hoge.two_times_x(), 4);
+    assert_eq!(hoge.count.get(), 2);
+}
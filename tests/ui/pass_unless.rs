@@ -0,0 +1,56 @@
+use std::cell::Cell;
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(capacity = 4, unless = "result.is_empty()")]
+    pub fn search(&self, query: &str) -> Vec<u64> {
+        self.count.set(self.count.get() + 1);
+        self.items
+            .iter()
+            .copied()
+            .filter(|x| x.to_string().contains(query))
+            .collect()
+    }
+
+    #[cached(resettable, unless = "*result == 0")]
+    pub fn total(&self) -> u64 {
+        self.count.set(self.count.get() + 1);
+        self.items.iter().sum()
+    }
+
+    #[cached(recompute_if_changed(self.limit), unless = "*result == 0")]
+    pub fn capped(&self) -> u64 {
+        self.count.set(self.count.get() + 1);
+        self.items.iter().filter(|x| **x <= self.limit).sum()
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    items: Vec<u64>,
+    limit: u64,
+    count: Cell<usize>,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        items: vec![],
+        limit: 10,
+        count: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.search("1"), Vec::<u64>::new());
+    assert_eq!(hoge.total(), 0);
+    assert_eq!(hoge.capped(), 0);
+    assert_eq!(hoge.count.get(), 3);
+    // Empty results are not stored.
+    hoge.items = vec![1, 12, 3];
+    assert_eq!(hoge.search("1"), vec![1, 12]);
+    assert_eq!(hoge.total(), 16);
+    assert_eq!(hoge.capped(), 4);
+    assert_eq!(hoge.count.get(), 6);
+    assert_eq!(hoge.search("1"), vec![1, 12]);
+    assert_eq!(hoge.total(), 16);
+    assert_eq!(hoge.capped(), 4);
+    assert_eq!(hoge.count.get(), 6);
+}
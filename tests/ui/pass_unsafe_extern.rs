@@ -0,0 +1,43 @@
+#[struct_cache_field::impl_cached_method(dyn_trait)]
+impl Hoge {
+    /// # Safety
+    ///
+    /// `self.ptr` must be valid.
+    pub unsafe fn pointee(&self) -> u64 {
+        2 * *self.ptr
+    }
+
+    #[cached(refresh)]
+    pub unsafe fn pointee_plus(&self) -> u64 {
+        *self.ptr + 1
+    }
+
+    pub extern "C" fn answer() -> u64 {
+        42
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    ptr: *const u64,
+}
+
+fn main() {
+    let mut x = 1;
+    let mut hoge = Hoge {
+        ptr: &x,
+        __cache_fields__: Default::default(),
+    };
+    unsafe {
+        assert_eq!(*hoge.pointee(), 2);
+        assert_eq!(*CachedHoge::pointee_plus(&hoge), 2);
+    }
+    x = 2;
+    hoge.ptr = &x;
+    unsafe {
+        assert_eq!(*hoge.pointee(), 2);
+        assert_eq!(*hoge.pointee_plus_refresh(), 3);
+    }
+    let f: extern "C" fn() -> u64 = Hoge::answer;
+    assert_eq!(f(), 42);
+}
@@ -0,0 +1,26 @@
+#![deny(deprecated)]
+
+// Methods keyed by arguments or type parameters cache a value per key, so they don't warn even if
+// they don't use `self`.
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(capacity = 2)]
+    pub fn repeat(&self, n: usize) -> String {
+        "a".repeat(n)
+    }
+
+    pub fn name<T: 'static>(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {}
+
+fn main() {
+    let hoge = Hoge {
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.repeat(3), "aaa");
+    assert_eq!(*hoge.name::<u64>(), "u64");
+}
@@ -0,0 +1,70 @@
+use std::cell::Cell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+struct Opaque(u64);
+
+#[struct_cache_field::impl_cached_method(verify)]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x.get()
+    }
+
+    pub fn label(&self) -> &str {
+        format!("x = {}", self.x.get())
+    }
+
+    pub fn opaque(&self) -> Opaque {
+        Opaque(self.x.get())
+    }
+
+    #[cached(no_verify)]
+    pub fn unchecked(&self) -> u64 {
+        self.x.get()
+    }
+
+    #[cached(capacity = 4)]
+    pub fn x_times(&self, n: u64) -> u64 {
+        self.x.get() * n
+    }
+
+    pub fn counted(&mut self) -> u64 {
+        self.count += 1;
+        self.count
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: Cell<u64>,
+    count: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: Cell::new(1),
+        count: 0,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(*hoge.two_times_x(), 2);
+    assert_eq!(*hoge.two_times_x(), 2);
+    assert_eq!(hoge.label(), "x = 1");
+    assert_eq!(hoge.x_times(3), 3);
+    assert_eq!(hoge.x_times(3), 3);
+    assert_eq!(hoge.opaque().0, 1);
+    // `&mut self` is not computed again.
+    assert_eq!(*hoge.counted(), 1);
+    assert_eq!(*hoge.counted(), 1);
+
+    // Mutation without resetting caches.
+    hoge.x.set(2);
+    let stale = catch_unwind(AssertUnwindSafe(|| *hoge.two_times_x()));
+    assert_eq!(cfg!(debug_assertions), stale.is_err());
+    let stale = catch_unwind(AssertUnwindSafe(|| hoge.x_times(3)));
+    assert_eq!(cfg!(debug_assertions), stale.is_err());
+    // Not `PartialEq`.
+    assert_eq!(hoge.opaque().0, 1);
+    // Opted out.
+    assert_eq!(*hoge.unchecked(), 2);
+    hoge.x.set(3);
+    assert_eq!(*hoge.unchecked(), 2);
+}
@@ -0,0 +1,41 @@
+mod module1 {
+    #[struct_cache_field::impl_cached_method]
+    impl Hoge {
+        pub fn two_times_x(&self) -> u64 {
+            2 * self.x
+        }
+    }
+
+    #[struct_cache_field::add_cache_field]
+    pub struct Hoge {
+        pub x: u64,
+    }
+}
+
+mod module2 {
+    #[struct_cache_field::impl_cached_method]
+    impl Hoge {
+        pub fn two_times_x(&self) -> u64 {
+            2 * self.x
+        }
+    }
+
+    #[struct_cache_field::add_cache_field]
+    pub(crate) struct Hoge {
+        pub(crate) x: u64,
+    }
+}
+
+fn main() {
+    let hoge = module1::Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+
+    let hoge = module2::Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+}
@@ -0,0 +1,50 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+struct Document {
+    text: String,
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(weak)]
+    pub fn document(&self) -> Rc<Document> {
+        self.count.set(self.count.get() + 1);
+        Rc::new(Document {
+            text: format!("x = {}", self.x),
+        })
+    }
+
+    #[cached(weak)]
+    pub fn shared(&self) -> Arc<str> {
+        Arc::from(self.x.to_string())
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    count: Cell<usize>,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        count: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    let document = hoge.document();
+    assert_eq!(document.text, "x = 1");
+    assert!(Rc::ptr_eq(&document, &hoge.document()));
+    assert_eq!(hoge.count.get(), 1);
+
+    // Recreated after dropped.
+    let weak = Rc::downgrade(&document);
+    drop(document);
+    assert!(weak.upgrade().is_none());
+    assert_eq!(hoge.document().text, "x = 1");
+    assert_eq!(hoge.count.get(), 2);
+
+    assert_eq!(&*hoge.shared(), "1");
+}
@@ -0,0 +1,70 @@
+use std::cell::Cell;
+
+#[struct_cache_field::impl_cached_method]
+impl Asset {
+    #[cached(persist = "serde_json")]
+    pub fn words(&self) -> Vec<String> {
+        self.count.set(self.count.get() + 1);
+        self.text.split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[cached(persist = "serde_json", capacity = 4)]
+    pub fn word(&self, i: usize) -> Option<String> {
+        self.count.set(self.count.get() + 1);
+        self.text.split_whitespace().nth(i).map(str::to_owned)
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+#[derive(Hash)]
+struct Asset {
+    text: String,
+    count: Counter,
+}
+
+/// Not a part of the content.
+#[derive(Default)]
+struct Counter(Cell<usize>);
+
+impl std::hash::Hash for Counter {
+    fn hash<H: std::hash::Hasher>(&self, _state: &mut H) {}
+}
+
+impl std::ops::Deref for Counter {
+    type Target = Cell<usize>;
+
+    fn deref(&self) -> &Cell<usize> {
+        &self.0
+    }
+}
+
+fn asset(text: &str) -> Asset {
+    Asset {
+        text: text.to_owned(),
+        count: Counter::default(),
+        __cache_fields__: Default::default(),
+    }
+}
+
+fn main() {
+    let dir = std::env::temp_dir().join(format!("struct_cache_field_{}", std::process::id()));
+    struct_cache_field::set_persist_dir(&dir);
+
+    let a = asset("a b");
+    assert_eq!(a.words(), &["a", "b"]);
+    assert_eq!(a.word(1).as_deref(), Some("b"));
+    assert_eq!(a.count.get(), 2);
+
+    // Another instance of the same content finds the values on disk.
+    let b = asset("a b");
+    assert_eq!(b.words(), &["a", "b"]);
+    assert_eq!(b.word(1).as_deref(), Some("b"));
+    assert_eq!(b.word(0).as_deref(), Some("a"));
+    assert_eq!(b.count.get(), 1);
+
+    let c = asset("c");
+    assert_eq!(c.words(), &["c"]);
+    assert_eq!(c.count.get(), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
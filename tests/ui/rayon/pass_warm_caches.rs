@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use struct_cache_field::WarmCaches;
+
+#[struct_cache_field::impl_cached_method(cell_path = "std::sync::OnceLock", warm_caches)]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        2 * self.x
+    }
+
+    pub fn x_plus_1(&self) -> u64 {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        self.x + 1
+    }
+
+    pub fn sum(&self) -> u64 {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        self.two_times_x() + self.x_plus_1()
+    }
+
+    pub fn x_times(&mut self) -> u64 {
+        self.x * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    count: AtomicUsize,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        count: AtomicUsize::new(0),
+        __cache_fields__: Default::default(),
+    };
+    hoge.warm_caches_par();
+    assert_eq!(hoge.count.load(Ordering::SeqCst), 3);
+    assert_eq!(hoge.sum(), &4);
+    assert_eq!(hoge.count.load(Ordering::SeqCst), 3);
+
+    let hoges = (0..100)
+        .map(|x| Hoge {
+            x,
+            count: AtomicUsize::new(0),
+            __cache_fields__: Default::default(),
+        })
+        .collect::<Vec<_>>();
+    struct_cache_field::warm_caches_par(&hoges);
+    assert!(hoges
+        .iter()
+        .all(|hoge| hoge.count.load(Ordering::SeqCst) == 3));
+}
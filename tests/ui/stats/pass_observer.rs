@@ -0,0 +1,69 @@
+use std::sync::Mutex;
+use struct_cache_field::{CacheKey, CacheObserver, HasCacheFields};
+
+static EVENTS: Mutex<Vec<(&'static str, CacheKey)>> = Mutex::new(vec![]);
+
+struct Recorder;
+
+impl CacheObserver for Recorder {
+    fn on_hit(&self, key: CacheKey) {
+        EVENTS.lock().unwrap().push(("hit", key));
+    }
+
+    fn on_miss(&self, key: CacheKey) {
+        EVENTS.lock().unwrap().push(("miss", key));
+    }
+
+    fn on_reset(&self, key: CacheKey) {
+        EVENTS.lock().unwrap().push(("reset", key));
+    }
+}
+
+#[struct_cache_field::impl_cached_method(stats)]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    #[cached(timed)]
+    pub fn x_plus_1(&self) -> u64 {
+        self.x + 1
+    }
+}
+
+#[struct_cache_field::add_cache_field(snapshot)]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    struct_cache_field::set_observer(Recorder).unwrap();
+    assert!(struct_cache_field::set_observer(Recorder).is_err());
+
+    let mut hoge = Hoge {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.x_plus_1(), &2);
+    let snapshot = hoge.cache_snapshot();
+    hoge.reset_caches();
+    // Restoring resets caches as well.
+    hoge.restore_cache(snapshot);
+
+    let ty = std::any::type_name::<Hoge>();
+    let key = |method| CacheKey { ty, method };
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        vec![
+            ("miss", key("two_times_x")),
+            ("hit", key("two_times_x")),
+            ("miss", key("x_plus_1")),
+            ("reset", key("two_times_x")),
+            ("reset", key("x_plus_1")),
+            ("reset", key("two_times_x")),
+            ("reset", key("x_plus_1")),
+        ]
+    );
+}
@@ -0,0 +1,67 @@
+use struct_cache_field::{CacheStat, HasCacheFields};
+
+#[struct_cache_field::impl_cached_method(stats)]
+impl<T> Hoge<T>
+where
+    T: ToString,
+{
+    pub fn two_times_t(&self) -> String {
+        self.t.to_string().repeat(2)
+    }
+
+    pub fn x_plus_1(&self) -> u64 {
+        self.x + 1
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge<T>
+where
+    T: ToString,
+{
+    x: u64,
+    t: T,
+}
+
+// Caches of impls without `stats` are not counted even with the feature.
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    pub fn x_plus_1(&self) -> u64 {
+        self.x + 1
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        t: "t",
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_t(), "tt");
+    assert_eq!(hoge.two_times_t(), "tt");
+    assert_eq!(hoge.two_times_t(), "tt");
+    hoge.reset_caches();
+    assert_eq!(hoge.two_times_t(), "tt");
+
+    let stats = hoge.cache_stats();
+    assert_eq!(
+        stats.get("two_times_t"),
+        Some(CacheStat { hits: 2, misses: 2 })
+    );
+    assert_eq!(stats.get("x_plus_1"), Some(CacheStat::default()));
+    assert_eq!(stats.get("two_times_t").unwrap().hit_ratio(), Some(0.5));
+    assert_eq!(stats.get("x_plus_1").unwrap().hit_ratio(), None);
+    assert_eq!(stats.total(), CacheStat { hits: 2, misses: 2 });
+
+    let fuga = Fuga {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(*fuga.x_plus_1(), 2);
+    assert!(fuga.cache_stats().entries().is_empty());
+}
@@ -0,0 +1,96 @@
+use std::cell::Cell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use struct_cache_field::HasCacheFields;
+
+#[struct_cache_field::impl_cached_method(switch)]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        self.count.set(self.count.get() + 1);
+        2 * self.x
+    }
+
+    pub fn name<T: 'static>(&self) -> &str {
+        format!("{}: {}", std::any::type_name::<T>(), self.x)
+    }
+
+    #[cached(capacity = 4)]
+    pub fn add(&self, y: u64) -> u64 {
+        self.count.set(self.count.get() + 1);
+        self.x + y
+    }
+
+    #[cached(timed)]
+    pub fn x_plus_1(&mut self) -> u64 {
+        self.x + 1
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+    count: Cell<usize>,
+}
+
+// Caches of impls without `switch` can't be disabled even with the feature.
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    pub fn x_plus_1(&self) -> u64 {
+        self.x + 1
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    let mut hoge = Hoge {
+        x: 1,
+        count: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x(), &2);
+    assert_eq!(hoge.name::<u8>(), "u8: 1");
+    assert_eq!(hoge.add(1), 2);
+    assert_eq!(hoge.x_plus_1(), &2);
+
+    // Disabling clears caches, and values are computed on every call.
+    hoge.set_caching_enabled(false);
+    hoge.x = 2;
+    hoge.count.set(0);
+    let a = hoge.two_times_x();
+    let b = hoge.two_times_x();
+    assert_eq!((a, b), (&4, &4));
+    assert_eq!(hoge.count.get(), 2);
+    assert_eq!(hoge.name::<u8>(), "u8: 2");
+    assert_eq!(hoge.add(1), 3);
+    assert_eq!(hoge.add(1), 3);
+    assert_eq!(hoge.count.get(), 4);
+    assert_eq!(hoge.x_plus_1(), &3);
+    hoge.x = 3;
+    assert_eq!(hoge.x_plus_1(), &4);
+    assert_eq!(hoge.x_plus_1_compute_time(), None);
+    assert_eq!(hoge.cache_occupancy().num_filled(), 0);
+
+    // Enabled again.
+    hoge.set_caching_enabled(true);
+    hoge.count.set(0);
+    assert_eq!(hoge.two_times_x(), &6);
+    assert_eq!(hoge.two_times_x(), &6);
+    assert_eq!(hoge.add(1), 4);
+    assert_eq!(hoge.add(1), 4);
+    assert_eq!(hoge.count.get(), 2);
+    assert_eq!(hoge.name::<u8>(), "u8: 3");
+    hoge.x = 4;
+    assert_eq!(hoge.name::<u8>(), "u8: 3");
+
+    let mut fuga = Fuga {
+        x: 1,
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(*fuga.x_plus_1(), 2);
+    fuga.set_caching_enabled(true);
+    assert_eq!(fuga.cache_occupancy().num_filled(), 0);
+    assert!(catch_unwind(AssertUnwindSafe(|| fuga.set_caching_enabled(false))).is_err());
+}
@@ -0,0 +1,87 @@
+use std::cell::Cell;
+
+#[struct_cache_field::impl_cached_method(test_util)]
+impl Hoge {
+    pub fn two_times_x(&self) -> u64 {
+        self.expensive.set(self.expensive.get() + 1);
+        2 * self.x
+    }
+
+    pub fn four_times_x(&self) -> u64 {
+        2 * self.two_times_x()
+    }
+
+    #[allow(deprecated)]
+    pub fn label(&self) -> &str {
+        "label".to_owned()
+    }
+
+    #[cached(timed)]
+    pub fn slow(&self) -> u64 {
+        self.x
+    }
+
+    #[cached(capacity = 4)]
+    pub fn x_times(&self, n: u64) -> u64 {
+        self.x * n
+    }
+}
+
+#[struct_cache_field::add_cache_field(clone = "preserve")]
+#[derive(Clone)]
+struct Hoge {
+    x: u64,
+    expensive: Cell<usize>,
+}
+
+// Without `test_util`, the cache struct has no counts.
+#[struct_cache_field::impl_cached_method]
+impl Fuga {
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Fuga {
+    x: u64,
+}
+
+fn main() {
+    let hoge = Hoge {
+        x: 1,
+        expensive: Cell::new(0),
+        __cache_fields__: Default::default(),
+    };
+    assert_eq!(hoge.two_times_x_init_count(), 0);
+    assert_eq!(*hoge.four_times_x(), 4);
+    assert_eq!(*hoge.four_times_x(), 4);
+    assert_eq!(hoge.two_times_x_init_count(), 1);
+    assert_eq!(hoge.four_times_x_init_count(), 1);
+    assert_eq!(hoge.expensive.get(), 1);
+
+    hoge.x_times(2);
+    hoge.x_times(2);
+    hoge.x_times(3);
+    assert_eq!(hoge.x_times_init_count(), 2);
+
+    // Inject fake values, which skip the computation.
+    hoge.inject_label("fake".to_owned());
+    assert_eq!(hoge.label(), "fake");
+    assert_eq!(hoge.label_init_count(), 0);
+    hoge.inject_slow(42);
+    assert_eq!(*hoge.slow(), 42);
+    assert_eq!(hoge.slow_init_count(), 0);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        hoge.inject_two_times_x(10);
+    }));
+    assert!(result.is_err());
+
+    // Counts are kept by clones that keep values.
+    assert_eq!(hoge.clone().two_times_x_init_count(), 1);
+
+    assert_eq!(
+        std::mem::size_of::<Fuga>(),
+        std::mem::size_of::<(u64, std::cell::OnceCell<u64>)>()
+    );
+}
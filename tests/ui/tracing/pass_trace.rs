@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// Counts spans and events.
+#[derive(Default)]
+struct Counter {
+    spans: AtomicUsize,
+    events: AtomicUsize,
+}
+
+struct CountingSubscriber(Arc<Counter>);
+
+impl Subscriber for CountingSubscriber {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        assert_eq!(span.metadata().name(), "cache_miss");
+        let id = self.0.spans.fetch_add(1, Ordering::SeqCst);
+        Id::from_u64(id as u64 + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {
+        self.0.events.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[struct_cache_field::impl_cached_method]
+impl Hoge {
+    #[cached(trace)]
+    pub fn two_times_x(&self) -> u64 {
+        2 * self.x
+    }
+
+    #[cached(trace, timed)]
+    pub fn x_plus_1(&self) -> u64 {
+        self.x + 1
+    }
+}
+
+#[struct_cache_field::add_cache_field]
+struct Hoge {
+    x: u64,
+}
+
+fn main() {
+    let counter = Arc::new(Counter::default());
+    let subscriber = CountingSubscriber(counter.clone());
+    tracing::subscriber::with_default(subscriber, || {
+        let hoge = Hoge {
+            x: 1,
+            __cache_fields__: Default::default(),
+        };
+        assert_eq!(hoge.two_times_x(), &2);
+        assert_eq!(hoge.two_times_x(), &2);
+        assert_eq!(hoge.x_plus_1(), &2);
+        assert!(hoge.x_plus_1_compute_time().is_some());
+    });
+    assert_eq!(counter.spans.load(Ordering::SeqCst), 2);
+    assert_eq!(counter.events.load(Ordering::SeqCst), 2);
+}